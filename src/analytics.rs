@@ -0,0 +1,153 @@
+//! Analytics snippets that are added to the `<head>` of every HTML page, so that the readers of
+//! hosted documentation can be counted.
+//!
+//! A snippet is rendered by the `analytics` variable of every built-in page template, after the
+//! stylesheets and scripts of the page. Custom templates should render it with
+//! `{{{ analytics }}}` in their `<head>` to keep it.
+
+use std::str::FromStr;
+
+use handlebars::html_escape;
+
+use errors::*;
+
+/// The script of Umami Cloud, which is used unless the URL of a self-hosted one is given.
+const UMAMI_SCRIPT: &str = "https://cloud.umami.is/script.js";
+
+/// An analytics service. The snippet of any other service can be added from a file with
+/// `RenderOptions::analytics_snippet`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Analytics {
+    /// [Plausible](https://plausible.io), which counts page views for a domain without cookies.
+    Plausible {
+        /// The domain of the site, as it is registered with Plausible.
+        domain: String,
+    },
+
+    /// [Umami](https://umami.is), which counts page views for a website without cookies.
+    Umami {
+        /// The ID of the website in Umami.
+        website_id: String,
+
+        /// The URL of the tracking script, which is that of Umami Cloud unless it is self-hosted.
+        script: String,
+    },
+
+    /// Google Analytics, through the Google tag.
+    GoogleAnalytics {
+        /// The measurement ID of the property, such as `G-XXXXXXX`.
+        measurement_id: String,
+    },
+}
+
+impl Analytics {
+    /// Returns the HTML that is added to the `<head>` of every page.
+    pub fn snippet(&self) -> String {
+        match *self {
+            Analytics::Plausible { ref domain } => format!(
+                "<script defer data-domain=\"{}\" src=\"https://plausible.io/js/script.js\">\
+                 </script>",
+                html_escape(domain)
+            ),
+            Analytics::Umami { ref website_id, ref script } => format!(
+                "<script defer src=\"{}\" data-website-id=\"{}\"></script>",
+                html_escape(script),
+                html_escape(website_id)
+            ),
+            // The tag is configured by `settings.js`, since an inline script would be blocked by
+            // a Content Security Policy.
+            Analytics::GoogleAnalytics { ref measurement_id } => format!(
+                "<script async src=\"https://www.googletagmanager.com/gtag/js?id={0}\" \
+                 data-gtag=\"{0}\"></script>",
+                html_escape(measurement_id)
+            ),
+        }
+    }
+
+    /// Returns the origins that the service loads its script from and sends page views to, which
+    /// a Content Security Policy has to allow.
+    pub fn origins(&self) -> Vec<String> {
+        match *self {
+            Analytics::Plausible { .. } => vec![String::from("https://plausible.io")],
+            Analytics::Umami { ref script, .. } => {
+                let host = script.find("://").map_or(0, |scheme| scheme + 3);
+                let end = script[host..].find('/').map_or(script.len(), |path| host + path);
+                vec![String::from(&script[..end])]
+            }
+            Analytics::GoogleAnalytics { .. } => vec![
+                String::from("https://www.googletagmanager.com"),
+                String::from("https://*.google-analytics.com"),
+                String::from("https://*.analytics.google.com"),
+            ],
+        }
+    }
+}
+
+impl FromStr for Analytics {
+    type Err = Error;
+
+    /// Parses a service and its ID, such as `plausible:docs.example.com`, `umami:ID`,
+    /// `umami:ID@https://analytics.example.com/script.js` or `google:G-XXXXXXX`.
+    fn from_str(analytics: &str) -> Result<Analytics> {
+        let (service, id) = match analytics.find(':') {
+            Some(colon) => (&analytics[..colon], &analytics[colon + 1..]),
+            None => bail!("analytics `{}` must be of the form `service:id`", analytics),
+        };
+        if id.is_empty() {
+            bail!("analytics `{}` has no ID", analytics);
+        }
+
+        Ok(match service {
+            "plausible" => Analytics::Plausible { domain: String::from(id) },
+            "umami" => {
+                let (website_id, script) = match id.find('@') {
+                    Some(at) => (&id[..at], &id[at + 1..]),
+                    None => (id, UMAMI_SCRIPT),
+                };
+                Analytics::Umami {
+                    website_id: String::from(website_id),
+                    script: String::from(script),
+                }
+            }
+            "google" => Analytics::GoogleAnalytics { measurement_id: String::from(id) },
+            _ => bail!(
+                "unknown analytics service `{}`, expected `plausible`, `umami` or `google`",
+                service
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Analytics;
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            "plausible:docs.example.com".parse::<Analytics>().unwrap(),
+            Analytics::Plausible { domain: String::from("docs.example.com") }
+        );
+        assert_eq!(
+            "umami:1234@https://stats.example.com/umami.js".parse::<Analytics>().unwrap(),
+            Analytics::Umami {
+                website_id: String::from("1234"),
+                script: String::from("https://stats.example.com/umami.js"),
+            }
+        );
+        assert!("umami:1234".parse::<Analytics>().unwrap().snippet().contains("cloud.umami.is"));
+        let umami = "umami:1234@https://stats.example.com/umami.js".parse::<Analytics>();
+        assert_eq!(umami.unwrap().origins(), ["https://stats.example.com"]);
+        assert!("google:".parse::<Analytics>().is_err());
+        assert!("matomo:1".parse::<Analytics>().is_err());
+    }
+
+    #[test]
+    fn snippet() {
+        let analytics = Analytics::GoogleAnalytics { measurement_id: String::from("G-ABC") };
+        let snippet = analytics.snippet();
+        assert!(snippet.contains("gtag/js?id=G-ABC"));
+        assert!(snippet.contains(r#"data-gtag="G-ABC""#));
+        assert!(!snippet.contains("<script>"));
+    }
+}
@@ -0,0 +1,144 @@
+//! Packaging of a rendered documentation tree into a single archive.
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use tar;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use files;
+
+/// The format of a documentation archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// A gzip-compressed tarball (`.tar.gz`).
+    TarGz,
+
+    /// A zip archive (`.zip`).
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Guesses the archive format from the extension of a path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ArchiveFormat> {
+        let name = path.as_ref().file_name()?.to_str()?;
+
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Packages every file under the doc root into an archive at `output`.
+///
+/// Paths within the archive are relative to the doc root. The output path should not be inside the
+/// doc root.
+pub fn package<P, Q>(doc_root: P, output: Q, format: ArchiveFormat) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let doc_root = doc_root.as_ref();
+    let files = files(doc_root)?;
+    let archive = BufWriter::new(File::create(output.as_ref())?);
+
+    info!("packaging `{}` as `{}`", doc_root.display(), output.as_ref().display());
+
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut tar = tar::Builder::new(GzEncoder::new(archive, Compression::default()));
+            for file in &files {
+                tar.append_path_with_name(doc_root.join(file), file)?;
+            }
+            tar.into_inner()?.finish()?;
+        }
+        ArchiveFormat::Zip => {
+            let mut zip = ZipWriter::new(archive);
+            for file in &files {
+                zip.start_file(archive_name(file), FileOptions::default())?;
+                io::copy(&mut File::open(doc_root.join(file))?, &mut zip)?;
+            }
+            zip.finish()?.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the name of a file within a zip archive, which always uses forward slashes.
+fn archive_name(path: &Path) -> String {
+    path.iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::prelude::*;
+
+    use flate2::read::GzDecoder;
+    use tar;
+    use zip::ZipArchive;
+
+    use super::ArchiveFormat;
+
+    #[test]
+    fn from_path() {
+        assert_eq!(ArchiveFormat::from_path("docs.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(ArchiveFormat::from_path("out/docs.zip"), Some(ArchiveFormat::Zip));
+        assert_eq!(ArchiveFormat::from_path("docs.rar"), None);
+    }
+
+    #[test]
+    fn package() {
+        let root = env::temp_dir().join("rustdoc-static-package");
+        let _ = fs::remove_dir_all(&root);
+        let doc_root = root.join("doc");
+        fs::create_dir_all(doc_root.join("demo")).unwrap();
+        fs::write(doc_root.join("demo/index.html"), "<html>").unwrap();
+        fs::write(doc_root.join("styles.css"), "body {}").unwrap();
+
+        let tarball = root.join("docs.tar.gz");
+        super::package(&doc_root, &tarball, ArchiveFormat::TarGz).unwrap();
+        let mut tar = tar::Archive::new(GzDecoder::new(File::open(&tarball).unwrap()));
+        let entries = tar
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let mut contents = String::new();
+                entry.read_to_string(&mut contents).unwrap();
+                (entry.path().unwrap().to_string_lossy().into_owned(), contents)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            [
+                (String::from("demo/index.html"), String::from("<html>")),
+                (String::from("styles.css"), String::from("body {}")),
+            ]
+        );
+
+        let zip = root.join("docs.zip");
+        super::package(&doc_root, &zip, ArchiveFormat::Zip).unwrap();
+        let mut zip = ZipArchive::new(File::open(&zip).unwrap()).unwrap();
+        assert_eq!(zip.len(), 2);
+        let mut contents = String::new();
+        zip.by_name("demo/index.html").unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "<html>");
+        assert!(zip.by_name("styles.css").is_ok());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,263 @@
+//! Renders the documentation as an EPUB book for offline reading.
+//!
+//! The crate and each of its modules become chapters of the book, and the items defined in a module
+//! become sections of its chapter. Documentation is rendered with the same Markdown pipeline as the
+//! HTML output.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use handlebars::html_escape;
+use jsonapi::api::{JsonApiDocument, Resource};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use index::Index;
+use {children, crate_metadata, markdown, resources};
+
+/// Given a JSON-API document generated by the rustdoc backend, generates an EPUB book named after
+/// the crate at the doc root.
+pub fn render<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> io::Result<()> {
+    let index = Index::new(document);
+    let doc_root = root.as_ref();
+    fs::create_dir_all(doc_root)?;
+
+    let krate = crate_metadata(document);
+    let title = krate["name"].as_str().unwrap_or("docs").to_owned();
+    let path = doc_root.join(format!("{}.epub", title));
+    info!("rendering crate as `{}`", path.display());
+
+    // Chapters are rendered one at a time as they are written to the archive, rather than all
+    // being held in memory at once.
+    let modules = resources(document)
+        .filter(|resource| resource._type == "crate" || resource._type == "module")
+        .collect::<Vec<_>>();
+
+    let mut zip = ZipWriter::new(BufWriter::new(File::create(&path)?));
+
+    // The mimetype must be the first file in the archive, and must not be compressed.
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+    zip.start_file("mimetype", stored)?;
+    zip.write_all(b"application/epub+zip")?;
+
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file("META-INF/container.xml", deflated)?;
+    zip.write_all(CONTAINER.as_bytes())?;
+
+    zip.start_file("OEBPS/content.opf", deflated)?;
+    zip.write_all(package(&title, krate["version"].as_str(), &modules).as_bytes())?;
+
+    zip.start_file("OEBPS/nav.xhtml", deflated)?;
+    zip.write_all(nav(&title, &modules).as_bytes())?;
+
+    for module in modules {
+        zip.start_file(format!("OEBPS/{}", chapter_file(module)), deflated)?;
+        zip.write_all(render_chapter(&index, module).as_bytes())?;
+    }
+
+    zip.finish()?.flush()
+}
+
+/// Points the reading system at the package document.
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// Returns the name of the chapter file for a module.
+fn chapter_file(module: &Resource) -> String {
+    format!("{}.xhtml", module.id.replace("::", "-"))
+}
+
+/// Renders the package document, which lists every file in the book and their reading order.
+fn package(title: &str, version: Option<&str>, modules: &[&Resource]) -> String {
+    let mut manifest = String::new();
+    let mut spine = String::new();
+
+    for (i, module) in modules.iter().enumerate() {
+        manifest.push_str(&format!(
+            "    <item id=\"chapter{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+            i,
+            html_escape(&chapter_file(module))
+        ));
+        spine.push_str(&format!("    <itemref idref=\"chapter{}\"/>\n", i));
+    }
+
+    let title = match version {
+        Some(version) => format!("{} {}", title, version),
+        None => title.to_owned(),
+    };
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">urn:rustdoc-static:{id}</dc:identifier>
+    <dc:title>{title}</dc:title>
+    <dc:language>en</dc:language>
+    <meta property="dcterms:modified">1970-01-01T00:00:00Z</meta>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+{manifest}  </manifest>
+  <spine>
+{spine}  </spine>
+</package>
+"#,
+        id = html_escape(&title.replace(' ', "-")),
+        title = html_escape(&title),
+        manifest = manifest,
+        spine = spine
+    )
+}
+
+/// Renders the navigation document, which serves as the book's table of contents.
+fn nav(title: &str, modules: &[&Resource]) -> String {
+    let entries = modules
+        .iter()
+        .map(|module| {
+            format!(
+                "      <li><a href=\"{}\">{}</a></li>\n",
+                html_escape(&chapter_file(module)),
+                html_escape(&module.id)
+            )
+        })
+        .collect::<String>();
+
+    xhtml(
+        title,
+        &format!("  <nav epub:type=\"toc\">\n    <ol>\n{}    </ol>\n  </nav>\n", entries),
+    )
+}
+
+/// Renders the chapter for a module, with a section for each of its items.
+fn render_chapter(document: &Index, module: &Resource) -> String {
+    let mut body = format!("  <h1>{} {}</h1>\n", module._type, html_escape(&module.id));
+    body.push_str(&docs(module));
+
+    let items = children(document, module)
+        .into_iter()
+        .filter(|item| item._type != "module" && document.path(item).is_some());
+
+    for item in items {
+        body.push_str(&format!(
+            "  <section id=\"{}\">\n  <h2>{} {}</h2>\n",
+            html_escape(&item.id.replace("::", "-")),
+            item._type,
+            html_escape(document.name(&item.id))
+        ));
+        body.push_str(&docs(item));
+
+        for member in children(document, item) {
+            body.push_str(&format!(
+                "  <h3>{}</h3>\n",
+                html_escape(document.name(&member.id))
+            ));
+            body.push_str(&docs(member));
+        }
+
+        body.push_str("  </section>\n");
+    }
+
+    xhtml(&module.id, &body)
+}
+
+/// Renders the documentation of a resource as XHTML.
+fn docs(resource: &Resource) -> String {
+    resource
+        .attributes
+        .get("docs")
+        .and_then(|docs| docs.as_str())
+        .map(markdown::render_plain)
+        .unwrap_or_default()
+}
+
+/// Wraps a body in an XHTML document.
+fn xhtml(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<head>
+  <meta charset="utf-8"/>
+  <title>{}</title>
+</head>
+<body>
+{}</body>
+</html>
+"#,
+        html_escape(title),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::prelude::*;
+
+    use jsonapi::api::JsonApiDocument;
+    use zip::{CompressionMethod, ZipArchive};
+
+    #[test]
+    fn render() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": {},
+                    "relationships": {
+                        "modules": {
+                            "data": [
+                                { "type": "module", "id": "test_crate::a" },
+                                { "type": "module", "id": "test_crate::b" }
+                            ]
+                        }
+                    }
+                },
+                "included": [
+                    { "type": "module", "id": "test_crate::a", "attributes": {} },
+                    { "type": "module", "id": "test_crate::b", "attributes": {} },
+                    { "type": "struct", "id": "test_crate::a::Foo", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-epub");
+        let _ = fs::remove_dir_all(&root);
+        super::render(&document, &root).unwrap();
+        let mut epub = ZipArchive::new(File::open(root.join("test_crate.epub")).unwrap()).unwrap();
+
+        {
+            let mut mimetype = epub.by_index(0).unwrap();
+            assert_eq!(mimetype.name(), "mimetype");
+            assert_eq!(mimetype.compression(), CompressionMethod::Stored);
+            let mut contents = String::new();
+            mimetype.read_to_string(&mut contents).unwrap();
+            assert_eq!(contents, "application/epub+zip");
+        }
+
+        let mut package = String::new();
+        epub.by_name("OEBPS/content.opf").unwrap().read_to_string(&mut package).unwrap();
+        assert!(package.contains(r#"<itemref idref="chapter2"/>"#));
+
+        let mut chapters = epub
+            .file_names()
+            .filter(|name| name.ends_with(".xhtml") && *name != "OEBPS/nav.xhtml")
+            .collect::<Vec<_>>();
+        chapters.sort();
+        assert_eq!(
+            chapters,
+            ["OEBPS/test_crate-a.xhtml", "OEBPS/test_crate-b.xhtml", "OEBPS/test_crate.xhtml"]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,40 @@
+//! Renders the processed context of every item as JSON files.
+//!
+//! Each file contains exactly the data that the HTML templates receive, after links have been
+//! resolved and Markdown has been rendered, so other frontends can consume it directly.
+
+use std::path::Path;
+
+use jsonapi::api::JsonApiDocument;
+use serde_json;
+
+use errors::*;
+use index::Index;
+use output::{Filesystem, OutputBackend};
+use {generate_context, path_for_resource, resources};
+
+/// Given a JSON-API document generated by the rustdoc backend, generates a tree of JSON files at
+/// the doc root.
+pub fn render<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> Result<()> {
+    render_to(document, &Filesystem::new(root.as_ref()))
+}
+
+/// Generates the JSON files of a document into a backend, with paths relative to the doc root.
+pub fn render_to(document: &JsonApiDocument, backend: &dyn OutputBackend) -> Result<()> {
+    let index = Index::parse(document)?;
+
+    for resource in resources(document) {
+        let path = match path_for_resource(resource) {
+            Some(path) => path.with_extension("json"),
+            None => continue,
+        };
+
+        info!("rendering `{}` as `{}`", resource.id, path.display());
+
+        let context = generate_context(Path::new(""), &index, resource);
+        let json = serde_json::to_string_pretty(&context).unwrap();
+        backend.write(&path, json.as_bytes())?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+//! Renders the documentation as a single plain-text Markdown document, in the style of `llms.txt`.
+//!
+//! The document contains the signature and documentation of every item, which makes it suitable
+//! for feeding the crate's documentation to AI tooling and embedding pipelines.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io;
+use std::path::Path;
+
+use jsonapi::api::{JsonApiDocument, Resource};
+
+use {crate_metadata, resources};
+
+/// Given a JSON-API document generated by the rustdoc backend, generates `llms.txt` at the doc
+/// root.
+pub fn render<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> io::Result<()> {
+    let doc_root = root.as_ref();
+    fs::create_dir_all(doc_root)?;
+
+    let path = doc_root.join("llms.txt");
+    info!("rendering crate as `{}`", path.display());
+
+    File::create(&path)?.write_all(render_document(document).as_bytes())
+}
+
+/// Renders the entire document as text.
+fn render_document(document: &JsonApiDocument) -> String {
+    let krate = crate_metadata(document);
+    let mut text = format!("# {}", krate["name"].as_str().unwrap_or_default());
+
+    if let Some(version) = krate["version"].as_str() {
+        text.push_str(&format!(" {}", version));
+    }
+    text.push('\n');
+
+    if let Some(description) = krate["description"].as_str() {
+        text.push_str(&format!("\n> {}\n", description));
+    }
+
+    for resource in resources(document) {
+        text.push('\n');
+        text.push_str(&render_resource(resource));
+    }
+
+    text
+}
+
+/// Renders the signature and documentation of a single item.
+fn render_resource(resource: &Resource) -> String {
+    let mut text = format!("## {} `{}`\n", resource._type, resource.id);
+
+    let attribute = |name| resource.attributes.get(name).and_then(|value| value.as_str());
+
+    if let Some(signature) = attribute("signature") {
+        text.push_str(&format!("\n```rust\n{}\n```\n", signature.trim()));
+    }
+
+    if let Some(docs) = attribute("docs").map(str::trim).filter(|docs| !docs.is_empty()) {
+        text.push('\n');
+        text.push_str(docs);
+        text.push('\n');
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonapi::api::Resource;
+
+    #[test]
+    fn render_resource() {
+        let function = Resource::from_str(
+            r#"{
+                "type": "function",
+                "id": "test_crate::f",
+                "attributes": { "docs": "Does things.\n", "signature": "pub fn f()" }
+            }"#,
+        ).unwrap();
+
+        assert_eq!(
+            super::render_resource(&function),
+            "## function `test_crate::f`\n\n```rust\npub fn f()\n```\n\nDoes things.\n"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Renders documentation as `man` pages in section 3, one per item.
+//!
+//! Pages are written to `man3/<path>.3` under the doc root, where `<path>` is the full path of the
+//! item, such as `mycrate::module::Item`.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+
+use jsonapi::api::{IdentifierData, JsonApiDocument, Resource};
+use pulldown_cmark::{Event, Parser, Tag};
+
+use errors::*;
+use index::Index;
+use {crate_metadata_for_resource, path_for_resource, resource_by_id, resources,
+     summary_for_resource};
+
+/// Given a JSON-API document generated by the rustdoc backend, generates a `man3` directory of
+/// manual pages at the doc root.
+pub fn render<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> Result<()> {
+    let index = Index::parse(document)?;
+    let man_dir = root.as_ref().join("man3");
+    fs::create_dir_all(&man_dir)?;
+
+    for resource in resources(document) {
+        if path_for_resource(resource).is_none() {
+            continue;
+        }
+
+        let path = man_dir.join(format!("{}.3", resource.id));
+        info!("rendering `{}` as `{}`", resource.id, path.display());
+
+        let page = render_resource(&index, resource);
+        File::create(&path)?.write_all(page.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Renders the manual page for a single resource.
+fn render_resource(document: &Index, resource: &Resource) -> String {
+    let krate = crate_metadata_for_resource(document, resource);
+    let source = match (krate["name"].as_str(), krate["version"].as_str()) {
+        (Some(name), Some(version)) => format!("{} {}", name, version),
+        (Some(name), None) => name.to_owned(),
+        _ => String::new(),
+    };
+
+    let mut page = format!(
+        ".TH \"{}\" \"3\" \"\" \"{}\" \"{}\"\n",
+        escape(&resource.id.to_uppercase()),
+        escape(&source),
+        escape(krate["name"].as_str().unwrap_or_default()),
+    );
+
+    page.push_str(".SH NAME\n");
+    page.push_str(&escape_line(&resource.id));
+    if let Some(summary) = summary_for_resource(document, resource) {
+        page.push_str(&format!(" \\- {}", escape(&summary)));
+    }
+    page.push('\n');
+
+    page.push_str(&format!(".SH SYNOPSIS\n{} \\fB{}\\fR\n", resource._type, escape(&resource.id)));
+
+    if let Some(docs) = resource.attributes.get("docs").and_then(|docs| docs.as_str()) {
+        page.push_str(".SH DESCRIPTION\n");
+        page.push_str(&markdown_to_roff(docs));
+    }
+
+    let mut relationships = resource
+        .relationships
+        .iter()
+        .flat_map(|relationships| relationships.iter())
+        .collect::<Vec<_>>();
+    relationships.sort_by_key(|&(key, _)| key);
+
+    for (key, relationship) in relationships {
+        let children = match relationship.data {
+            IdentifierData::Single(ref child) => vec![child],
+            IdentifierData::Multiple(ref children) => children.iter().collect(),
+            IdentifierData::None => vec![],
+        };
+
+        page.push_str(&format!(".SH {}\n", escape(&key.to_uppercase())));
+
+        for child in children.iter().filter_map(|child| resource_by_id(document, &child.id)) {
+            let name = document.name(&child.id);
+            page.push_str(&format!(".TP\n.B {}\n", escape(name)));
+            page.push_str(&escape_line(&summary_for_resource(document, child).unwrap_or_default()));
+            page.push('\n');
+        }
+    }
+
+    page
+}
+
+/// Converts Markdown to roff markup.
+fn markdown_to_roff(markdown: &str) -> String {
+    let mut roff = String::new();
+    let mut in_code_block = false;
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Paragraph) => roff.push_str(".PP\n"),
+            Event::Start(Tag::Header(_)) => roff.push_str(".SS "),
+            Event::Start(Tag::CodeBlock(_)) => {
+                in_code_block = true;
+                roff.push_str(".PP\n.RS 4\n.nf\n");
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code_block = false;
+                roff.push_str(".fi\n.RE\n");
+            }
+            Event::Start(Tag::Item) => roff.push_str(".IP \\(bu 2\n"),
+            Event::Start(Tag::Emphasis) => roff.push_str("\\fI"),
+            Event::Start(Tag::Strong) | Event::Start(Tag::Code) => roff.push_str("\\fB"),
+            Event::End(Tag::Emphasis) | Event::End(Tag::Strong) | Event::End(Tag::Code) => {
+                roff.push_str("\\fR")
+            }
+            Event::End(Tag::Paragraph) | Event::End(Tag::Header(_)) | Event::End(Tag::Item)
+                if !roff.ends_with('\n') =>
+            {
+                roff.push('\n')
+            }
+            Event::Text(ref text) if in_code_block => {
+                for line in text.lines() {
+                    roff.push_str(&escape_line(line));
+                    roff.push('\n');
+                }
+            }
+            Event::Text(text) => push_text(&mut roff, &text),
+            Event::SoftBreak => roff.push('\n'),
+            Event::HardBreak => roff.push_str("\n.br\n"),
+            _ => (),
+        }
+    }
+
+    roff
+}
+
+/// Escapes text so that it is not interpreted as roff escapes.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e").replace('-', "\\-")
+}
+
+/// Escapes text at the start of a line so that it is not interpreted as a roff request.
+fn escape_line(text: &str) -> String {
+    let text = escape(text);
+
+    if text.starts_with('.') || text.starts_with('\'') {
+        format!("\\&{}", text)
+    } else {
+        text
+    }
+}
+
+/// Appends text to roff markup, escaping it as a line if it begins a new line.
+fn push_text(roff: &mut String, text: &str) {
+    if roff.is_empty() || roff.ends_with('\n') {
+        roff.push_str(&escape_line(text));
+    } else {
+        roff.push_str(&escape(text));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn markdown_to_roff() {
+        assert_eq!(
+            super::markdown_to_roff("Returns the `len`.\n\n```\n.foo()\n```\n"),
+            ".PP\nReturns the \\fBlen\\fR.\n.PP\n.RS 4\n.nf\n\\&.foo()\n.fi\n.RE\n"
+        );
+    }
+}
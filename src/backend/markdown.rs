@@ -0,0 +1,130 @@
+//! Renders documentation as a tree of Markdown files, one per item.
+//!
+//! The tree mirrors the layout of the HTML output, with each `.html` file replaced by a `.md` file.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use jsonapi::api::{IdentifierData, JsonApiDocument, Resource};
+
+use errors::*;
+use index::Index;
+use {html_diff_paths, path_for_resource, resource_by_id, resources, summary_for_resource};
+
+/// Given a JSON-API document generated by the rustdoc backend, generates a tree of Markdown files
+/// at the doc root.
+pub fn render<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> Result<()> {
+    let index = Index::parse(document)?;
+    let doc_root = root.as_ref();
+
+    for resource in resources(document) {
+        let path = match path_for_markdown(resource) {
+            Some(path) => path,
+            None => continue,
+        };
+
+        let file_path = doc_root.join(&path);
+        fs::create_dir_all(file_path.parent().unwrap())?;
+        info!("rendering `{}` as `{}`", resource.id, file_path.display());
+
+        let markdown = render_resource(&index, resource, &path);
+        File::create(&file_path)?.write_all(markdown.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Returns the path of the Markdown file for a resource, relative to the doc root.
+fn path_for_markdown(resource: &Resource) -> Option<PathBuf> {
+    path_for_resource(resource).map(|path| path.with_extension("md"))
+}
+
+/// Renders the Markdown file for a single resource.
+fn render_resource(document: &Index, resource: &Resource, path: &Path) -> String {
+    let mut markdown = format!("# {} `{}`\n", resource._type, resource.id);
+
+    if let Some(docs) = resource.attributes.get("docs").and_then(|docs| docs.as_str()) {
+        markdown.push('\n');
+        markdown.push_str(docs.trim());
+        markdown.push('\n');
+    }
+
+    let mut relationships = resource
+        .relationships
+        .iter()
+        .flat_map(|relationships| relationships.iter())
+        .collect::<Vec<_>>();
+    relationships.sort_by_key(|&(key, _)| key);
+
+    for (key, relationship) in relationships {
+        let children = match relationship.data {
+            IdentifierData::Single(ref child) => vec![child],
+            IdentifierData::Multiple(ref children) => children.iter().collect(),
+            IdentifierData::None => vec![],
+        };
+
+        markdown.push_str(&format!("\n## {}\n\n", key));
+
+        for child in children.iter().filter_map(|child| resource_by_id(document, &child.id)) {
+            let name = document.name(&child.id);
+            let link = path_for_markdown(child).and_then(|child_path| {
+                html_diff_paths(&child_path, path)
+            });
+
+            match link {
+                Some(link) => markdown.push_str(&format!("- [`{}`]({})", name, link)),
+                None => markdown.push_str(&format!("- `{}`", name)),
+            }
+
+            if let Some(summary) = summary_for_resource(document, child) {
+                markdown.push_str(&format!(": {}", summary));
+            }
+
+            markdown.push('\n');
+        }
+    }
+
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use jsonapi::api::JsonApiDocument;
+
+    use index::Index;
+    use resources;
+
+    #[test]
+    fn render_resource() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": { "docs": "Crate docs." },
+                    "relationships": {
+                        "functions": { "data": [{ "type": "function", "id": "test_crate::f" }] }
+                    }
+                },
+                "included": [
+                    {
+                        "type": "function",
+                        "id": "test_crate::f",
+                        "attributes": { "docs": "Does things. Quickly." }
+                    }
+                ]
+            }"#,
+        ).unwrap();
+
+        let index = Index::parse(&document).unwrap();
+        let krate = resources(&document).next().unwrap();
+        assert_eq!(
+            super::render_resource(&index, krate, Path::new("test_crate/index.md")),
+            "# crate `test_crate`\n\nCrate docs.\n\n## functions\n\n\
+             - [`f`](fn.f.md): Does things.\n"
+        );
+    }
+}
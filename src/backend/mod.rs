@@ -0,0 +1,9 @@
+//! Renderers that produce documentation in formats other than HTML.
+
+pub mod epub;
+pub mod json;
+pub mod llms;
+pub mod man;
+pub mod markdown;
+pub mod single;
+pub mod text;
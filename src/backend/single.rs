@@ -0,0 +1,97 @@
+//! Renders the documentation of an entire crate into a single, self-contained HTML file.
+//!
+//! The stylesheet and scripts are inlined into the page, and links between items point to anchors
+//! within the page, so the file can be archived or shared on its own.
+
+use std::io::prelude::*;
+use std::path::Path;
+
+use jsonapi::api::{IdentifierData, JsonApiDocument, Resource};
+use serde_json::Value;
+
+use errors::*;
+use index::Index;
+use options::DEFAULT_LANG;
+use output::{Filesystem, OutputBackend};
+use {cfg_for_resource, crate_metadata, create_handlebars, deprecation_for_resource,
+     docs_for_resource, path_for_resource, render_to_writer, resource_by_id, resources,
+     summary_for_resource};
+
+/// Given a JSON-API document generated by the rustdoc backend, generates a single HTML file named
+/// after the crate at the doc root.
+pub fn render<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> Result<()> {
+    render_to(document, &Filesystem::new(root.as_ref()))
+}
+
+/// Generates the HTML file of a document into a backend, with a path relative to the doc root.
+pub fn render_to(document: &JsonApiDocument, backend: &dyn OutputBackend) -> Result<()> {
+    let index = Index::parse(document)?;
+
+    let krate = crate_metadata(document);
+    let path = format!("{}.html", krate["name"].as_str().unwrap_or("docs"));
+
+    let items = resources(document)
+        .filter(|resource| path_for_resource(resource).is_some())
+        .map(|resource| item_context(&index, resource))
+        .collect::<Vec<_>>();
+
+    let context = json!({
+        "lang": DEFAULT_LANG,
+        "crate": krate,
+        "items": items,
+        "css": include_str!("../../static/styles.css"),
+        "printCss": include_str!("../../static/print.css"),
+        "js": [include_str!("../../static/settings.js"), include_str!("../../static/main.js")],
+    });
+
+    info!("rendering crate as `{}`", path);
+    let mut file = backend.create(Path::new(&path))?;
+    render_to_writer(&create_handlebars(), "single", &context, &mut file)?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Returns the anchor of an item within the page.
+fn anchor(resource: &Resource) -> String {
+    resource.id.replace("::", "-")
+}
+
+/// Generates the context for a single item's section of the page.
+fn item_context(document: &Index, resource: &Resource) -> Value {
+    // Links to the paths of items in the documentation point to the sections of the items.
+    let link = |id: &str| Some(format!("#{}", id.replace("::", "-")));
+    let mut sections = json!({});
+
+    for (key, relationship) in resource.relationships.iter().flat_map(|r| r.iter()) {
+        let children = match relationship.data {
+            IdentifierData::Single(ref child) => vec![child],
+            IdentifierData::Multiple(ref children) => children.iter().collect(),
+            IdentifierData::None => vec![],
+        };
+
+        let children = children
+            .iter()
+            .filter_map(|child| resource_by_id(document, &child.id))
+            .map(|child| {
+                json!({
+                    "name": document.name(&child.id),
+                    "link": document.path(child).map(|_| format!("#{}", anchor(child))),
+                    "docs": docs_for_resource(document, child, link),
+                    "summary": summary_for_resource(document, child),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        sections.as_object_mut().unwrap().insert(key.clone(), Value::Array(children));
+    }
+
+    json!({
+        "anchor": anchor(resource),
+        "type": resource._type,
+        "path": resource.id,
+        "docs": docs_for_resource(document, resource, link),
+        "cfg": cfg_for_resource(document, resource).map(|cfg| cfg.banner()),
+        "deprecation": deprecation_for_resource(document, resource),
+        "sections": sections,
+    })
+}
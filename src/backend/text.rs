@@ -0,0 +1,132 @@
+//! Renders documentation as ANSI-colored plain text files, one per item, for reading in a terminal.
+//!
+//! The tree mirrors the layout of the HTML output, with each `.html` file replaced by a `.txt`
+//! file. The files can be viewed with a pager that understands ANSI escapes, such as `less -R`.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::Path;
+
+use jsonapi::api::{IdentifierData, JsonApiDocument, Resource};
+use pulldown_cmark::{Event, Parser, Tag};
+
+use errors::*;
+use index::Index;
+use {path_for_resource, resource_by_id, resources, summary_for_resource};
+
+const BOLD: &str = "\x1b[1m";
+const ITALIC: &str = "\x1b[3m";
+const CYAN: &str = "\x1b[36m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// Given a JSON-API document generated by the rustdoc backend, generates a tree of text files at
+/// the doc root.
+pub fn render<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> Result<()> {
+    let index = Index::parse(document)?;
+    let doc_root = root.as_ref();
+
+    for resource in resources(document) {
+        let path = match path_for_resource(resource) {
+            Some(path) => doc_root.join(path.with_extension("txt")),
+            None => continue,
+        };
+
+        fs::create_dir_all(path.parent().unwrap())?;
+        info!("rendering `{}` as `{}`", resource.id, path.display());
+
+        let text = render_resource(&index, resource);
+        File::create(&path)?.write_all(text.as_bytes())?;
+    }
+
+    Ok(())
+}
+
+/// Renders the text file for a single resource.
+fn render_resource(document: &Index, resource: &Resource) -> String {
+    let mut text = format!("{}{} {}{}\n", BOLD, resource._type, resource.id, RESET);
+
+    if let Some(docs) = resource.attributes.get("docs").and_then(|docs| docs.as_str()) {
+        text.push('\n');
+        text.push_str(&markdown_to_ansi(docs));
+    }
+
+    let mut relationships = resource
+        .relationships
+        .iter()
+        .flat_map(|relationships| relationships.iter())
+        .collect::<Vec<_>>();
+    relationships.sort_by_key(|&(key, _)| key);
+
+    for (key, relationship) in relationships {
+        let children = match relationship.data {
+            IdentifierData::Single(ref child) => vec![child],
+            IdentifierData::Multiple(ref children) => children.iter().collect(),
+            IdentifierData::None => vec![],
+        };
+
+        text.push_str(&format!("\n{}{}{}\n\n", BOLD, key.to_uppercase(), RESET));
+
+        for child in children.iter().filter_map(|child| resource_by_id(document, &child.id)) {
+            let name = document.name(&child.id);
+            text.push_str(&format!("    {}{}{}", GREEN, name, RESET));
+
+            if let Some(summary) = summary_for_resource(document, child) {
+                text.push_str(&format!("  {}", summary));
+            }
+
+            text.push('\n');
+        }
+    }
+
+    text
+}
+
+/// Converts Markdown to plain text, using ANSI escapes for emphasis and code.
+fn markdown_to_ansi(markdown: &str) -> String {
+    let mut text = String::new();
+    let mut in_code_block = false;
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::Header(_)) | Event::Start(Tag::Strong) => text.push_str(BOLD),
+            Event::Start(Tag::Emphasis) => text.push_str(ITALIC),
+            Event::Start(Tag::Code) => text.push_str(CYAN),
+            Event::Start(Tag::CodeBlock(_)) => in_code_block = true,
+            Event::Start(Tag::Item) => text.push_str("  * "),
+            Event::End(Tag::Header(_)) => text.push_str(&format!("{}\n\n", RESET)),
+            Event::End(Tag::Strong) | Event::End(Tag::Emphasis) | Event::End(Tag::Code) => {
+                text.push_str(RESET)
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code_block = false;
+                text.push('\n');
+            }
+            Event::End(Tag::Paragraph) => text.push_str("\n\n"),
+            Event::End(Tag::Item) => text.push('\n'),
+            Event::End(Tag::List(_)) => text.push('\n'),
+            Event::Text(ref code) if in_code_block => {
+                for line in code.lines() {
+                    text.push_str(&format!("    {}{}{}\n", CYAN, line, RESET));
+                }
+            }
+            Event::Text(t) => text.push_str(&t),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak => text.push('\n'),
+            _ => (),
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn markdown_to_ansi() {
+        assert_eq!(
+            super::markdown_to_ansi("Returns *the* `len`.\n\n```\nx.len()\n```\n"),
+            "Returns \x1b[3mthe\x1b[0m \x1b[36mlen\x1b[0m.\n\n    \x1b[36mx.len()\x1b[0m\n\n"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! `cargo doc-static`: documents the current crate or workspace with rustdoc, and renders the JSON
+//! as static documentation.
+
+extern crate rustdoc_static;
+
+#[macro_use]
+extern crate error_chain;
+
+extern crate clap;
+extern crate pretty_env_logger;
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use clap::{App, AppSettings, Arg, SubCommand};
+
+use rustdoc_static::cargo::CargoDoc;
+use rustdoc_static::config::Config;
+use rustdoc_static::errors::*;
+use rustdoc_static::{RenderOptions, Renderer};
+
+fn run() -> Result<()> {
+    // Cargo runs `cargo-doc-static doc-static [ARGS]`, so the arguments are those of a
+    // subcommand.
+    let matches = App::new("cargo-doc-static")
+        .bin_name("cargo")
+        .version(env!("CARGO_PKG_VERSION"))
+        .setting(AppSettings::SubcommandRequired)
+        .subcommand(
+            SubCommand::with_name("doc-static")
+                .about("Documents a crate and renders the documentation as static files")
+                .arg(
+                    Arg::with_name("package")
+                        .long("package")
+                        .short("p")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("SPEC")
+                        .help("a package to document; may be given more than once"),
+                )
+                .arg(
+                    Arg::with_name("workspace")
+                        .long("workspace")
+                        .help("document every member of the workspace"),
+                )
+                .arg(
+                    Arg::with_name("manifest-path")
+                        .long("manifest-path")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .help("the Cargo.toml of the crate or workspace"),
+                )
+                .arg(
+                    Arg::with_name("toolchain")
+                        .long("toolchain")
+                        .takes_value(true)
+                        .default_value("nightly")
+                        .help("the toolchain to run rustdoc with, which must support JSON output"),
+                )
+                .arg(
+                    Arg::with_name("document-private-items")
+                        .long("document-private-items")
+                        .help("document private items"),
+                )
+                .arg(
+                    Arg::with_name("jobs")
+                        .long("jobs")
+                        .short("j")
+                        .takes_value(true)
+                        .value_name("N")
+                        .help("the number of jobs that build and render the documentation"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .long("output")
+                        .short("o")
+                        .takes_value(true)
+                        .help("where the documentation is output; defaults to target/doc-static"),
+                )
+                .arg(
+                    Arg::with_name("open")
+                        .long("open")
+                        .help("open the documentation in a browser once it is rendered"),
+                )
+                .arg(
+                    Arg::with_name("quiet")
+                        .long("quiet")
+                        .short("q")
+                        .help("only print errors"),
+                ),
+        )
+        .get_matches();
+    let matches = matches.subcommand_matches("doc-static").unwrap();
+
+    pretty_env_logger::init().unwrap();
+    let start = Instant::now();
+
+    let private = matches.is_present("document-private-items");
+    let jobs = match matches.value_of("jobs").map(str::parse) {
+        Some(Ok(0)) | Some(Err(_)) => bail!("--jobs must be a positive number"),
+        Some(Ok(jobs)) => Some(jobs),
+        None => None,
+    };
+    let mut cargo_doc = CargoDoc::new()
+        .workspace(matches.is_present("workspace"))
+        .toolchain(matches.value_of("toolchain").unwrap())
+        .document_private_items(private);
+    if let Some(manifest_path) = matches.value_of("manifest-path") {
+        cargo_doc = cargo_doc.manifest_path(manifest_path);
+    }
+    for package in matches.values_of("package").into_iter().flatten() {
+        cargo_doc = cargo_doc.package(package);
+    }
+    if let Some(jobs) = jobs {
+        cargo_doc = cargo_doc.jobs(jobs);
+    }
+    let doc_output = cargo_doc.run()?;
+
+    // The configuration is read from the directory of the manifest, like other cargo commands.
+    let workspace_dir = matches
+        .value_of("manifest-path")
+        .and_then(|manifest_path| Path::new(manifest_path).parent())
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let config = Config::discover(workspace_dir)?.unwrap_or_default();
+    let output_path = match matches.value_of("output") {
+        Some(output) => PathBuf::from(output),
+        None => config
+            .output
+            .clone()
+            .unwrap_or_else(|| doc_output.target_directory.join("doc-static")),
+    };
+
+    let documents = doc_output
+        .documents
+        .iter()
+        .map(rustdoc_static::load::from_path)
+        .collect::<Result<Vec<_>>>()?;
+    // The paths of the source files in rustdoc JSON are relative to the root of the workspace.
+    let options = RenderOptions::new().source_dir(&doc_output.workspace_root);
+    let mut options = config.render_options(options)?;
+    if private {
+        options = options.document_private_items(true);
+    }
+    if let Some(jobs) = jobs {
+        options = options.jobs(jobs);
+    }
+    let report = Renderer::with_options(options)?.render_multi(&documents, &output_path)?;
+
+    if !matches.is_present("quiet") {
+        eprintln!(
+            "Rendered documentation to `{}` in {:.2}s: {} files written",
+            output_path.display(),
+            start.elapsed().as_secs_f64(),
+            report.files_written.len()
+        );
+        if let Some(summary) = report.summary() {
+            eprint!("{}", summary);
+        }
+    }
+
+    if matches.is_present("open") {
+        if let Some(page) = rustdoc_static::browser::crate_page(&documents) {
+            let page = output_path.join(page);
+            let page = env::current_dir().map(|dir| dir.join(&page)).unwrap_or(page);
+            rustdoc_static::browser::open(&page)
+                .chain_err(|| format!("could not open `{}` in a browser", page.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+quick_main!(run);
@@ -0,0 +1,75 @@
+//! Opening rendered documentation in a web browser, like `cargo doc --open`.
+
+use std::env;
+use std::ffi::OsStr;
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use jsonapi::api::JsonApiDocument;
+
+use {crates, path_for_resource};
+
+/// Returns the page that is opened after documents are rendered together, relative to the doc
+/// root: the page of their crate, or the landing page if they have several crates.
+pub fn crate_page(documents: &[JsonApiDocument]) -> Option<PathBuf> {
+    let mut crates = documents.iter().flat_map(crates).collect::<Vec<_>>();
+    crates.dedup_by(|a, b| a.id == b.id);
+
+    match crates[..] {
+        [] => None,
+        [krate] => path_for_resource(krate),
+        _ => Some(PathBuf::from("index.html")),
+    }
+}
+
+/// Opens a file or URL in the default browser, without waiting for the browser to exit.
+///
+/// Like cargo, the browser in the `BROWSER` environment variable is preferred over the default
+/// browser of the system.
+pub fn open<S: AsRef<OsStr>>(target: S) -> io::Result<()> {
+    let mut command = match env::var_os("BROWSER").filter(|browser| !browser.is_empty()) {
+        Some(browser) => Command::new(browser),
+        None if cfg!(target_os = "macos") => Command::new("open"),
+        None if cfg!(windows) => {
+            let mut command = Command::new("cmd");
+            command.args(["/C", "start", ""]);
+            command
+        }
+        None => Command::new("xdg-open"),
+    };
+
+    info!("opening `{}`", target.as_ref().to_string_lossy());
+    command
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use jsonapi::api::JsonApiDocument;
+
+    #[test]
+    fn crate_page() {
+        let document = |krate: &str| {
+            JsonApiDocument::from_str(&format!(
+                r#"{{ "data": {{ "type": "crate", "id": "{}", "attributes": {{}} }} }}"#,
+                krate
+            )).unwrap()
+        };
+
+        let page = super::crate_page(&[document("a"), document("a")]);
+        assert_eq!(page, Some(PathBuf::from("a/index.html")));
+        let page = super::crate_page(&[document("a"), document("b")]);
+        assert_eq!(page, Some(PathBuf::from("index.html")));
+
+        let document = JsonApiDocument::from_str(r#"{ "data": [] }"#).unwrap();
+        assert_eq!(super::crate_page(&[document]), None);
+    }
+}
@@ -0,0 +1,290 @@
+//! Generation of the rustdoc JSON of a Cargo package or workspace, for `cargo doc-static`.
+//!
+//! The JSON is written by `cargo doc` with `--output-format json`, which is unstable, so cargo is
+//! run with a nightly toolchain by default.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::{self, Value};
+
+use errors::*;
+
+/// The kinds of targets that `cargo doc` documents as a library.
+const LIB_KINDS: &[&str] = &["lib", "rlib", "dylib", "cdylib", "staticlib", "proc-macro"];
+
+/// Runs `cargo doc` on the packages of a workspace, writing their rustdoc JSON.
+#[derive(Debug, Clone)]
+pub struct CargoDoc {
+    manifest_path: Option<PathBuf>,
+    packages: Vec<String>,
+    workspace: bool,
+    toolchain: String,
+    document_private_items: bool,
+    jobs: Option<usize>,
+}
+
+/// The rustdoc JSON written by `cargo doc`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocOutput {
+    /// The target directory of the workspace.
+    pub target_directory: PathBuf,
+
+    /// The root directory of the workspace, which the paths of the source files in the rustdoc
+    /// JSON are relative to.
+    pub workspace_root: PathBuf,
+
+    /// The rustdoc JSON of each documented target.
+    pub documents: Vec<PathBuf>,
+}
+
+impl CargoDoc {
+    /// Documents the packages that `cargo doc` documents by default in the current directory.
+    pub fn new() -> CargoDoc {
+        CargoDoc {
+            manifest_path: None,
+            packages: vec![],
+            workspace: false,
+            toolchain: String::from("nightly"),
+            document_private_items: false,
+            jobs: None,
+        }
+    }
+
+    /// Documents the workspace of this `Cargo.toml` instead of the current directory.
+    pub fn manifest_path<P: Into<PathBuf>>(mut self, manifest_path: P) -> CargoDoc {
+        self.manifest_path = Some(manifest_path.into());
+        self
+    }
+
+    /// Documents a package of the workspace, by name. May be called more than once.
+    pub fn package<S: Into<String>>(mut self, package: S) -> CargoDoc {
+        self.packages.push(package.into());
+        self
+    }
+
+    /// Documents every member of the workspace.
+    pub fn workspace(mut self, workspace: bool) -> CargoDoc {
+        self.workspace = workspace;
+        self
+    }
+
+    /// Sets the rustup toolchain that cargo is run with. Defaults to `nightly`.
+    pub fn toolchain<S: Into<String>>(mut self, toolchain: S) -> CargoDoc {
+        self.toolchain = toolchain.into();
+        self
+    }
+
+    /// Includes private items in the JSON.
+    pub fn document_private_items(mut self, document_private_items: bool) -> CargoDoc {
+        self.document_private_items = document_private_items;
+        self
+    }
+
+    /// Sets the number of jobs that cargo builds with. Defaults to the number of cores.
+    pub fn jobs(mut self, jobs: usize) -> CargoDoc {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Runs `cargo doc`, returning the paths of the rustdoc JSON it wrote.
+    ///
+    /// The output of cargo is passed through, so that build errors are shown.
+    pub fn run(&self) -> Result<DocOutput> {
+        let mut metadata = self.cargo();
+        metadata.args(["metadata", "--format-version", "1", "--no-deps"]);
+        self.add_manifest_path(&mut metadata);
+
+        info!("running {:?}", metadata);
+        let output = metadata.output().chain_err(|| "could not run `cargo metadata`")?;
+        if !output.status.success() {
+            bail!(
+                "`cargo metadata` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        let metadata = serde_json::from_slice::<Value>(&output.stdout)
+            .chain_err(|| "could not parse the output of `cargo metadata`")?;
+        let output = doc_output(&metadata, &self.packages, self.workspace)?;
+
+        let mut flags = env::var("RUSTDOCFLAGS").unwrap_or_default();
+        flags.push_str(" -Z unstable-options --output-format json");
+
+        let mut doc = self.cargo();
+        doc.args(["doc", "--no-deps"]).env("RUSTDOCFLAGS", flags.trim_start());
+        self.add_manifest_path(&mut doc);
+        for package in &self.packages {
+            doc.args(["--package", package]);
+        }
+        if self.workspace {
+            doc.arg("--workspace");
+        }
+        if self.document_private_items {
+            doc.arg("--document-private-items");
+        }
+        if let Some(jobs) = self.jobs {
+            doc.arg("--jobs").arg(jobs.to_string());
+        }
+
+        info!("running {:?}", doc);
+        let status = doc.status().chain_err(|| "could not run `cargo doc`")?;
+        if !status.success() {
+            bail!("`cargo doc` failed with {}", status);
+        }
+
+        if let Some(missing) = output.documents.iter().find(|document| !document.is_file()) {
+            bail!("`cargo doc` did not write `{}`", missing.display());
+        }
+        Ok(output)
+    }
+
+    /// Returns a cargo command run with the toolchain.
+    fn cargo(&self) -> Command {
+        let mut cargo = Command::new("cargo");
+        if !self.toolchain.is_empty() {
+            cargo.arg(format!("+{}", self.toolchain));
+        }
+        cargo
+    }
+
+    /// Points a cargo command at the manifest, if one was given.
+    fn add_manifest_path(&self, command: &mut Command) {
+        if let Some(ref manifest_path) = self.manifest_path {
+            command.arg("--manifest-path").arg(manifest_path);
+        }
+    }
+}
+
+impl Default for CargoDoc {
+    fn default() -> CargoDoc {
+        CargoDoc::new()
+    }
+}
+
+/// Returns where `cargo doc` writes the rustdoc JSON of the selected packages of a workspace,
+/// given the output of `cargo metadata`.
+///
+/// Like `cargo doc`, the library of each package is documented along with its binaries, except
+/// for binaries named after the library. Without packages, the default members of the workspace
+/// are documented.
+fn doc_output(metadata: &Value, packages: &[String], workspace: bool) -> Result<DocOutput> {
+    let invalid = || "unexpected output from `cargo metadata`";
+    let target_directory = metadata["target_directory"].as_str().ok_or_else(invalid)?;
+    let workspace_root = metadata["workspace_root"].as_str().ok_or_else(invalid)?;
+    let all_packages = metadata["packages"].as_array().ok_or_else(invalid)?;
+
+    let ids = |key: &str| {
+        metadata[key]
+            .as_array()
+            .map(|ids| ids.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+    };
+    let members = ids("workspace_members").ok_or_else(invalid)?;
+
+    let selected = if !packages.is_empty() {
+        let mut selected = vec![];
+        for name in packages {
+            let package = all_packages
+                .iter()
+                .find(|package| package["name"].as_str() == Some(&name[..]))
+                .ok_or_else(|| format!("the workspace has no package `{}`", name))?;
+            selected.push(package);
+        }
+        selected
+    } else {
+        // Versions of cargo that do not list the default members document every member.
+        let ids = match ids("workspace_default_members") {
+            Some(ref default_members) if !workspace => default_members.clone(),
+            _ => members,
+        };
+        all_packages
+            .iter()
+            .filter(|package| package["id"].as_str().is_some_and(|id| ids.contains(&id)))
+            .collect()
+    };
+
+    let doc_dir = Path::new(target_directory).join("doc");
+    let mut documents = vec![];
+    for package in selected {
+        let targets = package["targets"].as_array().ok_or_else(invalid)?;
+        let is_kind = |target: &Value, kinds: &[&str]| {
+            target["kind"].as_array().is_some_and(|kind| {
+                kind.iter().filter_map(Value::as_str).any(|kind| kinds.contains(&kind))
+            })
+        };
+
+        let lib = targets.iter().find(|target| is_kind(target, LIB_KINDS));
+        let lib_name = lib.and_then(|lib| lib["name"].as_str());
+        let bins = targets
+            .iter()
+            .filter(|target| is_kind(target, &["bin"]))
+            .filter(|bin| bin["name"].as_str() != lib_name);
+
+        for target in lib.into_iter().chain(bins) {
+            let name = target["name"].as_str().ok_or_else(invalid)?;
+            documents.push(doc_dir.join(format!("{}.json", name.replace('-', "_"))));
+        }
+    }
+
+    Ok(DocOutput {
+        target_directory: PathBuf::from(target_directory),
+        workspace_root: PathBuf::from(workspace_root),
+        documents,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::DocOutput;
+
+    #[test]
+    fn doc_output() {
+        let metadata = json!({
+            "target_directory": "/ws/target",
+            "workspace_root": "/ws",
+            "workspace_members": ["core 0.1.0", "my-cli 0.1.0"],
+            "workspace_default_members": ["my-cli 0.1.0"],
+            "packages": [
+                {
+                    "id": "core 0.1.0",
+                    "name": "core",
+                    "targets": [
+                        { "name": "my-core", "kind": ["lib"] },
+                        { "name": "bench", "kind": ["bench"] }
+                    ]
+                },
+                {
+                    "id": "my-cli 0.1.0",
+                    "name": "my-cli",
+                    "targets": [
+                        { "name": "my-cli", "kind": ["lib"] },
+                        { "name": "my-cli", "kind": ["bin"] },
+                        { "name": "helper", "kind": ["bin"] }
+                    ]
+                }
+            ]
+        });
+
+        let doc_dir = PathBuf::from("/ws/target/doc");
+        let output = |documents: &[&str]| DocOutput {
+            target_directory: PathBuf::from("/ws/target"),
+            workspace_root: PathBuf::from("/ws"),
+            documents: documents.iter().map(|name| doc_dir.join(name)).collect(),
+        };
+        assert_eq!(
+            super::doc_output(&metadata, &[], false).unwrap(),
+            output(&["my_cli.json", "helper.json"])
+        );
+        assert_eq!(
+            super::doc_output(&metadata, &[], true).unwrap(),
+            output(&["my_core.json", "my_cli.json", "helper.json"])
+        );
+        assert_eq!(
+            super::doc_output(&metadata, &[String::from("core")], false).unwrap(),
+            output(&["my_core.json"])
+        );
+        assert!(super::doc_output(&metadata, &[String::from("missing")], false).is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Descriptions of the conditional compilation (`#[cfg]`) requirements of items.
+//!
+//! Resources may carry a `cfg` attribute containing the predicate that must hold for the item to
+//! be available, such as `feature = "serde"` or `all(unix, target_arch = "x86_64")`.
+
+use handlebars::html_escape;
+
+/// A parsed `#[cfg]` predicate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cfg {
+    /// An option that is either set or unset, such as `unix`.
+    Name(String),
+
+    /// An option with a value, such as `feature = "serde"`.
+    NameValue(String, String),
+
+    /// `all(...)`
+    All(Vec<Cfg>),
+
+    /// `any(...)`
+    Any(Vec<Cfg>),
+
+    /// `not(...)`
+    Not(Box<Cfg>),
+}
+
+impl Cfg {
+    /// Parses a `#[cfg]` predicate, returning `None` if it is malformed.
+    pub fn parse(predicate: &str) -> Option<Cfg> {
+        let mut parser = Parser { input: predicate };
+        let cfg = parser.predicate()?;
+        parser.skip_whitespace();
+
+        if parser.input.is_empty() {
+            Some(cfg)
+        } else {
+            None
+        }
+    }
+
+    /// Returns an HTML banner describing when the item is available, such as "Available on
+    /// crate feature `serde` only."
+    pub fn banner(&self) -> String {
+        format!("Available on {} only.", self.describe(false))
+    }
+
+    /// Returns a short HTML description of the predicate, suitable for a badge in item listings.
+    pub fn badge(&self) -> String {
+        self.describe(true)
+    }
+
+    fn describe(&self, short: bool) -> String {
+        match *self {
+            Cfg::Name(ref name) => match name.as_str() {
+                "unix" => String::from("Unix"),
+                "windows" => String::from("Windows"),
+                "debug_assertions" => String::from("debug-assertions enabled"),
+                "doc" => String::from("documentation"),
+                name => format!("<code>{}</code>", html_escape(name)),
+            },
+            Cfg::NameValue(ref name, ref value) => match (name.as_str(), value.as_str()) {
+                ("feature", feature) if short => format!("<code>{}</code>", html_escape(feature)),
+                ("feature", feature) => {
+                    format!("crate feature <code>{}</code>", html_escape(feature))
+                }
+                ("target_os", os) | ("target_family", os) => String::from(match os {
+                    "linux" => "Linux",
+                    "macos" => "macOS",
+                    "ios" => "iOS",
+                    "windows" => "Windows",
+                    "android" => "Android",
+                    "freebsd" => "FreeBSD",
+                    "netbsd" => "NetBSD",
+                    "openbsd" => "OpenBSD",
+                    "unix" => "Unix",
+                    "wasm" => "WebAssembly",
+                    os => return html_escape(os),
+                }),
+                ("target_pointer_width", width) => format!("{}-bit", html_escape(width)),
+                (name, value) => {
+                    format!("<code>{}=\"{}\"</code>", html_escape(name), html_escape(value))
+                }
+            },
+            Cfg::All(ref cfgs) => join(cfgs, " and ", short),
+            Cfg::Any(ref cfgs) => join(cfgs, " or ", short),
+            Cfg::Not(ref cfg) => match **cfg {
+                Cfg::Name(_) | Cfg::NameValue(..) => format!("non-{}", cfg.describe(short)),
+                _ => format!("not ({})", cfg.describe(short)),
+            },
+        }
+    }
+}
+
+fn join(cfgs: &[Cfg], separator: &str, short: bool) -> String {
+    cfgs.iter().map(|cfg| cfg.describe(short)).collect::<Vec<_>>().join(separator)
+}
+
+/// A recursive descent parser for `#[cfg]` predicates.
+struct Parser<'a> {
+    input: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn predicate(&mut self) -> Option<Cfg> {
+        let name = self.ident()?;
+        self.skip_whitespace();
+
+        match name {
+            "all" | "any" | "not" if self.eat('(') => {
+                let mut cfgs = vec![];
+                loop {
+                    self.skip_whitespace();
+                    if self.eat(')') {
+                        break;
+                    }
+                    cfgs.push(self.predicate()?);
+                    self.skip_whitespace();
+                    if !self.eat(',') {
+                        self.skip_whitespace();
+                        if !self.eat(')') {
+                            return None;
+                        }
+                        break;
+                    }
+                }
+
+                match name {
+                    "all" => Some(Cfg::All(cfgs)),
+                    "any" => Some(Cfg::Any(cfgs)),
+                    _ if cfgs.len() == 1 => Some(Cfg::Not(Box::new(cfgs.remove(0)))),
+                    _ => None,
+                }
+            }
+            _ if self.eat('=') => {
+                self.skip_whitespace();
+                Some(Cfg::NameValue(name.to_owned(), self.string()?.to_owned()))
+            }
+            _ => Some(Cfg::Name(name.to_owned())),
+        }
+    }
+
+    fn ident(&mut self) -> Option<&'a str> {
+        self.skip_whitespace();
+        let end = self
+            .input
+            .find(|c: char| !(c == '_' || c.is_alphanumeric()))
+            .unwrap_or(self.input.len());
+
+        if end == 0 {
+            return None;
+        }
+
+        let (ident, rest) = self.input.split_at(end);
+        self.input = rest;
+        Some(ident)
+    }
+
+    fn string(&mut self) -> Option<&'a str> {
+        if !self.eat('"') {
+            return None;
+        }
+
+        let end = self.input.find('"')?;
+        let string = &self.input[..end];
+        self.input = &self.input[end + 1..];
+        Some(string)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.input.starts_with(c) {
+            self.input = &self.input[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.input = self.input.trim_start();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Cfg;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Cfg::parse("all(unix, feature = \"serde\")"),
+            Some(Cfg::All(vec![
+                Cfg::Name(String::from("unix")),
+                Cfg::NameValue(String::from("feature"), String::from("serde")),
+            ]))
+        );
+        assert_eq!(Cfg::parse("not(windows, unix)"), None);
+        assert_eq!(Cfg::parse("feature = "), None);
+    }
+
+    #[test]
+    fn describe() {
+        let cfg = Cfg::parse("feature = \"serde\"").unwrap();
+        assert_eq!(cfg.banner(), "Available on crate feature <code>serde</code> only.");
+        assert_eq!(cfg.badge(), "<code>serde</code>");
+
+        let cfg = Cfg::parse("any(target_os = \"linux\", not(windows))").unwrap();
+        assert_eq!(cfg.banner(), "Available on Linux or non-Windows only.");
+    }
+}
@@ -0,0 +1,108 @@
+//! Removal of the files a render wrote into a doc root, such as the pages of items that no longer
+//! exist.
+//!
+//! Only the files recorded in the manifest of the doc root are removed, along with their
+//! precompressed copies and the directories left empty, so files added to the doc root by other
+//! tools are kept.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use errors::*;
+use manifest::{Manifest, MANIFEST};
+
+/// The extensions of the precompressed copies of a file.
+const COMPRESSED_EXTENSIONS: &[&str] = &["gz", "br"];
+
+/// Returns the files under a doc root that were written by rustdoc-static, relative to the doc
+/// root, in sorted order.
+///
+/// Returns an error if the doc root has no manifest, since its files cannot be told apart from
+/// files written by other tools.
+pub fn generated_files<P: AsRef<Path>>(doc_root: P) -> Result<Vec<PathBuf>> {
+    let doc_root = doc_root.as_ref();
+    let files = Manifest::files(doc_root)
+        .chain_err(|| format!("could not read the manifest of `{}`", doc_root.display()))?
+        .ok_or_else(|| format!("`{}` has no manifest to clean with", doc_root.display()))?;
+
+    let mut generated = BTreeSet::new();
+    generated.insert(PathBuf::from(MANIFEST));
+
+    // The paths come from a file in the doc root, so they are never followed out of it.
+    let files = files.into_iter().filter(|file| {
+        file.components().all(|component| matches!(component, Component::Normal(_)))
+    });
+    for file in files {
+        for extension in COMPRESSED_EXTENSIONS {
+            let mut compressed = file.clone().into_os_string();
+            compressed.push(".");
+            compressed.push(extension);
+            generated.insert(PathBuf::from(compressed));
+        }
+        generated.insert(file);
+    }
+
+    Ok(generated.into_iter().filter(|file| doc_root.join(file).is_file()).collect())
+}
+
+/// Removes the files under a doc root that were written by rustdoc-static, and the directories
+/// that are empty afterwards. Returns the removed files, relative to the doc root.
+pub fn clean<P: AsRef<Path>>(doc_root: P) -> Result<Vec<PathBuf>> {
+    let doc_root = doc_root.as_ref();
+    let files = generated_files(doc_root)?;
+
+    let mut directories = BTreeSet::new();
+    for file in &files {
+        let path = doc_root.join(file);
+        debug!("removing `{}`", path.display());
+        fs::remove_file(&path).chain_err(|| format!("could not remove `{}`", path.display()))?;
+        directories.extend(file.ancestors().skip(1).filter(|dir| *dir != Path::new("")));
+    }
+
+    // Subdirectories sort after their parents, so they are removed first. Directories that still
+    // have other files in them cannot be removed, and are kept.
+    for directory in directories.iter().rev() {
+        let _ = fs::remove_dir(doc_root.join(directory));
+    }
+
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use manifest::{Manifest, MANIFEST};
+
+    #[test]
+    fn clean() {
+        let root = env::temp_dir().join("rustdoc-static-clean");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("demo/util")).unwrap();
+        for file in &["demo/index.html", "demo/index.html.gz", "demo/util/index.html", "CNAME"] {
+            fs::write(root.join(file), "").unwrap();
+        }
+
+        let mut manifest = Manifest::default();
+        manifest.insert_file(&PathBuf::from("demo/index.html"));
+        manifest.insert_file(&PathBuf::from("demo/util/index.html"));
+        manifest.insert_file(&PathBuf::from("../outside.html"));
+        manifest.save(&root).unwrap();
+
+        let removed = super::clean(&root).unwrap();
+        let paths = |paths: &[&str]| paths.iter().map(PathBuf::from).collect::<Vec<_>>();
+        assert_eq!(
+            removed,
+            paths(&[MANIFEST, "demo/index.html", "demo/index.html.gz", "demo/util/index.html"])
+        );
+        assert!(root.join("CNAME").is_file());
+        assert!(!root.join("demo").exists());
+
+        assert!(super::clean(&root).is_err());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,470 @@
+//! Configuration files that set the options of a render, so that they do not have to be passed
+//! on the command line every time.
+//!
+//! The options are read from a `rustdoc-static.toml` file, or from the
+//! `[package.metadata.rustdoc-static]` table of a `Cargo.toml`:
+//!
+//! ```toml
+//! theme = "dark"
+//! base-url = "https://docs.example.com/"
+//! exclude = ["my_crate::internal"]
+//! extra-css = ["docs/custom.css"]
+//! readme = "README.md"
+//! output = "target/doc"
+//! ```
+//!
+//! Keys that are not options are an error, as are tables nested in the configuration. Paths are
+//! relative to the directory of the file.
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use serde::de::{self, Deserialize, Deserializer};
+use toml;
+
+use analytics::Analytics;
+use errors::*;
+use load;
+use options::RenderOptions;
+
+/// The name of the configuration file that is looked for next to `Cargo.toml`.
+pub const CONFIG_FILE: &str = "rustdoc-static.toml";
+
+/// The options of a render, as read from a configuration file.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    /// The theme pages are displayed with until the reader picks one, from `theme`.
+    pub theme: Option<String>,
+
+    /// The language the docs are written in, from `lang`.
+    pub lang: Option<String>,
+
+    /// The URL the documentation will be hosted at, from `base-url`.
+    pub base_url: Option<String>,
+
+    /// The globs of the items to document, from `include`.
+    pub include: Vec<String>,
+
+    /// The globs of the items to leave out, from `exclude`.
+    pub exclude: Vec<String>,
+
+    /// Whether a `robots.txt` is written, from `robots-txt`.
+    pub robots_txt: bool,
+
+    /// Paths that `robots.txt` disallows besides the default ones, from `robots-disallow`.
+    pub robots_disallow: Vec<String>,
+
+    /// Whether a `_headers` file with a Content Security Policy is written, from `csp-headers`.
+    pub csp_headers: bool,
+
+    /// Whether a service worker for offline browsing is written, from `service-worker`.
+    pub service_worker: bool,
+
+    /// Whether a map of the files to hashes of their contents is written, from `content-hashes`.
+    pub content_hashes: bool,
+
+    /// Stylesheets added to the built-in styles, from `extra-css`.
+    pub extra_css: Vec<PathBuf>,
+
+    /// A Markdown file rendered on the page of the crate, from `readme`.
+    pub readme: Option<PathBuf>,
+
+    /// Whether the README replaces the docs of the crate root, from `readme-replaces-docs`.
+    pub readme_replaces_docs: bool,
+
+    /// The directory that the source files of the crates are read from, from `source-dir`.
+    pub source_dir: Option<PathBuf>,
+
+    /// The URL of the logo of the crate, from `logo-url`.
+    pub logo_url: Option<String>,
+
+    /// The URL of the favicon of every page, from `favicon-url`.
+    pub favicon_url: Option<String>,
+
+    /// The analytics service whose snippet is added to every page, from `analytics`.
+    #[serde(deserialize_with = "analytics")]
+    pub analytics: Option<Analytics>,
+
+    /// A file of HTML that is added to every page, from `analytics-snippet`.
+    pub analytics_snippet: Option<PathBuf>,
+
+    /// A directory of templates that override the built-in ones, from `template-dir`.
+    pub template_dir: Option<PathBuf>,
+
+    /// Whether items that are not public are documented, from `document-private-items`.
+    pub document_private_items: bool,
+
+    /// Whether items hidden with `#[doc(hidden)]` are documented, from `document-hidden-items`.
+    pub document_hidden_items: bool,
+
+    /// The directory to write the documentation to, from `output`.
+    pub output: Option<PathBuf>,
+
+    /// The format of the documentation, such as `html` or `markdown`, from `format`.
+    pub format: Option<String>,
+
+    /// Whether compressed copies of the documentation are written, from `precompress`.
+    pub precompress: bool,
+
+    /// The path of an archive to package the documentation into, from `archive`.
+    pub archive: Option<PathBuf>,
+
+    /// The number of output files that may be open at the same time, from `max-open-files`.
+    pub max_open_files: Option<usize>,
+
+    /// The number of threads that write the files of a render, from `write-threads`.
+    pub write_threads: Option<usize>,
+
+    /// The bytes of rendered pages that may wait for the write threads, from `memory-limit`.
+    pub memory_limit: Option<usize>,
+
+    /// Whether peak memory use is lowered at the cost of wall-clock time, from `low-memory`.
+    pub low_memory: bool,
+
+    /// Whether fragments repeated on every item page are shared, from `share-fragments`.
+    pub share_fragments: bool,
+
+    /// Whether the documentation is rendered under the version of the crate, from `versioned`.
+    pub versioned: bool,
+
+    /// The version to render the documentation under instead, from `doc-version`.
+    #[serde(rename = "doc-version")]
+    pub version: Option<String>,
+
+    /// The document of an older release that API changes are listed since, from
+    /// `changes-since`.
+    pub changes_since: Option<PathBuf>,
+
+    /// The documents of dependencies rendered along with the documentation, from
+    /// `dependencies`.
+    pub dependencies: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Parses the contents of a `rustdoc-static.toml`.
+    pub fn parse(toml: &str) -> Result<Config> {
+        toml::from_str(toml).map_err(|e| ErrorKind::Config(e.to_string()).into())
+    }
+
+    /// Parses the `[package.metadata.rustdoc-static]` table of the contents of a `Cargo.toml`,
+    /// returning `None` if the manifest has no such table.
+    pub fn parse_cargo_manifest(toml: &str) -> Result<Option<Config>> {
+        let manifest: Manifest =
+            toml::from_str(toml).map_err(|e| Error::from(ErrorKind::Config(e.to_string())))?;
+        Ok(manifest.package.and_then(|package| package.metadata?.rustdoc_static))
+    }
+
+    /// Reads a configuration file, which is parsed as a `Cargo.toml` if it is named like one.
+    ///
+    /// Relative paths in the file are resolved against the directory of the file.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Config> {
+        let path = path.as_ref();
+
+        let load = || -> Result<Config> {
+            let mut toml = String::new();
+            File::open(path)?.read_to_string(&mut toml)?;
+
+            let config = if path.file_name() == Some("Cargo.toml".as_ref()) {
+                Config::parse_cargo_manifest(&toml)?.unwrap_or_default()
+            } else {
+                Config::parse(&toml)?
+            };
+            Ok(config.relative_to(path.parent().unwrap_or_else(|| Path::new(""))))
+        };
+
+        load().chain_err(|| format!("could not load `{}`", path.display()))
+    }
+
+    /// Looks for the configuration of the crate in a directory, in a `rustdoc-static.toml` or
+    /// else in the `Cargo.toml`. Returns `None` if neither file configures the render.
+    pub fn discover<P: AsRef<Path>>(dir: P) -> Result<Option<Config>> {
+        let dir = dir.as_ref();
+
+        let config_file = dir.join(CONFIG_FILE);
+        if config_file.is_file() {
+            return Config::from_path(config_file).map(Some);
+        }
+
+        let manifest = dir.join("Cargo.toml");
+        if manifest.is_file() {
+            let mut toml = String::new();
+            File::open(&manifest)
+                .and_then(|mut file| file.read_to_string(&mut toml))
+                .chain_err(|| format!("could not load `{}`", manifest.display()))?;
+
+            let config = Config::parse_cargo_manifest(&toml)
+                .chain_err(|| format!("could not load `{}`", manifest.display()))?;
+            return Ok(config.map(|config| config.relative_to(dir)));
+        }
+
+        Ok(None)
+    }
+
+    /// Applies the configuration to the options of a render.
+    ///
+    /// The output settings, such as `output` and `archive`, are not options of a render, and are
+    /// left to the caller. Returns an error if the document of `changes-since` or of a dependency
+    /// cannot be loaded.
+    pub fn render_options(&self, mut options: RenderOptions) -> Result<RenderOptions> {
+        if let Some(ref theme) = self.theme {
+            options = options.theme(theme.clone());
+        }
+        if let Some(ref lang) = self.lang {
+            options = options.lang(lang.clone());
+        }
+        if let Some(ref base_url) = self.base_url {
+            options = options.base_url(base_url.clone());
+        }
+        if let Some(ref template_dir) = self.template_dir {
+            options = options.template_dir(template_dir.clone());
+        }
+        for glob in &self.include {
+            options = options.include(glob.clone());
+        }
+        for glob in &self.exclude {
+            options = options.exclude(glob.clone());
+        }
+        if self.robots_txt {
+            options = options.robots_txt(true);
+        }
+        for path in &self.robots_disallow {
+            options = options.robots_disallow(path.clone());
+        }
+        if self.csp_headers {
+            options = options.csp_headers(true);
+        }
+        if self.service_worker {
+            options = options.service_worker(true);
+        }
+        if self.content_hashes {
+            options = options.content_hashes(true);
+        }
+        for stylesheet in &self.extra_css {
+            options = options.extra_css(stylesheet.clone());
+        }
+        if let Some(ref readme) = self.readme {
+            options = options.readme(readme.clone());
+        }
+        if self.readme_replaces_docs {
+            options = options.readme_replaces_docs(true);
+        }
+        if let Some(ref source_dir) = self.source_dir {
+            options = options.source_dir(source_dir.clone());
+        }
+        if let Some(ref logo_url) = self.logo_url {
+            options = options.logo_url(logo_url.clone());
+        }
+        if let Some(ref favicon_url) = self.favicon_url {
+            options = options.favicon_url(favicon_url.clone());
+        }
+        if let Some(ref analytics) = self.analytics {
+            options = options.analytics(analytics.clone());
+        }
+        if let Some(ref snippet) = self.analytics_snippet {
+            options = options.analytics_snippet(snippet.clone());
+        }
+
+        if self.document_private_items {
+            options = options.document_private_items(true);
+        }
+        if self.document_hidden_items {
+            options = options.document_hidden_items(true);
+        }
+
+        if let Some(max) = self.max_open_files {
+            options = options.max_open_files(max);
+        }
+        if let Some(threads) = self.write_threads {
+            options = options.write_threads(threads);
+        }
+        if let Some(bytes) = self.memory_limit {
+            options = options.memory_limit(bytes);
+        }
+        if self.low_memory {
+            options = options.low_memory(true);
+        }
+        if self.share_fragments {
+            options = options.share_fragments(true);
+        }
+        if self.versioned {
+            options = options.versioned(true);
+        }
+        if let Some(ref version) = self.version {
+            options = options.version(version.clone());
+        }
+        if let Some(ref old) = self.changes_since {
+            options = options.changes_since(load::from_path(old)?);
+        }
+        for dependency in &self.dependencies {
+            options = options.dependency(load::from_path(dependency)?);
+        }
+
+        Ok(options)
+    }
+
+    /// Resolves the relative paths of the configuration against a directory.
+    fn relative_to(mut self, dir: &Path) -> Config {
+        let resolve = |path: &mut PathBuf| *path = dir.join(&*path);
+
+        self.extra_css.iter_mut().for_each(&resolve);
+        self.readme.iter_mut().for_each(&resolve);
+        self.source_dir.iter_mut().for_each(&resolve);
+        self.analytics_snippet.iter_mut().for_each(&resolve);
+        self.template_dir.iter_mut().for_each(&resolve);
+        self.output.iter_mut().for_each(&resolve);
+        self.archive.iter_mut().for_each(&resolve);
+        self.changes_since.iter_mut().for_each(&resolve);
+        self.dependencies.iter_mut().for_each(&resolve);
+
+        // Logos and favicons are URLs unless they name a file.
+        let resolve_file = |url: &mut String| {
+            let path = dir.join(&*url);
+            if path.is_file() {
+                *url = path.to_string_lossy().into_owned();
+            }
+        };
+        self.logo_url.iter_mut().for_each(&resolve_file);
+        self.favicon_url.iter_mut().for_each(&resolve_file);
+        self
+    }
+}
+
+/// The parts of a `Cargo.toml` that the configuration is read from.
+#[derive(Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    metadata: Option<Metadata>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    #[serde(rename = "rustdoc-static")]
+    rustdoc_static: Option<Config>,
+}
+
+/// Deserializes an analytics service from a string such as `plausible:docs.example.com`.
+fn analytics<'de, D>(deserializer: D) -> ::std::result::Result<Option<Analytics>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let analytics = String::deserialize(deserializer)?;
+    let analytics = analytics.parse::<Analytics>();
+    analytics.map(Some).map_err(|e| de::Error::custom(format!("invalid analytics: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::Config;
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            r#"
+            # The defaults for the docs.
+            theme = "dark"
+            base-url = 'https://docs.example.com/' # with a comment
+            exclude = [
+                "my_crate::internal",
+                "my_crate::*::tests",
+            ]
+            document-private-items = true
+            max-open-files = 64
+            memory-limit = 268435456
+            versioned = true
+            "#,
+        ).unwrap();
+
+        assert_eq!(config.theme, Some(String::from("dark")));
+        assert_eq!(config.base_url, Some(String::from("https://docs.example.com/")));
+        assert_eq!(config.exclude, ["my_crate::internal", "my_crate::*::tests"]);
+        assert!(config.document_private_items);
+        assert_eq!(config.max_open_files, Some(64));
+        assert_eq!(config.memory_limit, Some(268_435_456));
+        assert!(config.versioned);
+
+        let error = Config::parse("theme = true").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid configuration: invalid type: boolean `true`, expected a string for key \
+             `theme` at line 1 column 9"
+        );
+        let error = Config::parse("\nthemes = \"dark\"").unwrap_err().to_string();
+        assert!(error.starts_with("invalid configuration: unknown field `themes`"), "{}", error);
+        let error = Config::parse("analytics = \"plausible\"").unwrap_err().to_string();
+        assert!(error.contains("must be of the form `service:id`"), "{}", error);
+
+        let error = Config::parse("[search]\nenabled = true").unwrap_err().to_string();
+        assert!(error.contains("unknown field `search`"), "{}", error);
+    }
+
+    #[test]
+    fn parse_cargo_manifest() {
+        let manifest = r#"
+            [package]
+            name = "my_crate"
+            description = """
+            A crate.
+            """
+
+            [package.metadata.rustdoc-static]
+            extra-css = ["docs/custom.css"]
+            output = "target/docs"
+            source-dir = "."
+
+            [dependencies]
+            serde = { version = "1", features = ["derive"] }
+        "#;
+
+        let config = Config::parse_cargo_manifest(manifest).unwrap().unwrap();
+        let config = config.relative_to(Path::new("/my_crate"));
+        assert_eq!(config.extra_css, [PathBuf::from("/my_crate/docs/custom.css")]);
+        assert_eq!(config.output, Some(PathBuf::from("/my_crate/target/docs")));
+        assert_eq!(config.source_dir, Some(PathBuf::from("/my_crate/.")));
+
+        assert_eq!(Config::parse_cargo_manifest("[package]\nname = \"my_crate\"").unwrap(), None);
+
+        // Multi-line strings may hold lines that look like headers and comments.
+        let manifest = r#"
+            [package]
+            name = "my_crate"
+            description = """
+            [package.metadata.rustdoc-static]
+            # theme = "dark"
+            """
+
+            [package.metadata]
+            rustdoc-static = { theme = "light", exclude = ["my_crate::internal"] }
+        "#;
+        let config = Config::parse_cargo_manifest(manifest).unwrap().unwrap();
+        assert_eq!(config.theme, Some(String::from("light")));
+        assert_eq!(config.exclude, ["my_crate::internal"]);
+
+        let manifest = r#"
+            [package]
+            name = "my_crate"
+            metadata.rustdoc-static.theme = "dark"
+            metadata.rustdoc-static.robots-txt = true
+        "#;
+        let config = Config::parse_cargo_manifest(manifest).unwrap().unwrap();
+        assert_eq!(config.theme, Some(String::from("dark")));
+        assert!(config.robots_txt);
+
+        let manifest = r#"
+            [package.metadata.rustdoc-static]
+            theme = "dark"
+
+            [package.metadata.rustdoc-static.search]
+            enabled = true
+        "#;
+        let error = Config::parse_cargo_manifest(manifest).unwrap_err().to_string();
+        assert!(error.contains("unknown field `search`"), "{}", error);
+    }
+}
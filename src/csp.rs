@@ -0,0 +1,58 @@
+//! A recommended Content Security Policy for hosted documentation.
+//!
+//! Pages load every script and stylesheet from files under the doc root, and have no inline
+//! scripts, styles or event handlers, so the policy only allows the doc site itself, along with
+//! the analytics service and images from other sites that docs often embed. The policy is written
+//! to `_headers` at the doc root, in the format that Netlify and Cloudflare Pages read, and can be
+//! copied into the configuration of other hosts.
+
+use analytics::Analytics;
+
+/// The name of the file of headers, at the doc root.
+pub const HEADERS: &str = "_headers";
+
+/// Returns the recommended policy for pages that add the snippet of `analytics`, if any.
+pub fn policy(analytics: Option<&Analytics>) -> String {
+    let origins = analytics.map(Analytics::origins).unwrap_or_default();
+    let with_origins = |sources: &str| {
+        origins.iter().fold(String::from(sources), |sources, origin| sources + " " + origin)
+    };
+
+    [
+        String::from("default-src 'none'"),
+        format!("script-src {}", with_origins("'self'")),
+        String::from("style-src 'self'"),
+        String::from("img-src 'self' data: https:"),
+        format!("connect-src {}", with_origins("'self'")),
+        String::from("base-uri 'none'"),
+        String::from("form-action 'none'"),
+        String::from("frame-ancestors 'none'"),
+    ].join("; ")
+}
+
+/// Renders the `_headers` file that sends `policy` with every page under `prefix`, the path that
+/// the documentation is hosted at.
+pub(crate) fn headers(prefix: &str, policy: &str) -> String {
+    format!(
+        "{}/*\n  Content-Security-Policy: {}\n  X-Content-Type-Options: nosniff\n",
+        prefix.trim_end_matches('/'),
+        policy
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn policy() {
+        let analytics = "plausible:docs.example.com".parse().unwrap();
+        let policy = super::policy(Some(&analytics));
+        assert!(policy.starts_with("default-src 'none'; script-src 'self' https://plausible.io;"));
+        assert!(!super::policy(None).contains("unsafe-inline"));
+
+        assert_eq!(
+            super::headers("/docs/", "default-src 'none'"),
+            "/docs/*\n  Content-Security-Policy: default-src 'none'\n  \
+             X-Content-Type-Options: nosniff\n"
+        );
+    }
+}
@@ -0,0 +1,396 @@
+//! Comparison of the public APIs documented by two documents, such as those of two releases of a
+//! crate.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use jsonapi::api::{JsonApiDocument, Resource};
+use serde_json::Value;
+
+use errors::*;
+use options::RenderOptions;
+use {
+    crate_metadata, create_handlebars, hidden_items, path_for_resource, render_to_writer,
+    resources,
+};
+
+/// The attributes that do not affect the API of an item, and are not compared.
+const IGNORED_ATTRIBUTES: &[&str] = &["docs", "source"];
+
+/// The page that lists the changes, at the doc root.
+pub(crate) const CHANGES_PAGE: &str = "changes.html";
+
+/// The part of the version of a crate that a change to its API requires to be increased, under
+/// semantic versioning.
+///
+/// Bumps are ordered by how much of the API they may break, so the bump that a release requires
+/// is the largest bump of its changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Bump {
+    /// The change does not affect code that uses the crate.
+    Patch,
+
+    /// The change adds to the API without breaking code that uses the crate.
+    Minor,
+
+    /// The change may break code that uses the crate.
+    Major,
+}
+
+impl Bump {
+    /// Returns the version that follows a version with this bump, such as `2.0.0` for a major
+    /// bump of `1.4.2`, or `None` if the version is not of the form `MAJOR.MINOR.PATCH`.
+    ///
+    /// Like Cargo, the first part of the version that is not zero is treated as the major
+    /// version, so a breaking change to `0.3.1` requires `0.4.0`.
+    pub fn next_version(self, version: &str) -> Option<String> {
+        let release = version.split(['-', '+']).next().unwrap_or(version);
+        let parts = release.split('.').map(str::parse).collect::<Vec<_>>();
+        let (major, minor, patch) = match parts[..] {
+            [Ok(major), Ok(minor), Ok(patch)] => (major, minor, patch),
+            _ => return None,
+        };
+
+        let (major, minor, patch): (u64, u64, u64) = match (self, major, minor) {
+            (_, 0, 0) => (0, 0, patch + 1),
+            (Bump::Major, 0, _) => (0, minor + 1, 0),
+            (_, 0, _) => (0, minor, patch + 1),
+            (Bump::Major, ..) => (major + 1, 0, 0),
+            (Bump::Minor, ..) => (major, minor + 1, 0),
+            (Bump::Patch, ..) => (major, minor, patch + 1),
+        };
+        Some(format!("{}.{}.{}", major, minor, patch))
+    }
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Bump::Patch => "patch",
+            Bump::Minor => "minor",
+            Bump::Major => "major",
+        })
+    }
+}
+
+/// An item of the API that was added, removed or changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItemChange {
+    /// The path of the item, such as `my_crate::Foo`.
+    pub id: String,
+
+    /// The kind of the item, such as `struct`.
+    pub kind: String,
+
+    /// The page of the item in the documentation of the new document, relative to the doc root.
+    /// Removed items have no page.
+    pub page: Option<PathBuf>,
+
+    /// The attributes of a changed item whose values changed, such as `signature`, or `type` if
+    /// the kind of the item changed. Empty for added and removed items.
+    pub attributes: Vec<String>,
+
+    /// The bump of the version that the change requires.
+    pub bump: Bump,
+}
+
+/// The differences between the public APIs of two documents.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApiDiff {
+    /// The items that are only in the new document, in the order of the new document.
+    pub added: Vec<ItemChange>,
+
+    /// The items that are only in the old document, in the order of the old document.
+    pub removed: Vec<ItemChange>,
+
+    /// The items in both documents that are only deprecated in the new document, in the order of
+    /// the new document.
+    pub deprecated: Vec<ItemChange>,
+
+    /// The items in both documents whose attributes differ, other than by being deprecated, in
+    /// the order of the new document.
+    pub changed: Vec<ItemChange>,
+
+    /// The version of the crate of the old document, if it has one.
+    pub old_version: Option<String>,
+}
+
+impl ApiDiff {
+    /// Returns whether the public APIs of the documents are the same.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.deprecated.is_empty()
+            && self.changed.is_empty()
+    }
+
+    /// Returns the smallest bump of the version that every change requires, which is a patch
+    /// bump if the public API did not change.
+    pub fn bump(&self) -> Bump {
+        let changes = self.added.iter().chain(&self.removed).chain(&self.deprecated);
+        changes.chain(&self.changed).map(|change| change.bump).max().unwrap_or(Bump::Patch)
+    }
+
+    /// Returns the smallest version that the new document can be released as, if the old
+    /// document has a version of the form `MAJOR.MINOR.PATCH`.
+    pub fn next_version(&self) -> Option<String> {
+        self.bump().next_version(self.old_version.as_ref()?)
+    }
+}
+
+/// Compares the public items of two documents.
+///
+/// Items are public unless they, or an item they are in, are private or hidden with
+/// `#[doc(hidden)]`. Changes to the docs and source locations of items are not API changes, and
+/// are ignored. Fails if the attributes of an item of either document have the wrong shape.
+pub fn diff(old: &JsonApiDocument, new: &JsonApiDocument) -> Result<ApiDiff> {
+    let old_items = public_items(old)?;
+    let new_items = public_items(new)?;
+    let old_by_id = old_items.iter().map(|r| (&r.id[..], *r)).collect::<HashMap<_, _>>();
+    let new_ids = new_items.iter().map(|r| &r.id[..]).collect::<HashSet<_>>();
+
+    let change = |resource: &Resource, page, attributes, bump| ItemChange {
+        id: resource.id.clone(),
+        kind: resource._type.clone(),
+        page,
+        attributes,
+        bump,
+    };
+
+    let old_version = crate_metadata(old)["version"].as_str().map(String::from);
+    let mut diff = ApiDiff { old_version, ..ApiDiff::default() };
+    for &resource in &new_items {
+        let page = path_for_resource(resource);
+
+        match old_by_id.get(&resource.id[..]) {
+            None => diff.added.push(change(resource, page, vec![], Bump::Minor)),
+            Some(old) => {
+                let mut attributes = changed_attributes(old, resource);
+                let is_deprecated = |resource: &Resource| {
+                    resource.attributes.get("deprecated").is_some_and(|value| !value.is_null())
+                };
+                if !is_deprecated(old) && is_deprecated(resource) {
+                    attributes.retain(|name| name != "deprecated");
+                    diff.deprecated.push(change(resource, page.clone(), vec![], Bump::Minor));
+                }
+                if !attributes.is_empty() {
+                    let bump = attributes.iter().map(|name| attribute_bump(name)).max();
+                    let bump = bump.unwrap_or(Bump::Patch);
+                    diff.changed.push(change(resource, page, attributes, bump));
+                }
+            }
+        }
+    }
+
+    for &resource in old_items.iter().filter(|r| !new_ids.contains(&r.id[..])) {
+        diff.removed.push(change(resource, None, vec![], Bump::Major));
+    }
+
+    Ok(diff)
+}
+
+/// Returns the bump of the version that a change to an attribute of an item requires.
+///
+/// The kind, signature, return type and `cfg` of an item are part of how it is used, so changing
+/// them may break code. Changes to the deprecation of an item only affect warnings. Changes to
+/// other attributes, such as an item becoming stable, are assumed to add to the API.
+fn attribute_bump(name: &str) -> Bump {
+    match name {
+        "type" | "signature" | "output" | "cfg" => Bump::Major,
+        "deprecated" => Bump::Patch,
+        _ => Bump::Minor,
+    }
+}
+
+/// Renders a page listing the changes of a diff as `changes.html` at the doc root, linking to the
+/// pages of the added and changed items.
+pub fn write_changes_page<P: AsRef<Path>>(diff: &ApiDiff, doc_root: P) -> Result<()> {
+    let doc_root = doc_root.as_ref();
+    let handlebars = create_handlebars();
+
+    fs::create_dir_all(doc_root)?;
+    let path = doc_root.join(CHANGES_PAGE);
+    info!("rendering API changes as `{}`", path.display());
+    render_to_writer(&handlebars, "changes", &changes_context(diff), File::create(path)?)
+}
+
+/// Returns the context of the `changes` template for a diff, rendered at the doc root.
+///
+/// Changed items whose signature changed are listed apart from the items that changed in other
+/// ways, since they are the changes that are most likely to break code.
+pub(crate) fn changes_context(diff: &ApiDiff) -> Value {
+    let changes = |changes: &mut dyn Iterator<Item = &ItemChange>| {
+        changes
+            .map(|change| {
+                let page = change.page.as_ref().map(|page| page.to_string_lossy().into_owned());
+                json!({
+                    "id": change.id,
+                    "kind": change.kind,
+                    "page": page,
+                    "attributes": change.attributes.join(", "),
+                    "bump": change.bump.to_string(),
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+    let changes_signature =
+        |change: &&ItemChange| change.attributes.iter().any(|name| name == "signature");
+
+    json!({
+        "pathToRoot": ".",
+        "added": changes(&mut diff.added.iter()),
+        "removed": changes(&mut diff.removed.iter()),
+        "deprecated": changes(&mut diff.deprecated.iter()),
+        "signatureChanged": changes(&mut diff.changed.iter().filter(changes_signature)),
+        "changed": changes(&mut diff.changed.iter().filter(|change| !changes_signature(change))),
+        "unchanged": diff.is_empty(),
+        "bump": diff.bump().to_string(),
+        "nextVersion": diff.next_version(),
+    })
+}
+
+/// Returns the public resources of a document.
+fn public_items(document: &JsonApiDocument) -> Result<Vec<&Resource>> {
+    let hidden = hidden_items(document, &RenderOptions::new())?;
+    let hidden = hidden.into_iter().collect::<HashSet<_>>();
+
+    let public = resources(document)
+        .filter(|resource| {
+            let id = &resource.id[..];
+            let ancestors = id.match_indices("::").map(|(end, _)| &id[..end]);
+            !ancestors.chain(Some(id)).any(|path| hidden.contains(path))
+        })
+        .collect();
+    Ok(public)
+}
+
+/// Returns the names of the attributes that differ between two versions of an item.
+fn changed_attributes(old: &Resource, new: &Resource) -> Vec<String> {
+    let mut changed = vec![];
+    if old._type != new._type {
+        changed.push(String::from("type"));
+    }
+
+    let names = new.attributes.keys().chain(old.attributes.keys().filter(|name| {
+        !new.attributes.contains_key(*name)
+    }));
+    for name in names.filter(|name| !IGNORED_ATTRIBUTES.contains(&&name[..])) {
+        let old_value = old.attributes.get(name).filter(|value| !value.is_null());
+        let new_value = new.attributes.get(name).filter(|value| !value.is_null());
+        if old_value != new_value {
+            changed.push(name.clone());
+        }
+    }
+
+    changed.sort();
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use jsonapi::api::JsonApiDocument;
+
+    use super::{Bump, ItemChange};
+
+    fn document(included: &str) -> JsonApiDocument {
+        JsonApiDocument::from_str(&format!(
+            r#"{{
+                "data": {{ "type": "crate", "id": "test_crate", "attributes": {{}} }},
+                "included": [{}]
+            }}"#,
+            included
+        )).unwrap()
+    }
+
+    #[test]
+    fn diff() {
+        let old = document(
+            r#"
+            { "type": "function", "id": "test_crate::kept", "attributes": { "docs": "Old." } },
+            {
+                "type": "function",
+                "id": "test_crate::changed",
+                "attributes": { "signature": "pub fn changed()" }
+            },
+            { "type": "struct", "id": "test_crate::Removed", "attributes": {} },
+            { "type": "function", "id": "test_crate::old", "attributes": {} },
+            {
+                "type": "struct",
+                "id": "test_crate::Private",
+                "attributes": { "visibility": "crate" }
+            }
+            "#,
+        );
+        let new = document(
+            r#"
+            { "type": "function", "id": "test_crate::kept", "attributes": { "docs": "New." } },
+            {
+                "type": "function",
+                "id": "test_crate::changed",
+                "attributes": { "signature": "pub fn changed(x: u32)" }
+            },
+            { "type": "struct", "id": "test_crate::Added", "attributes": {} },
+            {
+                "type": "function",
+                "id": "test_crate::old",
+                "attributes": { "deprecated": { "since": "1.1.0", "note": null } }
+            }
+            "#,
+        );
+
+        let diff = super::diff(&old, &new).unwrap();
+        let change = |id: &str, kind: &str, page: Option<&str>, attributes: &[&str], bump| {
+            ItemChange {
+                id: String::from(id),
+                kind: String::from(kind),
+                page: page.map(PathBuf::from),
+                attributes: attributes.iter().map(|&name| String::from(name)).collect(),
+                bump,
+            }
+        };
+        let added = Some("test_crate/struct.Added.html");
+        assert_eq!(diff.added, [change("test_crate::Added", "struct", added, &[], Bump::Minor)]);
+        assert_eq!(
+            diff.removed,
+            [change("test_crate::Removed", "struct", None, &[], Bump::Major)]
+        );
+        let page = Some("test_crate/fn.old.html");
+        assert_eq!(
+            diff.deprecated,
+            [change("test_crate::old", "function", page, &[], Bump::Minor)]
+        );
+        assert_eq!(
+            diff.changed,
+            [change(
+                "test_crate::changed",
+                "function",
+                Some("test_crate/fn.changed.html"),
+                &["signature"],
+                Bump::Major
+            )]
+        );
+        assert_eq!(diff.bump(), Bump::Major);
+        assert!(super::diff(&new, &new).unwrap().is_empty());
+        assert_eq!(super::diff(&new, &new).unwrap().bump(), Bump::Patch);
+
+        let context = super::changes_context(&diff);
+        assert_eq!(context["signatureChanged"][0]["id"], "test_crate::changed");
+        assert_eq!(context["removed"][0]["bump"], "major");
+        assert_eq!(context["changed"], json!([]));
+    }
+
+    #[test]
+    fn next_version() {
+        assert_eq!(Bump::Major.next_version("1.4.2"), Some(String::from("2.0.0")));
+        assert_eq!(Bump::Minor.next_version("1.4.2"), Some(String::from("1.5.0")));
+        assert_eq!(Bump::Patch.next_version("1.4.2-beta.1"), Some(String::from("1.4.3")));
+        assert_eq!(Bump::Major.next_version("0.3.1"), Some(String::from("0.4.0")));
+        assert_eq!(Bump::Minor.next_version("0.3.1"), Some(String::from("0.3.2")));
+        assert_eq!(Bump::Major.next_version("0.0.7"), Some(String::from("0.0.8")));
+        assert_eq!(Bump::Major.next_version("main"), None);
+    }
+}
@@ -1,7 +1,40 @@
+//! Error types returned by the renderers.
+
+// The code generated by `error_chain!` uses deprecated `Error` methods.
+#![allow(deprecated)]
+
 use std::io;
+use std::path::PathBuf;
 
 error_chain! {
     foreign_links {
         Io(io::Error);
     }
+
+    errors {
+        Template(template: String) {
+            description("could not render template")
+            display("could not render template `{}`", template)
+        }
+
+        Parse {
+            description("could not parse the document as JSON API")
+            display("could not parse the document as JSON API")
+        }
+
+        InvalidDocument(reason: String) {
+            description("invalid document")
+            display("invalid document: {}", reason)
+        }
+
+        Config(reason: String) {
+            description("invalid configuration")
+            display("invalid configuration: {}", reason)
+        }
+
+        Path(path: PathBuf) {
+            description("invalid output path")
+            display("invalid output path `{}`", path.display())
+        }
+    }
 }
@@ -0,0 +1,300 @@
+//! An Atom feed of the changes to the public API of a crate in each release, for versioned
+//! documentation.
+//!
+//! The changes of each version are kept in `changes.json` at the shared root of the versions, as
+//! they are listed when the version is rendered, and the feed is written from them to `feed.xml`
+//! next to it.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use handlebars::html_escape;
+use serde_json::{self, Value};
+
+use diff::ApiDiff;
+use errors::*;
+use versions;
+
+/// The name of the file that keeps the changes of every version, at the shared root.
+pub const HISTORY: &str = "changes.json";
+
+/// The name of the feed, at the shared root.
+pub const FEED: &str = "feed.xml";
+
+/// The time that a feed without releases was updated.
+const EPOCH: &str = "1970-01-01T00:00:00Z";
+
+/// The changes to the public API in a release, by the paths of the items.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Release {
+    /// The version of the release, which is also its directory under the shared root.
+    pub version: String,
+
+    /// When the changes of the release were first recorded, in RFC 3339 format.
+    pub updated: String,
+
+    /// The items that were added.
+    pub added: Vec<String>,
+
+    /// The items that were removed.
+    pub removed: Vec<String>,
+
+    /// The items that were deprecated.
+    pub deprecated: Vec<String>,
+
+    /// The items that changed in other ways.
+    pub changed: Vec<String>,
+}
+
+impl Release {
+    /// Returns a one-line summary of the changes, such as `2 added, 1 removed`.
+    pub fn summary(&self) -> String {
+        let counts = [
+            (self.added.len(), "added"),
+            (self.removed.len(), "removed"),
+            (self.deprecated.len(), "deprecated"),
+            (self.changed.len(), "changed"),
+        ];
+        let counts = counts
+            .iter()
+            .filter(|&&(count, _)| count > 0)
+            .map(|&(count, change)| format!("{} {}", count, change))
+            .collect::<Vec<_>>();
+
+        if counts.is_empty() {
+            String::from("No API changes")
+        } else {
+            counts.join(", ")
+        }
+    }
+
+    /// Returns the release as it is kept in `changes.json`.
+    fn to_json(&self) -> Value {
+        json!({
+            "version": self.version,
+            "updated": self.updated,
+            "added": self.added,
+            "removed": self.removed,
+            "deprecated": self.deprecated,
+            "changed": self.changed,
+        })
+    }
+
+    /// Reads a release kept in `changes.json`, if it has every field.
+    fn from_json(json: &Value) -> Option<Release> {
+        let strings = |key: &str| -> Option<Vec<String>> {
+            json[key].as_array()?.iter().map(|s| s.as_str().map(String::from)).collect()
+        };
+
+        Some(Release {
+            version: String::from(json["version"].as_str()?),
+            updated: String::from(json["updated"].as_str()?),
+            added: strings("added")?,
+            removed: strings("removed")?,
+            deprecated: strings("deprecated")?,
+            changed: strings("changed")?,
+        })
+    }
+}
+
+/// Returns the releases recorded under a shared root, newest first.
+pub fn releases<P: AsRef<Path>>(root: P) -> Result<Vec<Release>> {
+    let path = root.as_ref().join(HISTORY);
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+
+    let contents = fs::read(&path).chain_err(|| format!("could not read `{}`", path.display()))?;
+    let invalid = || format!("`{}` is not a list of releases", path.display());
+    let json = serde_json::from_slice::<Value>(&contents).chain_err(invalid)?;
+    let releases = json.as_array().ok_or_else(invalid)?;
+    let releases = releases.iter().map(Release::from_json).collect::<Option<Vec<_>>>();
+    releases.ok_or_else(|| invalid().into())
+}
+
+/// Records the changes of a release under a shared root, and writes the feed of every release
+/// recorded there.
+///
+/// A release that was already recorded keeps the time it was first recorded, so that rendering it
+/// again does not move it to the top of feed readers. The links of the feed are relative to the
+/// feed, unless the shared root is hosted at `base_url`.
+pub fn record<P: AsRef<Path>>(
+    root: P,
+    krate: &str,
+    version: &str,
+    diff: &ApiDiff,
+    base_url: Option<&str>,
+) -> Result<Vec<Release>> {
+    let root = root.as_ref();
+    let mut releases = releases(root)?;
+
+    let ids = |changes: &[::diff::ItemChange]| changes.iter().map(|c| c.id.clone()).collect();
+    let mut release = Release {
+        version: String::from(version),
+        updated: now(),
+        added: ids(&diff.added),
+        removed: ids(&diff.removed),
+        deprecated: ids(&diff.deprecated),
+        changed: ids(&diff.changed),
+    };
+    if let Some(position) = releases.iter().position(|r| r.version == version) {
+        release.updated = releases.remove(position).updated;
+    }
+    releases.push(release);
+    releases.sort_by(|a, b| versions::compare(&b.version, &a.version));
+
+    let history = Value::Array(releases.iter().map(Release::to_json).collect());
+    let path = root.join(HISTORY);
+    fs::write(&path, serde_json::to_string_pretty(&history).unwrap())
+        .chain_err(|| format!("could not write `{}`", path.display()))?;
+
+    let path = root.join(FEED);
+    info!("writing the feed of API changes to `{}`", path.display());
+    fs::write(&path, feed(krate, &releases, base_url))
+        .chain_err(|| format!("could not write `{}`", path.display()))?;
+    Ok(releases)
+}
+
+/// Renders the Atom feed of the releases of a crate, newest first.
+pub(crate) fn feed(krate: &str, releases: &[Release], base_url: Option<&str>) -> String {
+    let base_url = base_url.map(|base_url| format!("{}/", base_url.trim_end_matches('/')));
+    let url = |path: &str| format!("{}{}", base_url.as_deref().unwrap_or(""), path);
+
+    let mut feed = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    feed.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    feed.push_str(&format!("  <title>API changes of {}</title>\n", html_escape(krate)));
+    feed.push_str(&format!("  <id>urn:rustdoc-static:{}</id>\n", html_escape(krate)));
+    let updated = releases.iter().map(|release| &release.updated[..]).max();
+    feed.push_str(&format!("  <updated>{}</updated>\n", updated.unwrap_or(EPOCH)));
+    if base_url.is_some() {
+        feed.push_str(&format!("  <link rel=\"self\" href=\"{}\"/>\n", html_escape(&url(FEED))));
+    }
+
+    for release in releases {
+        let mut content = String::new();
+        let sections = [
+            ("Added", &release.added),
+            ("Removed", &release.removed),
+            ("Deprecated", &release.deprecated),
+            ("Changed", &release.changed),
+        ];
+        for &(heading, items) in sections.iter().filter(|&&(_, items)| !items.is_empty()) {
+            content.push_str(&format!("{}:\n", heading));
+            for item in items {
+                content.push_str(&format!("  {}\n", item));
+            }
+        }
+
+        let link = url(&format!("{}/changes.html", release.version));
+        feed.push_str("  <entry>\n");
+        feed.push_str(&format!(
+            "    <title>{} {}</title>\n",
+            html_escape(krate),
+            html_escape(&release.version)
+        ));
+        feed.push_str(&format!(
+            "    <id>urn:rustdoc-static:{}:{}</id>\n",
+            html_escape(krate),
+            html_escape(&release.version)
+        ));
+        feed.push_str(&format!("    <updated>{}</updated>\n", release.updated));
+        feed.push_str(&format!("    <link href=\"{}\"/>\n", html_escape(&link)));
+        feed.push_str(&format!("    <summary>{}</summary>\n", release.summary()));
+        if !content.is_empty() {
+            let content = html_escape(&content);
+            feed.push_str(&format!("    <content type=\"text\">{}</content>\n", content));
+        }
+        feed.push_str("  </entry>\n");
+    }
+
+    feed.push_str("</feed>\n");
+    feed
+}
+
+/// Returns the current time in RFC 3339 format, or the time in `SOURCE_DATE_EPOCH` for
+/// reproducible builds.
+fn now() -> String {
+    let seconds = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |now| now.as_secs())
+        });
+    rfc3339(seconds)
+}
+
+/// Formats a number of seconds since the Unix epoch as a UTC time in RFC 3339 format.
+fn rfc3339(seconds: u64) -> String {
+    let (days, time) = (seconds / 86_400, seconds % 86_400);
+
+    // Converts days since the epoch to a civil date, from Howard Hinnant's `civil_from_days`.
+    let days = days as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096)
+        / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use diff::{ApiDiff, Bump, ItemChange};
+
+    #[test]
+    fn rfc3339() {
+        assert_eq!(super::rfc3339(0), "1970-01-01T00:00:00Z");
+        assert_eq!(super::rfc3339(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(super::rfc3339(1_700_000_000), "2023-11-14T22:13:20Z");
+    }
+
+    #[test]
+    fn record() {
+        let root = env::temp_dir().join("rustdoc-static-feed");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        let change = |id: &str| ItemChange {
+            id: String::from(id),
+            kind: String::from("struct"),
+            page: None,
+            attributes: vec![],
+            bump: Bump::Minor,
+        };
+        let diff = ApiDiff { added: vec![change("demo::Foo")], ..ApiDiff::default() };
+        super::record(&root, "demo", "1.1.0", &diff, None).unwrap();
+        let diff = ApiDiff { removed: vec![change("demo::Foo")], ..ApiDiff::default() };
+        let releases =
+            super::record(&root, "demo", "2.0.0", &diff, Some("https://example.com")).unwrap();
+
+        let versions = releases.iter().map(|release| &release.version[..]).collect::<Vec<_>>();
+        assert_eq!(versions, ["2.0.0", "1.1.0"]);
+        assert_eq!(super::releases(&root).unwrap(), releases);
+        assert_eq!(releases[0].summary(), "1 removed");
+
+        let feed = fs::read_to_string(root.join(super::FEED)).unwrap();
+        assert!(feed.contains("<title>demo 2.0.0</title>"));
+        assert!(feed.contains(r#"<link href="https://example.com/1.1.0/changes.html"/>"#));
+        assert!(feed.contains("<content type=\"text\">Added:\n  demo::Foo\n</content>"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+//! A map of the files of rendered documentation to hashes of their contents, so that deployment
+//! scripts can purge only the URLs that changed from a CDN.
+//!
+//! The map is written to `content-hashes.json` at the doc root:
+//!
+//! ```json
+//! {
+//!   "files": {
+//!     "my_crate/index.html": { "hash": "af63bd4c8601b7be", "changed": true },
+//!     "my_crate/struct.Foo.html": { "hash": "08b2d1d5e4f2935c", "changed": false }
+//!   },
+//!   "changed": ["my_crate/index.html"],
+//!   "removed": ["my_crate/struct.Bar.html"]
+//! }
+//! ```
+//!
+//! A file has changed if it is new, or if its hash differs from the one recorded by the previous
+//! render into the doc root. Files that were removed since are listed as well. Pages skipped by an
+//! incremental render keep the hash they were recorded with, without being read again.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::prelude::*;
+use std::io;
+use std::path::Path;
+
+use serde_json::{self, Value};
+
+/// The name of the map of content hashes, at the doc root.
+pub const CONTENT_HASHES: &str = "content-hashes.json";
+
+/// Returns the hash of the contents of a file, as 16 hexadecimal digits.
+///
+/// The hash is 64-bit FNV-1a rather than the hash of the manifest, so that it is the same no
+/// matter which version of rustdoc-static or of Rust rendered the file.
+pub fn content_hash(contents: &[u8]) -> String {
+    let mut writer = HashWriter::new(io::sink());
+    writer.update(contents);
+    writer.hash()
+}
+
+/// A writer that hashes the contents of a file as they are written through it, so that files
+/// that are streamed can be hashed without holding them in memory.
+pub(crate) struct HashWriter<W> {
+    inner: W,
+    hash: u64,
+}
+
+impl<W: Write> HashWriter<W> {
+    /// Wraps a writer, starting from the hash of an empty file.
+    pub fn new(inner: W) -> HashWriter<W> {
+        HashWriter { inner, hash: 0xcbf2_9ce4_8422_2325 }
+    }
+
+    /// Returns the hash of everything written so far, like `content_hash`.
+    pub fn hash(&self) -> String {
+        format!("{:016x}", self.hash)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.hash = bytes.iter().fold(self.hash, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    }
+}
+
+impl<W: Write> Write for HashWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Returns the hashes recorded by the previous render into a doc root, by the path of each file
+/// relative to the doc root. The map is empty if the doc root has none, or it cannot be read.
+pub fn previous(doc_root: &Path) -> BTreeMap<String, String> {
+    let path = doc_root.join(CONTENT_HASHES);
+    let json = match fs::read(&path) {
+        Ok(json) => json,
+        Err(_) => return BTreeMap::new(),
+    };
+    let map = match serde_json::from_slice::<Value>(&json) {
+        Ok(map) => map,
+        Err(e) => {
+            warn!("ignoring unreadable `{}`: {}", path.display(), e);
+            return BTreeMap::new();
+        }
+    };
+
+    map["files"]
+        .as_object()
+        .into_iter()
+        .flat_map(|files| files.iter())
+        .filter_map(|(file, entry)| Some((file.clone(), String::from(entry["hash"].as_str()?))))
+        .collect()
+}
+
+/// Renders the map of the files of a render to their hashes, given the hashes that the previous
+/// render recorded. Every file of the documentation is in `current`, by its path relative to the
+/// doc root.
+pub(crate) fn render(
+    previous: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+) -> String {
+    let mut files = serde_json::Map::new();
+    let mut changed = vec![];
+    for (file, hash) in current {
+        let is_changed = previous.get(file) != Some(hash);
+        if is_changed {
+            changed.push(file);
+        }
+        files.insert(file.clone(), json!({ "hash": hash, "changed": is_changed }));
+    }
+    let removed = previous.keys().filter(|file| !current.contains_key(*file)).collect::<Vec<_>>();
+
+    let map = json!({ "files": files, "changed": changed, "removed": removed });
+    serde_json::to_string_pretty(&map).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::io::prelude::*;
+
+    use serde_json::{self, Value};
+
+    #[test]
+    fn content_hash() {
+        assert_eq!(super::content_hash(b""), "cbf29ce484222325");
+        assert_eq!(super::content_hash(b"a"), "af63dc4c8601ec8c");
+
+        let mut writer = super::HashWriter::new(vec![]);
+        writer.write_all(b"a").unwrap();
+        assert_eq!(writer.hash(), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn render() {
+        let map = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|&(file, hash)| (String::from(file), String::from(hash)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let previous = map(&[("a.html", "1"), ("b.html", "2"), ("c.html", "3")]);
+        let current = map(&[("a.html", "1"), ("b.html", "4"), ("d.html", "5")]);
+
+        let rendered = super::render(&previous, &current);
+        let rendered = serde_json::from_str::<Value>(&rendered).unwrap();
+        assert_eq!(rendered["files"]["a.html"], json!({ "hash": "1", "changed": false }));
+        assert_eq!(rendered["changed"], json!(["b.html", "d.html"]));
+        assert_eq!(rendered["removed"], json!(["c.html"]));
+    }
+}
@@ -0,0 +1,101 @@
+//! Callbacks that embedders register on a `Renderer` to inspect or change what a render writes.
+
+use std::path::Path;
+
+use jsonapi::api::Resource;
+use serde_json::Value;
+
+use errors::Error;
+use report::{RenderReport, UnresolvedLink};
+use Progress;
+
+/// Something that happened during a render, for frontends that display the progress and problems
+/// of a render without parsing the log.
+#[derive(Debug)]
+pub enum RenderEvent<'a> {
+    /// The page of an item was rendered.
+    ItemRendered {
+        /// The ID of the item.
+        id: &'a str,
+
+        /// The path of the page.
+        path: &'a Path,
+    },
+
+    /// A relationship refers to a resource that the document does not include.
+    LinkUnresolved(&'a UnresolvedLink),
+
+    /// A template could not be rendered.
+    TemplateError {
+        /// The name of the template.
+        template: &'a str,
+
+        /// The path of the page that could not be rendered.
+        path: &'a Path,
+
+        /// The error that the render failed with.
+        error: &'a Error,
+    },
+}
+
+/// A callback that may change the context of an item page before it is rendered.
+type ContextHook = Box<dyn Fn(&Resource, &mut Value) + Send + Sync>;
+
+/// A callback that may change the HTML of an item page before it is written.
+type PageHook = Box<dyn Fn(&Resource, &mut String) + Send + Sync>;
+
+/// A callback that is given the report of a finished render.
+type ReportHook = Box<dyn Fn(&RenderReport) + Send + Sync>;
+
+/// A callback that is notified of the progress of a render.
+type ProgressHook = Box<dyn Fn(&Progress) + Send + Sync>;
+
+/// A callback that observes the events of a render.
+type EventHook = Box<dyn Fn(&RenderEvent) + Send + Sync>;
+
+/// The hooks registered on a renderer, run in the order they were registered.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub before_item: Vec<ContextHook>,
+    pub after_item: Vec<PageHook>,
+    pub after_all: Vec<ReportHook>,
+    pub on_event: Vec<EventHook>,
+    pub on_progress: Vec<ProgressHook>,
+}
+
+impl Hooks {
+    /// Runs the hooks that change the context of an item page.
+    pub fn before_item(&self, resource: &Resource, context: &mut Value) {
+        for hook in &self.before_item {
+            hook(resource, context);
+        }
+    }
+
+    /// Runs the hooks that change the HTML of an item page.
+    pub fn after_item(&self, resource: &Resource, html: &mut String) {
+        for hook in &self.after_item {
+            hook(resource, html);
+        }
+    }
+
+    /// Runs the hooks that are given the report of a finished render.
+    pub fn after_all(&self, report: &RenderReport) {
+        for hook in &self.after_all {
+            hook(report);
+        }
+    }
+
+    /// Notifies the progress callbacks that an item page was rendered.
+    pub fn progress(&self, progress: &Progress) {
+        for hook in &self.on_progress {
+            hook(progress);
+        }
+    }
+
+    /// Notifies the observers of an event.
+    pub fn emit(&self, event: &RenderEvent) {
+        for hook in &self.on_event {
+            hook(event);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! An index of the resources in a JSON-API document and their pages, built once before rendering.
+//!
+//! The paths of pages are worked out from the resources when they are needed rather than stored,
+//! since there is one for every resource of the document.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use jsonapi::api::{JsonApiDocument, Resource};
+
+use errors::*;
+use model::{self, Item};
+use {crates, identifiers, path_for_resource, resources};
+
+/// A JSON-API document along with a map from resource IDs to the included resources and to their
+/// typed views, and from resources to their siblings.
+///
+/// Looking up a resource by ID, or the parent of a resource, would otherwise scan every included
+/// resource, which makes rendering quadratic in the size of the crate. The keys borrow from the
+/// document, so each ID is stored only once no matter how often it is looked up.
+pub struct Index<'a> {
+    document: &'a JsonApiDocument,
+    resources: HashMap<&'a str, &'a Resource>,
+
+    /// Every distinct path segment of the IDs of the document and its dependencies, stored once,
+    /// such as `my_crate` or `Foo`.
+    segments: Vec<&'a str>,
+    segment_positions: HashMap<&'a str, u32>,
+
+    /// The path segments of each ID, as positions in `segments`.
+    ids: HashMap<&'a str, Box<[u32]>>,
+
+    /// The resources of dependencies that can be linked to, with the position of the directory
+    /// that the dependency is rendered into.
+    dependencies: HashMap<&'a str, (&'a Resource, usize)>,
+    dependency_dirs: Vec<PathBuf>,
+
+    /// The typed view of each resource, parsed once so that pages do not parse the attributes of
+    /// every resource they show again, or `None` if they are parsed whenever they are needed.
+    items: Option<HashMap<&'a str, Item<'a>>>,
+
+    /// The position of the parent of each resource among the resources of the document, which is
+    /// the first resource that has it in a relationship.
+    parents: HashMap<&'a str, usize>,
+
+    /// The children of each parent that have pages, by the position of the parent, sorted by kind
+    /// and then by ID.
+    children: HashMap<usize, Vec<&'a Resource>>,
+
+    has_deprecated: bool,
+}
+
+impl<'a> Index<'a> {
+    /// Indexes the included resources of a document, the pages of every resource and their typed
+    /// views, failing on the first resource whose attributes have the wrong shape.
+    pub fn parse(document: &'a JsonApiDocument) -> Result<Index<'a>> {
+        Ok(Index::with_items(document, Some(model::parse(document)?)))
+    }
+
+    /// Indexes a document like `parse`, but without keeping the typed views of its resources,
+    /// which are parsed again whenever they are needed. This trades time for the memory of the
+    /// typed views, for documents too large to hold them all.
+    ///
+    /// Every resource is still parsed once up front, so that the document fails on the first
+    /// resource whose attributes have the wrong shape, and later parses cannot fail.
+    pub fn on_demand(document: &'a JsonApiDocument) -> Result<Index<'a>> {
+        for resource in resources(document) {
+            Item::from_resource(resource)?;
+        }
+        Ok(Index::with_items(document, None))
+    }
+
+    /// Indexes a document like `parse`, leaving out the typed views of resources whose attributes
+    /// have the wrong shape, for checks that report the problems of a document themselves.
+    pub fn new(document: &'a JsonApiDocument) -> Index<'a> {
+        let items = resources(document).filter_map(|resource| Item::from_resource(resource).ok());
+        Index::with_items(document, Some(items.collect()))
+    }
+
+    fn with_items(document: &'a JsonApiDocument, items: Option<Vec<Item<'a>>>) -> Index<'a> {
+        let by_id = document
+            .included
+            .iter()
+            .flat_map(|included| included.iter())
+            .map(|resource| (resource.id.as_str(), resource))
+            .collect();
+
+        let mut index = Index {
+            document,
+            resources: by_id,
+            segments: vec![],
+            segment_positions: HashMap::new(),
+            ids: HashMap::new(),
+            dependencies: HashMap::new(),
+            dependency_dirs: vec![],
+            items: items.map(|items| items.into_iter().map(|item| (item.id, item)).collect()),
+            parents: HashMap::new(),
+            children: HashMap::new(),
+            has_deprecated: false,
+        };
+
+        for (position, parent) in resources(document).enumerate() {
+            index.intern(&parent.id);
+            let mut children = vec![];
+
+            for child in parent
+                .relationships
+                .iter()
+                .flat_map(|relationships| relationships.values())
+                .flat_map(|relationship| identifiers(&relationship.data))
+            {
+                index.parents.entry(child.id.as_str()).or_insert(position);
+                if let Some(child) = index.resource(&child.id) {
+                    if path_for_resource(child).is_some() {
+                        children.push(child);
+                    }
+                }
+            }
+
+            if !children.is_empty() {
+                children.sort_by(|a, b| (&a._type, &a.id).cmp(&(&b._type, &b.id)));
+                index.children.insert(position, children);
+            }
+
+            index.has_deprecated |= parent.attributes.contains_key("deprecated");
+        }
+
+        index
+    }
+
+    /// Adds the pages and typed views of the resources of a dependency, rendered into a directory
+    /// under the doc root, so that they can be linked to. Resources of the document take
+    /// precedence.
+    pub fn add_dependency(&mut self, dependency: &'a JsonApiDocument, dir: &Path) -> Result<()> {
+        let position = self.dependency_dirs.len();
+        self.dependency_dirs.push(dir.to_owned());
+        for resource in resources(dependency) {
+            self.intern(&resource.id);
+            if path_for_resource(resource).is_some() {
+                self.dependencies.entry(resource.id.as_str()).or_insert((resource, position));
+            }
+        }
+        match self.items {
+            Some(ref mut items) => {
+                for item in model::parse(dependency)? {
+                    items.entry(item.id).or_insert(item);
+                }
+            }
+            None => {
+                for resource in resources(dependency) {
+                    Item::from_resource(resource)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Splits an ID into its path segments, storing each segment that has not been seen before.
+    fn intern(&mut self, id: &'a str) {
+        if self.ids.contains_key(id) {
+            return;
+        }
+
+        let (segments, positions) = (&mut self.segments, &mut self.segment_positions);
+        let id_segments = id
+            .split("::")
+            .map(|segment| {
+                *positions.entry(segment).or_insert_with(|| {
+                    segments.push(segment);
+                    segments.len() as u32 - 1
+                })
+            })
+            .collect();
+        self.ids.insert(id, id_segments);
+    }
+
+    /// Returns the name of the item with an ID, which is the last segment of its path, such as
+    /// `Foo` for `my_crate::Foo`. IDs that are not in the document, such as the paths of traits
+    /// from other crates, are split when they are looked up.
+    pub fn name<'b>(&self, id: &'b str) -> &'b str
+    where
+        'a: 'b,
+    {
+        match self.ids.get(id).and_then(|segments| segments.last()) {
+            Some(&segment) => self.segments[segment as usize],
+            None => id.rsplit("::").next().unwrap_or(id),
+        }
+    }
+
+    /// Finds an included resource by its ID.
+    pub fn resource(&self, id: &str) -> Option<&'a Resource> {
+        self.resources.get(id).cloned()
+    }
+
+    /// Returns the typed view of a resource of the document or of a dependency, parsing it if the
+    /// typed views are not kept.
+    pub fn item<'b>(&'b self, resource: &'b Resource) -> Option<Cow<'b, Item<'b>>> {
+        match self.items {
+            Some(ref items) => items.get(resource.id.as_str()).map(Cow::Borrowed),
+            None => Item::from_resource(resource).ok().map(Cow::Owned),
+        }
+    }
+
+    /// Returns the path of the page for a resource, relative to the doc root, if it has one.
+    pub fn path(&self, resource: &Resource) -> Option<PathBuf> {
+        self.path_by_id(&resource.id)
+    }
+
+    /// Returns the path of the page for the resource with an ID, relative to the doc root, if it
+    /// has one.
+    pub fn path_by_id(&self, id: &str) -> Option<PathBuf> {
+        let krate = || crates(self.document).iter().find(|krate| krate.id == id);
+        if let Some(path) = self.resource(id).or_else(krate).and_then(path_for_resource) {
+            return Some(path);
+        }
+
+        let &(resource, dir) = self.dependencies.get(id)?;
+        path_for_resource(resource).map(|path| self.dependency_dirs[dir].join(path))
+    }
+
+    /// Returns the resources that share a parent with a resource and have their own pages,
+    /// including the resource itself, ordered by kind and alphabetically within each kind.
+    pub fn siblings(&self, resource: &Resource) -> &[&'a Resource] {
+        self.parents
+            .get(resource.id.as_str())
+            .and_then(|parent| self.children.get(parent))
+            .map_or(&[], |children| children.as_slice())
+    }
+
+    /// Returns whether any resource of the document is deprecated.
+    pub fn has_deprecated(&self) -> bool {
+        self.has_deprecated
+    }
+}
+
+impl<'a> Deref for Index<'a> {
+    type Target = JsonApiDocument;
+
+    fn deref(&self) -> &JsonApiDocument {
+        self.document
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use jsonapi::api::JsonApiDocument;
+
+    use resources;
+    use super::Index;
+
+    #[test]
+    fn name() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [
+                    { "type": "module", "id": "test_crate::module", "attributes": {} },
+                    { "type": "struct", "id": "test_crate::module::Foo", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let index = Index::new(&document);
+        assert_eq!(index.name("test_crate::module::Foo"), "Foo");
+        assert_eq!(index.name("test_crate"), "test_crate");
+        assert_eq!(index.name("std::clone::Clone"), "Clone");
+
+        // Each segment is stored once, however many IDs share it.
+        assert_eq!(index.segments, ["test_crate", "module", "Foo"]);
+        assert_eq!(&*index.ids["test_crate::module::Foo"], [0, 1, 2]);
+    }
+
+    #[test]
+    fn siblings() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": {},
+                    "relationships": {
+                        "structs": { "data": [{ "type": "struct", "id": "test_crate::B" }] },
+                        "functions": {
+                            "data": [{ "type": "function", "id": "test_crate::a" }]
+                        },
+                        "fields": { "data": [{ "type": "field", "id": "test_crate::c" }] }
+                    }
+                },
+                "included": [
+                    { "type": "struct", "id": "test_crate::B", "attributes": {} },
+                    { "type": "function", "id": "test_crate::a", "attributes": {} },
+                    { "type": "field", "id": "test_crate::c", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let index = Index::new(&document);
+        let field = index.resource("test_crate::c").unwrap();
+        let siblings = index.siblings(field).iter().map(|s| &s.id[..]).collect::<Vec<_>>();
+        assert_eq!(siblings, ["test_crate::a", "test_crate::B"]);
+
+        let krate = resources(&document).next().unwrap();
+        assert!(index.siblings(krate).is_empty());
+        assert!(!index.has_deprecated());
+    }
+
+    #[test]
+    fn items() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [
+                    { "type": "struct", "id": "test_crate::Foo", "attributes": { "docs": "Foo." } },
+                    { "type": "struct", "id": "test_crate::Bar", "attributes": { "docs": 1 } }
+                ]
+            }"#,
+        ).unwrap();
+
+        assert!(Index::parse(&document).is_err());
+        assert!(Index::on_demand(&document).is_err());
+
+        let index = Index::new(&document);
+        let foo = index.resource("test_crate::Foo").unwrap();
+        assert_eq!(index.item(foo).unwrap().docs, Some("Foo."));
+        let bar = index.resource("test_crate::Bar").unwrap();
+        assert!(index.item(bar).is_none());
+
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [
+                    { "type": "struct", "id": "test_crate::Foo", "attributes": { "docs": "Foo." } }
+                ]
+            }"#,
+        ).unwrap();
+        let index = Index::on_demand(&document).unwrap();
+        let foo = index.resource("test_crate::Foo").unwrap();
+        assert_eq!(index.item(foo).unwrap().docs, Some("Foo."));
+    }
+
+    #[test]
+    fn paths() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+        let dependency = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "b", "attributes": {} },
+                "included": [
+                    { "type": "trait", "id": "b::Bar", "attributes": {} },
+                    { "type": "trait", "id": "a::Foo", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let mut index = Index::parse(&document).unwrap();
+        index.add_dependency(&dependency, Path::new("deps")).unwrap();
+
+        assert_eq!(index.path_by_id("a"), Some(PathBuf::from("a/index.html")));
+        assert_eq!(index.path_by_id("b"), Some(PathBuf::from("deps/b/index.html")));
+        assert_eq!(index.path_by_id("b::Bar"), Some(PathBuf::from("deps/b/trait.Bar.html")));
+        // Resources of the document take precedence over those of dependencies.
+        assert_eq!(index.path_by_id("a::Foo"), Some(PathBuf::from("a/struct.Foo.html")));
+        assert_eq!(index.path_by_id("a::Missing"), None);
+    }
+}
@@ -8,291 +8,3833 @@ extern crate error_chain;
 #[macro_use]
 extern crate log;
 
+#[macro_use]
+extern crate serde_derive;
+
 #[macro_use]
 extern crate serde_json;
 
+extern crate brotli;
+extern crate flate2;
 extern crate handlebars;
 extern crate jsonapi;
 extern crate pathdiff;
 extern crate pulldown_cmark;
+extern crate serde;
+extern crate serde_json_jsonapi;
+extern crate tar;
+#[cfg(feature = "async")]
+extern crate tokio;
+extern crate toml;
+extern crate zip;
 
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
 use std::fs::{self, File};
 use std::io::prelude::*;
 use std::io;
+use std::panic;
 use std::path::{PathBuf, Path};
+use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
 
 use handlebars::Handlebars;
-use jsonapi::api::{JsonApiDocument, PrimaryData, IdentifierData, Resource};
-use pulldown_cmark::{html, Parser};
+use jsonapi::api::{
+    JsonApiDocument, PrimaryData, IdentifierData, Meta, Resource, ResourceIdentifier,
+};
 use serde_json::Value;
 
+use analytics::Analytics;
+use errors::{ErrorKind, Result, ResultExt};
+use hashes::HashWriter;
+use hooks::Hooks;
+use index::Index;
+use limit::{FileLimit, MemoryLimit};
+use manifest::Manifest;
+use output::{Filesystem, MemoryBackend, OutputBackend};
+use pool::{WritePool, WriteQueue};
+
+pub mod analytics;
+pub mod archive;
+pub mod backend;
+pub mod browser;
+pub mod cargo;
+pub mod clean;
+pub mod config;
+pub mod csp;
+pub mod diff;
 pub mod errors;
+pub mod feed;
+pub mod hashes;
+pub mod limit;
+pub mod link_check;
+pub mod load;
+pub mod model;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+pub mod offline;
+pub mod output;
+pub mod precompress;
+pub mod rustdoc_json;
+pub mod serve;
+pub mod sitemap;
+pub mod source;
+pub mod stats;
+pub mod versions;
+pub mod watch;
+
+pub use errors::Error;
+pub use hooks::RenderEvent;
+pub use options::RenderOptions;
+pub use report::{RenderFailure, RenderReport, UnresolvedLink};
+pub use validate::{validate, Diagnostic};
+#[cfg(feature = "async")]
+pub use nonblocking::render_docs_async;
+
+mod cfg;
+mod hooks;
+mod index;
+mod manifest;
+mod markdown;
+mod options;
+mod pool;
+mod report;
+mod validate;
 
 /// Given a JSON-API document generated by the rustdoc backend, generates a tree of documentation
 /// files at the doc root.
-pub fn render_docs<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> io::Result<()> {
-    let mut handlebars = Handlebars::new();
+///
+/// This compiles the templates on every call. Tools that render many crates should create a
+/// `Renderer` once and reuse it instead.
+pub fn render_docs<P: AsRef<Path>>(document: &JsonApiDocument, root: P) -> Result<RenderReport> {
+    Renderer::new().render(document, root)
+}
 
-    handlebars
-        .register_template_string("item", include_str!("../templates/item.hbs"))
-        .unwrap();
+/// Loads a JSON-API document generated by the rustdoc backend from a file, and generates a tree of
+/// documentation files at the doc root.
+pub fn render_docs_from_path<P, Q>(path: P, root: Q) -> Result<RenderReport>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    render_docs(&load::from_path(path)?, root)
+}
 
-    let doc_root = root.as_ref();
-    fs::create_dir_all(&doc_root)?;
+/// Reads a JSON-API document generated by the rustdoc backend, and generates a tree of
+/// documentation files at the doc root.
+pub fn render_docs_from_reader<R, P>(reader: R, root: P) -> Result<RenderReport>
+where
+    R: Read,
+    P: AsRef<Path>,
+{
+    render_docs(&load::from_reader(reader)?, root)
+}
 
-    // Render the top level crate docs.
-    let primary_resource = match document.data {
-        Some(PrimaryData::Single(ref resource)) => resource,
-        _ => panic!(),
-    };
+/// Given a JSON-API document generated by the rustdoc backend, generates a tree of documentation
+/// files at the doc root, configured by a set of options.
+///
+/// Returns an error if a template in the options' template directory cannot be loaded.
+pub fn render_docs_with<P: AsRef<Path>>(
+    document: &JsonApiDocument,
+    root: P,
+    options: RenderOptions,
+) -> Result<RenderReport> {
+    Renderer::with_options(options)?.render(document, root)
+}
 
-    write_doc(document, &primary_resource, &handlebars, &doc_root)?;
+/// Given the JSON-API documents of several crates, such as the members of a workspace, generates
+/// one tree of documentation files at the doc root.
+///
+/// The crates of every document are documented side by side. Resources that appear in more than
+/// one document, such as a dependency shared by several crates, are only rendered once.
+pub fn render_docs_multi<P: AsRef<Path>>(
+    documents: &[JsonApiDocument],
+    root: P,
+) -> Result<RenderReport> {
+    Renderer::new().render_multi(documents, root)
+}
 
-    for resource in document.included.as_ref().unwrap().iter() {
-        write_doc(document, &resource, &handlebars, &doc_root)?;
-    }
+/// The progress of a render, reported after each item page is written.
+#[derive(Debug, Clone, Copy)]
+pub struct Progress<'a> {
+    /// The number of item pages that will be rendered.
+    pub total: usize,
 
-    let mut css = File::create(doc_root.join("styles.css"))?;
-    css.write_all(
-        include_str!("../static/styles.css").as_bytes(),
-    )?;
+    /// The number of item pages rendered so far, including the current item.
+    pub rendered: usize,
 
-    let mut js = File::create(doc_root.join("main.js"))?;
-    js.write_all(include_str!("../static/main.js").as_bytes())?;
+    /// The ID of the item that was just rendered.
+    pub current: &'a str,
+}
 
-    Ok(())
+/// Renders documentation with templates that are compiled once, when the renderer is created.
+pub struct Renderer {
+    handlebars: Handlebars,
+    options: RenderOptions,
+    backend: Option<Box<dyn OutputBackend>>,
+    hooks: Hooks,
 }
 
-/// Writes a documentation file at the documentation root.
-fn write_doc<P: AsRef<Path>>(
-    document: &JsonApiDocument,
-    resource: &Resource,
-    handlebars: &Handlebars,
-    doc_root: P,
-) -> io::Result<()> {
-    let doc_root = doc_root.as_ref();
+impl Renderer {
+    /// Creates a renderer, compiling every template.
+    pub fn new() -> Renderer {
+        Renderer {
+            handlebars: create_handlebars(),
+            options: RenderOptions::new(),
+            backend: None,
+            hooks: Hooks::default(),
+        }
+    }
 
-    if let Some(path) = path_for_resource(resource) {
-        let path = doc_root.join(path);
-        fs::create_dir_all(path.parent().unwrap())?;
-        let mut file = File::create(&path)?;
+    /// Creates a renderer configured by a set of options, compiling every template.
+    ///
+    /// Returns an error if a template in the options' template directory cannot be loaded.
+    pub fn with_options(options: RenderOptions) -> Result<Renderer> {
+        let mut renderer = Renderer::new();
 
-        info!("rendering `{}` as `{}`", resource.id, path.display());
-        let context = generate_context(doc_root, document, resource);
-        debug!("context: {}", context);
-        let rendered_template = handlebars.render("item", &context).unwrap();
-        file.write_all(rendered_template.as_bytes()).unwrap();
+        if let Some(ref template_dir) = options.template_dir {
+            load_templates(&mut renderer.handlebars, template_dir)?;
+        }
+
+        if let Some(ref lang) = options.lang {
+            let valid = lang.split('-').all(|subtag| {
+                !subtag.is_empty() && subtag.bytes().all(|c| c.is_ascii_alphanumeric())
+            });
+            if !valid {
+                bail!(ErrorKind::Config(format!("`{}` is not a language tag", lang)));
+            }
+        }
+
+        if let Some(transform) = options.context_transform {
+            renderer = renderer.before_item(transform);
+        }
+
+        renderer.options = options;
+        Ok(renderer)
     }
 
-    Ok(())
-}
+    /// Adds a callback that is called after each item page is rendered, so that frontends can
+    /// display a progress bar.
+    pub fn on_progress<F>(mut self, callback: F) -> Renderer
+    where
+        F: Fn(&Progress) + Send + Sync + 'static,
+    {
+        self.hooks.on_progress.push(Box::new(callback));
+        self
+    }
 
-/// Generates a context to be used when rendering a resource with handlebars.
-fn generate_context(root: &Path, document: &JsonApiDocument, resource: &Resource) -> Value {
-    let path_to_root = path_for_resource(resource).and_then(|path| {
-        let path = root.join(path);
-        html_diff_paths(root, &path)
-    });
+    /// Writes the documentation to a backend instead of the filesystem.
+    ///
+    /// The backend is given paths relative to the doc root, which is still used to build the
+    /// paths in the report. Pages are always rendered again, because the manifest of the previous
+    /// render is only kept on the filesystem.
+    pub fn backend<B: OutputBackend + 'static>(mut self, backend: B) -> Renderer {
+        self.backend = Some(Box::new(backend));
+        self
+    }
 
-    let mut context = json!({
-        "type": resource._type,
-        "name": resource.id,
-        "pathToRoot": path_to_root,
-    });
+    /// Adds a hook that is called with the context of each item page before it is rendered, and
+    /// may change it.
+    ///
+    /// Hooks are called in the order they were added, from more than one thread at a time. They
+    /// also apply to pages rendered with `render_page`, so that templates can use data that the
+    /// document does not contain, such as the team that owns an item.
+    pub fn before_item<F>(mut self, hook: F) -> Renderer
+    where
+        F: Fn(&Resource, &mut Value) + Send + Sync + 'static,
+    {
+        self.hooks.before_item.push(Box::new(hook));
+        self
+    }
 
-    if let Some(docs) = docs_for_resource(&resource) {
-        context.as_object_mut().unwrap().insert(
-            String::from("docs"),
-            Value::String(docs),
-        );
+    /// Adds a hook that is called with the HTML of each item page before it is written, and may
+    /// change it.
+    ///
+    /// Pages are always rendered again while a hook is added, because the manifest of the
+    /// previous render cannot tell whether the hook would change them.
+    pub fn after_item<F>(mut self, hook: F) -> Renderer
+    where
+        F: Fn(&Resource, &mut String) + Send + Sync + 'static,
+    {
+        self.hooks.after_item.push(Box::new(hook));
+        self
     }
 
-    if let Some(relationships) = resource.relationships.as_ref() {
-        let mut sections = json!({});
+    /// Adds a hook that is called with the report once every file of a render is written.
+    pub fn after_all<F>(mut self, hook: F) -> Renderer
+    where
+        F: Fn(&RenderReport) + Send + Sync + 'static,
+    {
+        self.hooks.after_all.push(Box::new(hook));
+        self
+    }
 
-        for (key, data) in relationships {
-            let resources = match data.data {
-                IdentifierData::Multiple(ref resources) => resources,
-                _ => panic!(),
+    /// Adds an observer that is called with each event of a render, such as an item page being
+    /// rendered or a relationship that could not be resolved.
+    ///
+    /// Events are also logged, and may be emitted from more than one thread at a time.
+    pub fn on_event<F>(mut self, observer: F) -> Renderer
+    where
+        F: Fn(&RenderEvent) + Send + Sync + 'static,
+    {
+        self.hooks.on_event.push(Box::new(observer));
+        self
+    }
+
+    /// Given a JSON-API document generated by the rustdoc backend, generates a tree of
+    /// documentation files at the doc root.
+    pub fn render<P>(&self, document: &JsonApiDocument, root: P) -> Result<RenderReport>
+    where
+        P: AsRef<Path>,
+    {
+        let root = root.as_ref();
+        let version = self.version_for(document)?;
+        let doc_root = match version {
+            Some(ref version) => root.join(version),
+            None => root.to_owned(),
+        };
+
+        if !self.options.dependencies.is_empty() && self.backend.is_some() {
+            bail!("dependencies can only be rendered to the filesystem");
+        }
+
+        let readme = self.readme()?;
+        let document = self.visible_items(document)?;
+        let document = &*document;
+        let dependencies = self.options.dependencies.iter().map(|d| self.visible_items(d));
+        let dependencies = dependencies.collect::<Result<Vec<_>>>()?;
+        let target = Target {
+            doc_root: &doc_root,
+            version: version.as_deref(),
+            changes_since: self.options.changes_since.as_ref(),
+            readme: readme.as_deref(),
+            dependencies: &dependencies,
+        };
+        let mut report = render_html(self, document, &target)?;
+
+        let deps_root = doc_root.join(DEPS_DIR);
+        for dependency in &dependencies {
+            let target = Target {
+                doc_root: &deps_root,
+                version: None,
+                changes_since: None,
+                readme: None,
+                dependencies: &[],
             };
+            report.append(render_html(self, dependency, &target)?);
+        }
 
-            let json_resources = resources
-                .iter()
-                .flat_map(|child| {
-                    let id = &child.id;
-
-                    let child = resource_by_id(document, id);
-                    if child.is_none() {
-                        error!(
-                            "could not find '{}' in the document's included resources. \
-                            This is probably a bug in the rustdoc backend.", id);
-                        return None;
-                    }
-                    let child = child.unwrap();
+        if self.options.service_worker {
+            self.write_service_worker(document, &dependencies, &doc_root, &mut report)?;
+        }
+        if self.options.content_hashes {
+            self.write_content_hashes(document, &dependencies, &doc_root, &mut report)?;
+        }
 
-                    let name = child.id.rsplit("::").next().unwrap_or_else(|| id);
+        if let Some(ref version) = version {
+            if !self.options.dry_run {
+                versions::add_version(root, version)?;
 
-                    // Create a link to the child resource. Since /index.html paths in the
-                    // browser actually act like folders, we need to diff the paths from the
-                    // parent folder.
-                    let link = link(resource, child);
+                // The changes of each version are collected into a feed at the shared root.
+                if let Some(ref old) = self.options.changes_since {
+                    let krate = crate_metadata(document);
+                    let krate = krate["name"].as_str().unwrap_or_default();
+                    let diff = diff::diff(old, document)?;
+                    let base_url = self.options.base_url.as_deref();
+                    feed::record(root, krate, version, &diff, base_url)?;
+                }
+            }
+        }
+        report.version = version;
+        Ok(report)
+    }
 
-                    let json = json!({
-                        "name": name,
-                        "link": link,
-                        "docs": docs_for_resource(child),
-                    });
+    /// Writes the service worker that caches the documentation of a render for offline browsing,
+    /// along with the cache manifest of the files of the documentation and its dependencies.
+    fn write_service_worker(
+        &self,
+        document: &JsonApiDocument,
+        dependencies: &[Cow<JsonApiDocument>],
+        doc_root: &Path,
+        report: &mut RenderReport,
+    ) -> Result<()> {
+        let mut files = report
+            .files_written
+            .iter()
+            .filter_map(|file| file.strip_prefix(doc_root).ok())
+            .map(Path::to_path_buf)
+            .collect::<Vec<_>>();
+        files.extend(skipped_pages(document, dependencies, report));
 
-                    Some(json)
-                })
-                .collect();
+        let (cache_manifest, worker) = offline::offline_files(&files);
+        let filesystem = Filesystem::new(doc_root);
+        let backend = self.backend.as_ref().map_or(&filesystem as &dyn OutputBackend, |b| &**b);
+        for &(file, ref contents) in &[
+            (offline::CACHE_MANIFEST, cache_manifest),
+            (offline::SERVICE_WORKER, worker),
+        ] {
+            if !self.options.dry_run {
+                backend.write(Path::new(file), contents.as_bytes())?;
+                if self.options.content_hashes {
+                    let hash = hashes::content_hash(contents.as_bytes());
+                    report.content_hashes.insert(doc_root.join(file), hash);
+                }
+            }
+            report.files_written.push(doc_root.join(file));
+        }
 
-            sections.as_object_mut().unwrap().insert(
-                key.clone(),
-                Value::Array(
-                    json_resources,
-                ),
-            );
+        // The manifest was saved before the worker was written, so the worker is recorded in it
+        // to be cleaned along with the rest of the documentation.
+        if !self.options.dry_run && self.backend.is_none() {
+            let files = [Path::new(offline::CACHE_MANIFEST), Path::new(offline::SERVICE_WORKER)];
+            Manifest::record_files(doc_root, &files)?;
+        }
+        Ok(())
+    }
 
+    /// Writes the map of every file of the documentation of a render and its dependencies to the
+    /// hash of its contents, noting which files changed since the previous render.
+    fn write_content_hashes(
+        &self,
+        document: &JsonApiDocument,
+        dependencies: &[Cow<JsonApiDocument>],
+        doc_root: &Path,
+        report: &mut RenderReport,
+    ) -> Result<()> {
+        let previous = match self.backend {
+            None => hashes::previous(doc_root),
+            Some(_) => BTreeMap::new(),
+        };
+
+        let key = |file: &Path| {
+            file.iter().map(|c| c.to_string_lossy()).collect::<Vec<_>>().join("/")
+        };
+        let mut current = BTreeMap::new();
+        for (file, hash) in &report.content_hashes {
+            if let Ok(file) = file.strip_prefix(doc_root) {
+                current.insert(key(file), hash.clone());
+            }
         }
 
-        context.as_object_mut().unwrap().insert(
-            String::from("sections"),
-            sections,
-        );
+        // Skipped pages are unchanged, so they keep their previous hash. They are only read if
+        // hashes were not recorded when they were written.
+        for page in skipped_pages(document, dependencies, report) {
+            let file = key(&page);
+            let hash = match previous.get(&file) {
+                Some(hash) => hash.clone(),
+                None => hashes::content_hash(&fs::read(doc_root.join(&page))?),
+            };
+            current.insert(file, hash);
+        }
+
+        let path = doc_root.join(hashes::CONTENT_HASHES);
+        if !self.options.dry_run {
+            info!("writing content hashes to `{}`", path.display());
+            let filesystem = Filesystem::new(doc_root);
+            let backend =
+                self.backend.as_ref().map_or(&filesystem as &dyn OutputBackend, |b| &**b);
+            let map = hashes::render(&previous, &current);
+            backend.write(Path::new(hashes::CONTENT_HASHES), map.as_bytes())?;
+            if self.backend.is_none() {
+                Manifest::record_files(doc_root, &[Path::new(hashes::CONTENT_HASHES)])?;
+            }
+        }
+        report.files_written.push(path);
+        Ok(())
     }
 
-    context
-}
+    /// Given the JSON-API documents of several crates, such as the members of a workspace,
+    /// generates one tree of documentation files at the doc root.
+    ///
+    /// Resources that appear in more than one document are only rendered once.
+    pub fn render_multi<P>(&self, documents: &[JsonApiDocument], root: P) -> Result<RenderReport>
+    where
+        P: AsRef<Path>,
+    {
+        // Merging copies every resource, which a single document does not need.
+        match *documents {
+            [ref document] => self.render(document, root),
+            _ => self.render(&merge_documents(documents), root),
+        }
+    }
 
-/// Creates a link to a child resource if a page exists for it.
-fn link(resource: &Resource, child: &Resource) -> Option<String> {
-    match (path_for_resource(resource), path_for_resource(child)) {
-        (Some(parent_path), Some(child_path)) => html_diff_paths(&child_path, &parent_path),
-        _ => None,
+    /// Returns the Markdown of the README rendered on the page of the crate, if there is one.
+    fn readme(&self) -> Result<Option<String>> {
+        match self.options.readme {
+            Some(ref path) => Ok(Some(
+                fs::read_to_string(path)
+                    .chain_err(|| format!("could not read README `{}`", path.display()))?,
+            )),
+            None => Ok(None),
+        }
     }
-}
 
-/// Returns a path to the doc file for a given resource, if it exists.
-///
-/// For example, fields do not have individual links.
-fn path_for_resource(resource: &Resource) -> Option<PathBuf> {
-    let mut path: PathBuf = resource.id.split("::").collect();
+    /// Returns a document without the items that are not documented, such as private items.
+    fn visible_items<'a>(&self, document: &'a JsonApiDocument) -> Result<Cow<'a, JsonApiDocument>> {
+        let mut omitted = hidden_items(document, &self.options)?;
+        omitted.append(&mut not_included(document, &self.options));
 
-    if resource._type == "module" || resource._type == "crate" {
-        path.push("index.html");
-        Some(path)
-    } else {
-        let ty = match resource._type.as_str() {
-            "struct" => "struct",
-            "function" => "fn",
-            "trait" => "trait",
-            "type" => "type",
-            "enum" => "enum",
-            "const" => "constant",
-            "field" => return None,
-            res => unimplemented!("resource {}: {}", res, resource.id),
+        if self.options.excluded.is_empty() && omitted.is_empty() {
+            Ok(Cow::Borrowed(document))
+        } else {
+            let omitted = omitted.iter().map(|id| &id[..]).collect();
+            Ok(Cow::Owned(exclude_resources(document, &self.options, &omitted)))
+        }
+    }
+
+    /// Returns the version that a document is rendered under, if the documentation is versioned.
+    fn version_for(&self, document: &JsonApiDocument) -> Result<Option<String>> {
+        if !self.options.versioned {
+            return Ok(None);
+        }
+        if self.backend.is_some() {
+            bail!("versioned documentation can only be written to the filesystem");
+        }
+
+        let version = match self.options.version {
+            Some(ref version) => version.clone(),
+            None => crate_metadata(document)["version"]
+                .as_str()
+                .map(String::from)
+                .ok_or("the crate has no version to render the documentation under")?,
         };
+        if !versions::is_valid(&version) {
+            bail!("`{}` cannot be the name of a directory of documentation", version);
+        }
+        Ok(Some(version))
+    }
 
-        let item_name = path.file_name().unwrap().to_owned();
-        path.pop();
-        path.push(&format!("{}.{}.html", ty, item_name.to_str().unwrap()));
-        Some(path)
+    /// Renders the page of a single resource into a writer, without touching the filesystem.
+    ///
+    /// The page is rendered from the same context as in a full render, and links in the page are
+    /// relative, as if the page were written to its usual location in the doc root. Fails if the
+    /// resource does not have a page, such as a field or an impl.
+    pub fn render_page<W: Write>(
+        &self,
+        document: &JsonApiDocument,
+        resource: &Resource,
+        writer: W,
+    ) -> Result<()> {
+        let version = self.version_for(document)?;
+        let readme = self.readme()?;
+        let document = self.visible_items(document)?;
+        let index = Index::parse(&document)?;
+        let page = index.path(resource).ok_or_else(|| {
+            ErrorKind::InvalidDocument(format!("`{}` does not have a page", resource.id))
+        })?;
+
+        // Nothing is written, including the icons, which the page links to as in a full render.
+        let target = Target {
+            doc_root: Path::new(""),
+            version: version.as_deref(),
+            changes_since: self.options.changes_since.as_ref(),
+            readme: readme.as_deref(),
+            dependencies: &[],
+        };
+        let backend = MemoryBackend::new();
+        let open_files = FileLimit::new(None);
+        let analytics = analytics(&self.options)?;
+        let mut output = Output::new(self, &target, &backend, &open_files, analytics.as_deref());
+        output.dry_run = true;
+        output.assets = asset_names(&assets(&self.options)?);
+        output.icons = write_icons(&index, &self.options, &output, &mut RenderReport::default())?;
+
+        let context = page_context(&index, resource, &page, &output);
+        render_to_writer(&self.handlebars, "item", &context, writer)
     }
 }
 
-/// Returns the documentation rendered as HTML for a given resource.
-fn docs_for_resource(resource: &Resource) -> Option<String> {
-    // TODO: We could be smart and do some caching here.
-    resource.attributes.get("docs").and_then(|attr| {
-        let docs = attr.as_str().expect("docs attribute was not a string");
-        let parser = Parser::new(docs);
-        let mut rendered_docs = String::new();
-        html::push_html(&mut rendered_docs, parser);
-
-        if !rendered_docs.is_empty() {
-            Some(rendered_docs)
-        } else {
-            None
-        }
-    })
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer::new()
+    }
 }
 
-/// Given a resource ID, finds the resource in the JSON-API document.
-fn resource_by_id<'a>(document: &'a JsonApiDocument, id: &str) -> Option<&'a Resource> {
-    document.included.as_ref().and_then(|included| {
-        included.iter().find(|resource| resource.id == id)
-    })
+/// Returns the pages of the items that a render skipped because they were unchanged, relative to
+/// the doc root, including those of dependencies.
+fn skipped_pages(
+    document: &JsonApiDocument,
+    dependencies: &[Cow<JsonApiDocument>],
+    report: &RenderReport,
+) -> Vec<PathBuf> {
+    let skipped = report.skipped.iter().map(|id| &id[..]).collect::<HashSet<_>>();
+    let pages = resources(document).map(|resource| (Path::new(""), resource));
+    let pages = pages.chain(dependencies.iter().flat_map(|dependency| {
+        resources(dependency).map(|resource| (Path::new(DEPS_DIR), resource))
+    }));
+    pages
+        .filter(|&(_, resource)| skipped.contains(&resource.id[..]))
+        .filter_map(|(dir, resource)| path_for_resource(resource).map(|page| dir.join(page)))
+        .collect()
 }
 
-/// Perform a `pathdiff::diff_paths` of two `Path` objects, but return a `String` for HTML output.
-///
-/// The returned HTML path will differ from a filesystem path in two ways:
-///
-/// - It will have any backslashed replaced by forward slashes.
-/// - It will be relative from the parent folder, not the file itself.
-///
-/// # Panics
-///
-/// This function will panic if the `base` parameter does not have a parent, or if any of the path
-/// components are invalid UTF-8.
-fn html_diff_paths(path: &Path, base: &Path) -> Option<String> {
-    let base = base.parent().expect("path did not have a parent");
+/// Where a document is rendered, and the documents rendered along with it.
+struct Target<'a> {
+    /// The directory that the documentation is written to.
+    doc_root: &'a Path,
 
-    pathdiff::diff_paths(path, base).map(|relative_path| {
-        relative_path
-            .into_iter()
-            .map(|component| {
-                component.to_str().expect("Path contained invalid UTF-8")
-            })
-            .collect::<Vec<_>>()
-            .join("/")
+    /// The version that the documentation is rendered under, if it is versioned.
+    version: Option<&'a str>,
+
+    /// The older document that the API changes are listed since, if any.
+    changes_since: Option<&'a JsonApiDocument>,
+
+    /// The Markdown of the README rendered on the page of the crate, if any.
+    readme: Option<&'a str>,
+
+    /// The documents of dependencies, which are rendered under `deps/` at the doc root.
+    dependencies: &'a [Cow<'a, JsonApiDocument>],
+}
+
+/// Generates the HTML documentation of a document with a prepared renderer, through a pool of
+/// writers if the options have one.
+fn render_html(
+    renderer: &Renderer,
+    document: &JsonApiDocument,
+    target: &Target,
+) -> Result<RenderReport> {
+    let open_files = &FileLimit::new(renderer.options.max_open_files);
+    let memory = &MemoryLimit::new(renderer.options.memory_limit);
+    let filesystem = Filesystem::new(target.doc_root);
+    let backend = renderer.backend.as_ref().map_or(&filesystem as &dyn OutputBackend, |b| &**b);
+
+    // Pages are streamed into their files in low memory mode, rather than held in the queue.
+    let writers = renderer.options.write_threads.filter(|_| !renderer.options.low_memory);
+    let writers = match writers {
+        Some(writers) if !renderer.options.dry_run => writers,
+        _ => return write_html(renderer, document, target, backend, open_files, None),
+    };
+
+    thread::scope(|scope| {
+        let pool = WritePool::start(scope, backend, open_files, memory, writers);
+        let queue = Some(pool.queue());
+        let report = write_html(renderer, document, target, backend, open_files, queue);
+
+        // The errors of the writers come first, since a render that fails to queue a file only
+        // knows that the writers stopped.
+        pool.finish()?;
+        report
     })
 }
 
-#[cfg(test)]
-mod tests {
-    use std::path::PathBuf;
+/// Generates the HTML documentation of a document, writing files to a queue if one is given.
+fn write_html(
+    renderer: &Renderer,
+    document: &JsonApiDocument,
+    target: &Target,
+    backend: &dyn OutputBackend,
+    open_files: &FileLimit,
+    queue: Option<&WriteQueue>,
+) -> Result<RenderReport> {
+    let doc_root = target.doc_root;
+    let mut report = RenderReport::default();
 
-    use jsonapi::api::Resource;
+    let assets = assets(&renderer.options)?;
+    let analytics = analytics(&renderer.options)?;
+    let mut output = Output::new(renderer, target, backend, open_files, analytics.as_deref());
+    output.assets = asset_names(&assets);
+    output.queue = queue;
 
-    #[test]
-    fn path_for_resource() {
-        let module = Resource {
-            _type: "module".into(),
-            id: "test_crate::test_module".into(),
-            ..Default::default()
-        };
+    if renderer.options.share_fragments {
+        output.fragments = write_fragments(&output, &mut report)?;
+    }
 
-        assert_eq!(
-            super::path_for_resource(&module).unwrap(),
-            PathBuf::from("test_crate/test_module/index.html")
-        );
+    if crates(document).is_empty() {
+        bail!(ErrorKind::InvalidDocument(String::from("the document has no primary data")));
+    }
 
-        let strukt = Resource {
-            _type: "struct".into(),
-            id: "test_crate::TestStruct".into(),
-            ..Default::default()
-        };
+    // Attributes are parsed once, up front, so pages can rely on them having the expected types.
+    // In low memory mode, the typed views are parsed again whenever they are needed instead.
+    let mut index = match renderer.options.low_memory {
+        true => Index::on_demand(document)?,
+        false => Index::parse(document)?,
+    };
+    for dependency in target.dependencies {
+        index.add_dependency(dependency, Path::new(DEPS_DIR))?;
+    }
+    output.icons = write_icons(&index, &renderer.options, &output, &mut report)?;
 
-        assert_eq!(
-            super::path_for_resource(&strukt).unwrap(),
-            PathBuf::from("test_crate/struct.TestStruct.html")
-        );
+    // Problems with the document are reported once, before any page is rendered.
+    for diagnostic in validate::check(&index) {
+        if diagnostic.is_error() || renderer.options.strict {
+            bail!(ErrorKind::InvalidDocument(diagnostic.to_string()));
+        }
 
-        let field = Resource {
-            _type: "field".into(),
-            id: "test_crate::Struct::field".into(),
-            ..Default::default()
-        };
+        if let Diagnostic::UnresolvedLink(ref link) = diagnostic {
+            renderer.hooks.emit(&RenderEvent::LinkUnresolved(link));
+        }
+        report.diagnose(diagnostic);
+    }
 
-        assert_eq!(super::path_for_resource(&field), None);
+    // Pages rendered with custom templates may change even if their context does not, so every
+    // page is rendered again, as are pages changed by hooks. Other backends cannot be checked for
+    // previous pages.
+    let incremental = renderer.options.template_dir.is_none()
+        && renderer.backend.is_none()
+        && renderer.hooks.after_item.is_empty();
+    let previous = if incremental { Manifest::load(doc_root) } else { Manifest::default() };
+
+    // Every primary resource is rendered as the top level page of a crate. A document without
+    // included resources describes a crate with no items, so only the crate pages are rendered.
+    let items = resources(document).collect::<Vec<_>>();
+    let total = items.iter().filter(|resource| index.path(resource).is_some()).count();
+    let rendered = AtomicUsize::new(0);
+
+    let write_items = |items: &[&Resource]| -> Result<(RenderReport, Manifest)> {
+        let mut report = RenderReport::default();
+        let mut manifest = Manifest::default();
+
+        for &resource in items {
+            match write_doc(&index, resource, &output, &previous, &mut manifest, &mut report) {
+                Err(ref e) if renderer.options.keep_going => {
+                    report.fail(&resource.id, e.to_string());
+                    if renderer.options.error_pages {
+                        write_error_page(&index, resource, &output, e, &mut report);
+                    }
+                }
+                written => written?,
+            }
+
+            if index.path(resource).is_some() {
+                let rendered = rendered.fetch_add(1, Ordering::SeqCst) + 1;
+                renderer.hooks.progress(&Progress { total, rendered, current: &resource.id });
+            }
+        }
+
+        Ok((report, manifest))
+    };
+
+    let write_crate_pages = || -> Result<RenderReport> {
+        let mut report = RenderReport::default();
+        write_crate_pages(&index, &output, &mut report)?;
+        Ok(report)
+    };
+
+    let jobs = match renderer.options.jobs {
+        _ if renderer.options.low_memory || !renderer.options.parallel => 1,
+        Some(jobs) => jobs.max(1),
+        None => thread::available_parallelism().map_or(2, |jobs| jobs.get()),
+    };
+
+    let mut manifest = Manifest::default();
+    if jobs == 1 {
+        let (items_report, items_manifest) = write_items(&items)?;
+        report.append(items_report);
+        report.append(write_crate_pages()?);
+        manifest.append(items_manifest);
+    } else {
+        // The pages that cover the whole crate do not depend on the item pages, so they are
+        // rendered on one thread while the item pages are split between the others. The reports
+        // are appended in the order of the items, so that they do not depend on the threads.
+        let chunk_size = items.len().div_ceil(jobs - 1).max(1);
+        thread::scope(|scope| -> Result<()> {
+            let crate_pages = scope.spawn(write_crate_pages);
+            let chunks = items
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || write_items(chunk)))
+                .collect::<Vec<_>>();
+
+            for chunk in chunks {
+                let (items_report, items_manifest) =
+                    chunk.join().unwrap_or_else(|e| panic::resume_unwind(e))?;
+                report.append(items_report);
+                manifest.append(items_manifest);
+            }
+            report.append(crate_pages.join().unwrap_or_else(|e| panic::resume_unwind(e))?);
+            Ok(())
+        })?;
     }
 
-    #[test]
-    fn html_diff_paths() {
-        let base = PathBuf::from("/target/doc/example/index.html");
-        let path = PathBuf::from("/target/doc");
-        assert_eq!(super::html_diff_paths(&path, &base), Some("..".into()));
+    for (_, asset, contents) in &assets {
+        let path = doc_root.join(asset);
+        output.write(&path, contents.as_bytes())?;
+        report.files_written.push(path);
+    }
+
+    if let Some(ref base_url) = renderer.options.base_url {
+        let path = doc_root.join("sitemap.xml");
+
+        // Pages skipped because they were unchanged are not in the report, so the sitemap of a
+        // filesystem render lists the doc root instead, unless the writers of a pool may not have
+        // written every page yet. Renders to other backends are never incremental.
+        if !output.dry_run {
+            let pages = match renderer.backend {
+                None if output.queue.is_none() => sitemap::pages(doc_root)?,
+                _ => report
+                    .files_written
+                    .iter()
+                    .filter(|file| file.extension().is_some_and(|ext| ext == "html"))
+                    .filter_map(|file| file.strip_prefix(doc_root).ok())
+                    .map(Path::to_path_buf)
+                    .chain(skipped_pages(document, &[], &report))
+                    .collect(),
+            };
+            info!("writing sitemap to `{}`", path.display());
+            output.write(&path, sitemap::sitemap(base_url, &pages).as_bytes())?;
+        }
+        report.files_written.push(path);
+    }
+
+    // The paths of `robots.txt` and `_headers` are on the host of the documentation.
+    let prefix = renderer.options.base_url.as_deref().map_or("/", sitemap::url_path);
+    let prefix = match output.version {
+        Some(version) => format!("{}/{}", prefix.trim_end_matches('/'), version),
+        None => String::from(prefix),
+    };
+
+    if renderer.options.robots_txt {
+        let sitemap_url = output.canonical_url(&doc_root.join("sitemap.xml"));
+        let crates = crates(document).iter().map(|krate| &krate.id[..]).collect::<Vec<_>>();
+        let disallowed = &renderer.options.robots_disallow;
+        let robots = sitemap::robots(&prefix, &crates, disallowed, sitemap_url.as_deref());
+
+        let path = doc_root.join("robots.txt");
+        output.write(&path, robots.as_bytes())?;
+        report.files_written.push(path);
+    }
+
+    if renderer.options.csp_headers {
+        let policy = csp::policy(renderer.options.analytics.as_ref());
+        let path = doc_root.join(csp::HEADERS);
+        output.write(&path, csp::headers(&prefix, &policy).as_bytes())?;
+        report.files_written.push(path);
+    }
+
+    // The files of previous runs stay in the manifest while they exist, so that cleaning the doc
+    // root also removes the pages of items that were removed since.
+    if !output.dry_run && renderer.backend.is_none() {
+        let previous_files = Manifest::files(doc_root).unwrap_or(None).unwrap_or_default();
+        for file in previous_files.iter().filter(|file| doc_root.join(file).is_file()) {
+            manifest.insert_file(file);
+        }
+        let files_written = report.files_written.iter();
+        for file in files_written.filter_map(|file| file.strip_prefix(doc_root).ok()) {
+            manifest.insert_file(file);
+        }
+        manifest.save(doc_root)?;
+    }
+
+    if let Some(hashes) = output.content_hashes.take() {
+        report.content_hashes.extend(hashes.into_inner().unwrap());
+    }
+
+    if let Some(summary) = report.summary() {
+        warn!("{}", summary.trim_end());
+    }
+
+    renderer.hooks.after_all(&report);
+    Ok(report)
+}
+
+/// Returns the analytics snippet that is added to the head of every page, if the options have one.
+fn analytics(options: &RenderOptions) -> Result<Option<String>> {
+    let mut analytics = options.analytics.as_ref().map(Analytics::snippet);
+    if let Some(ref snippet) = options.analytics_snippet {
+        let contents = fs::read_to_string(snippet)
+            .chain_err(|| format!("could not read analytics snippet `{}`", snippet.display()))?;
+        let analytics = analytics.get_or_insert_with(String::new);
+        if !analytics.is_empty() {
+            analytics.push_str("\n  ");
+        }
+        analytics.push_str(contents.trim());
+    }
+    Ok(analytics)
+}
+
+/// Returns the stylesheets and scripts of HTML pages, by the name that templates refer to them
+/// with, along with the name of the file of each one and its contents.
+///
+/// Pages load every asset from a file rather than inline, so that hosts can forbid inline scripts
+/// and styles with a Content Security Policy. The name of each file contains a hash of its
+/// contents, like the names of fragments, so that hosts can let browsers cache assets forever.
+fn assets(options: &RenderOptions) -> Result<Vec<(&'static str, String, String)>> {
+    let mut settings_js = String::new();
+    if let Some(ref theme) = options.theme {
+        let theme = Value::String(theme.clone());
+        settings_js.push_str(&format!("window.rustdocDefaultTheme = {};\n", theme));
+    }
+    if options.service_worker {
+        let worker = Value::String(String::from(offline::SERVICE_WORKER));
+        settings_js.push_str(&format!("window.rustdocServiceWorker = {};\n", worker));
+    }
+    settings_js.push_str(include_str!("../static/settings.js"));
+
+    let mut styles_css = String::from(include_str!("../static/styles.css"));
+    for stylesheet in &options.extra_css {
+        let mut file = File::open(stylesheet)
+            .chain_err(|| format!("could not open stylesheet `{}`", stylesheet.display()))?;
+        styles_css.push('\n');
+        file.read_to_string(&mut styles_css)
+            .chain_err(|| format!("could not read stylesheet `{}`", stylesheet.display()))?;
+    }
+
+    let assets = vec![
+        ("styles", "css", styles_css),
+        ("print", "css", String::from(include_str!("../static/print.css"))),
+        ("main", "js", String::from(include_str!("../static/main.js"))),
+        ("settings", "js", settings_js),
+    ];
+    Ok(assets
+        .into_iter()
+        .map(|(name, extension, contents)| {
+            let hash = hashes::content_hash(contents.as_bytes());
+            (name, format!("{}-{}.{}", name, &hash[..8], extension), contents)
+        })
+        .collect())
+}
+
+/// Returns the names of the files of assets relative to the doc root, by the name of each asset.
+fn asset_names(assets: &[(&str, String, String)]) -> Value {
+    let names = assets.iter().map(|&(name, ref file, _)| (String::from(name), json!(file)));
+    Value::Object(names.collect())
+}
+
+/// The templates that are shared between item pages with `share_fragments`.
+const FRAGMENTS: &[&str] = &["settings", "sidebar", "search"];
+
+/// The templates used by the renderers, by name.
+const TEMPLATES: &[(&str, &str)] = &[
+    ("settings", include_str!("../templates/settings.hbs")),
+    ("sidebar", include_str!("../templates/sidebar.hbs")),
+    ("search", include_str!("../templates/search.hbs")),
+    ("item", include_str!("../templates/item.hbs")),
+    ("source", include_str!("../templates/source.hbs")),
+    ("deprecated", include_str!("../templates/deprecated.hbs")),
+    ("stats", include_str!("../templates/stats.hbs")),
+    ("single", include_str!("../templates/single.hbs")),
+    ("error", include_str!("../templates/error.hbs")),
+    ("changes", include_str!("../templates/changes.hbs")),
+    ("crates", include_str!("../templates/crates.hbs")),
+];
+
+/// The name of the search index, at the doc root.
+///
+/// Unlike the other scripts, the name of the index does not contain a hash of its contents, since
+/// every item page would then be rendered again whenever the summary of any item changed.
+const SEARCH_INDEX: &str = "search-index.js";
+
+/// The directory under the doc root that the documentation of dependencies is rendered into.
+const DEPS_DIR: &str = "deps";
+
+/// Creates a handlebars registry with every template used by the renderers.
+fn create_handlebars() -> Handlebars {
+    let mut handlebars = Handlebars::new();
+
+    for &(name, template) in TEMPLATES {
+        handlebars.register_template_string(name, template).unwrap();
+    }
+
+    handlebars
+}
+
+/// Replaces the built-in templates with those of the same name in a directory, if it has any.
+fn load_templates(handlebars: &mut Handlebars, template_dir: &Path) -> Result<()> {
+    for &(name, _) in TEMPLATES {
+        let path = template_dir.join(format!("{}.hbs", name));
+
+        if path.is_file() {
+            info!("loading template `{}` from `{}`", name, path.display());
+            handlebars
+                .register_template_file(name, &path)
+                .chain_err(|| format!("could not load template `{}`", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the IDs of the private and hidden items that the options leave out of the
+/// documentation.
+fn hidden_items(document: &JsonApiDocument, options: &RenderOptions) -> Result<Vec<String>> {
+    let items = model::parse(document)?;
+    let hidden = items
+        .into_iter()
+        .filter(|item| {
+            let private = item.visibility.is_some_and(|visibility| visibility != "public");
+            (private && !options.document_private_items)
+                || (item.hidden && !options.document_hidden_items)
+        })
+        .map(|item| String::from(item.id))
+        .collect();
+    Ok(hidden)
+}
+
+/// Returns the IDs of the items that the options do not include, and that do not lead to an
+/// included item. Crates are always included.
+fn not_included(document: &JsonApiDocument, options: &RenderOptions) -> Vec<String> {
+    if options.included.is_empty() {
+        return vec![];
+    }
+
+    let mut kept = HashSet::new();
+    for resource in resources(document).filter(|resource| options.is_included(&resource.id)) {
+        let id = resource.id.as_str();
+        kept.extend(id.match_indices("::").map(|(end, _)| &id[..end]));
+        kept.insert(id);
+    }
+
+    resources(document)
+        .filter(|resource| resource._type != "crate" && !kept.contains(resource.id.as_str()))
+        .map(|resource| resource.id.clone())
+        .collect()
+}
+
+/// Returns a copy of the document without the resources that the options exclude or that are
+/// under an omitted item, or the relationships that refer to them.
+///
+/// The omitted items are looked up by ID rather than matched like the globs of the options, since
+/// a crate as large as the standard library has thousands of private and hidden items.
+fn exclude_resources(
+    document: &JsonApiDocument,
+    options: &RenderOptions,
+    omitted: &HashSet<&str>,
+) -> JsonApiDocument {
+    let is_excluded = |id: &str| {
+        let ancestors = id.match_indices("::").map(|(end, _)| &id[..end]);
+        options.is_excluded(id) || ancestors.chain(Some(id)).any(|path| omitted.contains(path))
+    };
+    let mut document = document.clone();
+
+    if let Some(PrimaryData::Multiple(ref mut crates)) = document.data {
+        crates.retain(|krate| !is_excluded(&krate.id));
+    }
+
+    if let Some(ref mut included) = document.included {
+        included.retain(|resource| !is_excluded(&resource.id));
+    }
+
+    let primary = match document.data {
+        Some(PrimaryData::Single(ref mut resource)) => slice::from_mut(&mut **resource),
+        Some(PrimaryData::Multiple(ref mut resources)) => resources.as_mut_slice(),
+        Some(PrimaryData::None) | None => &mut [],
+    };
+    let included = document.included.iter_mut().flat_map(|included| included.iter_mut());
+
+    for resource in primary.iter_mut().chain(included) {
+        if let Some(ref mut relationships) = resource.relationships {
+            relationships.retain(|_, relationship| match relationship.data {
+                IdentifierData::Single(ref id) => !is_excluded(&id.id),
+                IdentifierData::Multiple(ref mut ids) => {
+                    ids.retain(|id| !is_excluded(&id.id));
+                    true
+                }
+                IdentifierData::None => true,
+            });
+        }
+    }
+
+    document
+}
+
+/// Merges several documents into one, whose primary data are the crates of every document.
+///
+/// When more than one document contains a resource with the same ID, the first one is kept.
+fn merge_documents(documents: &[JsonApiDocument]) -> JsonApiDocument {
+    let mut ids = HashSet::new();
+    let mut primary = vec![];
+    let mut included = vec![];
+
+    for document in documents {
+        for krate in crates(document) {
+            if ids.insert(krate.id.as_str()) {
+                primary.push(krate.clone());
+            }
+        }
+    }
+
+    for resource in documents.iter().flat_map(|document| document.included.iter().flatten()) {
+        if ids.insert(resource.id.as_str()) {
+            included.push(resource.clone());
+        }
+    }
+
+    // The metadata of the documents, such as the contents of their source files, is merged one
+    // level deep, keeping the value of the first document that has it.
+    let mut meta = Meta::new();
+    for (key, value) in documents.iter().flat_map(|document| document.meta.iter().flatten()) {
+        let merged = meta.entry(key.clone()).or_insert_with(|| value.clone());
+        if let (Some(merged), Some(object)) = (merged.as_object_mut(), value.as_object()) {
+            for (key, value) in object {
+                if !merged.contains_key(key) {
+                    merged.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    JsonApiDocument {
+        data: Some(PrimaryData::Multiple(primary)),
+        included: Some(included),
+        meta: Some(meta).filter(|meta| !meta.is_empty()),
+        ..Default::default()
+    }
+}
+
+/// Writes the pages that cover the whole crate: the source pages, the deprecated items, the
+/// documentation statistics and the API changes, the landing page of a document with several
+/// crates, and the search index.
+fn write_crate_pages(
+    document: &Index,
+    output: &Output,
+    report: &mut RenderReport,
+) -> Result<()> {
+    write_crate_index(document, output, report)?;
+    source::render_sources(document, output, output.source_dir, report)?;
+    write_deprecated_index(document, output, report)?;
+    write_stats(document, output, report)?;
+    write_changes(document, output, report)?;
+    write_search_index(document, output, report)
+}
+
+/// Where and how the pages of a render are written.
+struct Output<'a, 'q> {
+    handlebars: &'a Handlebars,
+    doc_root: &'a Path,
+    open_files: &'a FileLimit,
+    backend: &'a dyn OutputBackend,
+
+    /// The queue of the writers that files are handed to instead of the backend, if any.
+    queue: Option<&'a WriteQueue<'q>>,
+
+    hooks: &'a Hooks,
+
+    /// Whether pages are rendered without writing them, to check the document.
+    dry_run: bool,
+
+    /// The paths of the fragments shared between pages, relative to the doc root, by name.
+    fragments: Value,
+
+    /// The paths of the stylesheets and scripts of pages, relative to the doc root, by name.
+    assets: Value,
+
+    /// The hashes of the contents of the files written so far, by path, if they are recorded.
+    content_hashes: Option<Mutex<BTreeMap<PathBuf, String>>>,
+
+    /// The version that the documentation is rendered under, if it is versioned.
+    version: Option<&'a str>,
+
+    /// The older document that the API changes are listed since, if any.
+    changes_since: Option<&'a JsonApiDocument>,
+
+    /// The URL that the documentation is hosted at, which is that of the shared root of
+    /// versioned documentation.
+    base_url: Option<&'a str>,
+
+    /// The Markdown of the README rendered on the page of the first crate, if any.
+    readme: Option<&'a str>,
+
+    /// The logo and favicon of each crate of the document, in the order of the crates.
+    icons: Vec<(String, Icons)>,
+
+    /// The language of the pages, for their `lang` attribute.
+    lang: &'a str,
+
+    /// The analytics snippet that is added to the head of every page, if any.
+    analytics: Option<&'a str>,
+
+    /// Whether the README replaces the docs of the crate root, instead of following them.
+    readme_replaces_docs: bool,
+
+    /// The directory that the source files of every crate are read from, if it is not that of
+    /// each crate.
+    source_dir: Option<&'a Path>,
+
+    /// The crates of the dependencies rendered under `deps/`, which crate pages link to.
+    dependencies: Vec<&'a Resource>,
+}
+
+impl<'a, 'q> Output<'a, 'q> {
+    /// Prepares the output of a render into a target, without assets, icons or shared fragments,
+    /// which are added once their files are written.
+    fn new(
+        renderer: &'a Renderer,
+        target: &Target<'a>,
+        backend: &'a dyn OutputBackend,
+        open_files: &'a FileLimit,
+        analytics: Option<&'a str>,
+    ) -> Output<'a, 'q> {
+        let options = &renderer.options;
+        Output {
+            handlebars: &renderer.handlebars,
+            doc_root: target.doc_root,
+            open_files,
+            backend,
+            queue: None,
+            hooks: &renderer.hooks,
+            dry_run: options.dry_run,
+            fragments: json!({}),
+            assets: json!({}),
+            content_hashes: if options.content_hashes { Some(Mutex::default()) } else { None },
+            version: target.version,
+            changes_since: target.changes_since,
+            base_url: options.base_url.as_deref(),
+            readme: target.readme,
+            readme_replaces_docs: options.readme_replaces_docs,
+            source_dir: options.source_dir.as_deref(),
+            icons: vec![],
+            lang: options.lang.as_deref().unwrap_or(options::DEFAULT_LANG),
+            analytics,
+            dependencies: target.dependencies.iter().flat_map(|d| crates(d)).collect(),
+        }
+    }
+
+    /// Renders a template straight into a file under the doc root, without building the page in
+    /// memory first, notifying observers if it fails.
+    ///
+    /// In a dry run, the template is rendered and the result discarded.
+    fn render(&self, template: &str, context: &Value, path: &Path) -> Result<()> {
+        let render = |writer: &mut dyn Write| {
+            render_to_writer(self.handlebars, template, context, writer).inspect_err(|error| {
+                self.hooks.emit(&RenderEvent::TemplateError { template, path, error });
+            })
+        };
+
+        self.write_with(path, render)
+    }
+
+    /// Renders the template of a file under the doc root, notifying observers if it fails.
+    fn render_to_string(&self, template: &str, context: &Value, path: &Path) -> Result<String> {
+        let mut page = vec![];
+
+        match render_to_writer(self.handlebars, template, context, &mut page) {
+            Ok(()) => Ok(String::from_utf8(page).expect("handlebars rendered invalid UTF-8")),
+            Err(error) => {
+                self.hooks.emit(&RenderEvent::TemplateError { template, path, error: &error });
+                Err(error)
+            }
+        }
+    }
+
+    /// Returns the URL that a file under the doc root is hosted at, for the canonical link of its
+    /// page, if the base URL is set.
+    fn canonical_url(&self, path: &Path) -> Option<String> {
+        let base_url = self.base_url?.trim_end_matches('/');
+        let page = path.strip_prefix(self.doc_root).ok()?;
+        let page = page.iter().map(|c| c.to_string_lossy()).collect::<Vec<_>>().join("/");
+
+        Some(match self.version {
+            Some(version) => format!("{}/{}/{}", base_url, version, page),
+            None => format!("{}/{}", base_url, page),
+        })
+    }
+
+    /// Returns the logo and favicon of a crate, or those of the first crate of the document if the
+    /// crate is not given.
+    fn crate_icons(&self, krate: Option<&str>) -> Option<&Icons> {
+        let icons = self.icons.iter().find(|(name, _)| Some(name.as_str()) == krate);
+        icons.or_else(|| self.icons.first()).map(|(_, icons)| icons)
+    }
+
+    /// Returns the links to the logo and favicon of a crate from a page, for the `icons` of its
+    /// context.
+    fn icons(&self, krate: Option<&str>, path_to_root: &str) -> Value {
+        let href = |icon: &Option<Icon>| match *icon {
+            Some(Icon::File(ref file)) => Some(format!("{}/{}", path_to_root, file)),
+            Some(Icon::Url(ref url)) => Some(self.absolute_url(url)),
+            None => None,
+        };
+
+        match self.crate_icons(krate) {
+            Some(icons) => json!({ "logo": href(&icons.logo), "favicon": href(&icons.favicon) }),
+            None => json!({}),
+        }
+    }
+
+    /// Returns the URL of the logo of a crate, if it has one, for the cards of its pages. A logo
+    /// in the doc root is hosted at its canonical URL, since the cards are not on the page.
+    fn logo_url(&self, krate: Option<&str>) -> Option<String> {
+        match self.crate_icons(krate)?.logo {
+            Some(Icon::File(ref file)) => Some(
+                self.canonical_url(&self.doc_root.join(file)).unwrap_or_else(|| file.clone()),
+            ),
+            Some(Icon::Url(ref url)) => Some(self.absolute_url(url)),
+            None => None,
+        }
+    }
+
+    /// Resolves a relative URL against the base URL, if it is set.
+    fn absolute_url(&self, url: &str) -> String {
+        match self.base_url {
+            Some(base_url) if !url.contains("://") && !url.starts_with("data:") => {
+                format!("{}/{}", base_url.trim_end_matches('/'), url.trim_start_matches('/'))
+            }
+            _ => String::from(url),
+        }
+    }
+
+    /// Writes a file under the doc root as it is produced, for files too large to build in memory
+    /// first. The file is only built in memory to be handed to the writers of a queue.
+    fn write_with<F>(&self, path: &Path, write: F) -> Result<()>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<()>,
+    {
+        match self.queue {
+            Some(queue) if !self.dry_run => {
+                let mut contents = vec![];
+                write(&mut contents)?;
+                self.enqueue(queue, path, contents)
+            }
+            _ => self.stream(path, write),
+        }
+    }
+
+    /// Writes a file under the doc root through the backend. Nothing is written in a dry run.
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<()> {
+        match self.queue {
+            Some(queue) if !self.dry_run => self.enqueue(queue, path, contents.to_vec()),
+            _ => self.stream(path, |writer| Ok(writer.write_all(contents)?)),
+        }
+    }
+
+    /// Hands a file under the doc root to the writers of a queue, recording the hash of its
+    /// contents. Waits while the queue is full.
+    fn enqueue(&self, queue: &WriteQueue, path: &Path, contents: Vec<u8>) -> Result<()> {
+        let relative = self.relative(path)?;
+        if let Some(ref hashes) = self.content_hashes {
+            hashes.lock().unwrap().insert(path.to_owned(), hashes::content_hash(&contents));
+        }
+        Ok(queue.write(relative.to_owned(), contents)?)
+    }
+
+    /// Returns the path of a file under the doc root, relative to the doc root.
+    fn relative<'p>(&self, path: &'p Path) -> Result<&'p Path> {
+        Ok(path.strip_prefix(self.doc_root).map_err(|_| ErrorKind::Path(path.to_owned()))?)
+    }
+
+    /// Writes a file under the doc root as a stream through the backend, recording the hash of
+    /// its contents. In a dry run, the contents are discarded as they are written.
+    ///
+    /// The file counts towards the limit of open files while it is being written.
+    fn stream<F>(&self, path: &Path, write: F) -> Result<()>
+    where
+        F: FnOnce(&mut dyn Write) -> Result<()>,
+    {
+        if self.dry_run {
+            return write(&mut io::sink());
+        }
+
+        let relative = self.relative(path)?;
+        let _permit = self.open_files.acquire();
+        let mut file = HashWriter::new(self.backend.create(relative)?);
+        write(&mut file)?;
+        file.flush()?;
+        if let Some(ref hashes) = self.content_hashes {
+            hashes.lock().unwrap().insert(path.to_owned(), file.hash());
+        }
+        Ok(())
+    }
+}
+
+/// The logo and favicon of a crate.
+#[derive(Debug, Default)]
+struct Icons {
+    logo: Option<Icon>,
+    favicon: Option<Icon>,
+}
+
+/// An image that the pages of a crate show.
+#[derive(Debug)]
+enum Icon {
+    /// A file that was copied into the doc root, by its path relative to the doc root.
+    File(String),
+
+    /// An image that is hosted elsewhere, or on the site at a URL relative to the base URL.
+    Url(String),
+}
+
+/// Copies the logos and favicons of the crates of a document that are files into the doc root,
+/// returning the icons of each crate.
+///
+/// The icons of the options take precedence over the `html_logo_url` and `html_favicon_url` of
+/// each crate. The name of each file that is copied contains a hash of its contents, like the
+/// names of assets, so that it can be cached forever.
+///
+/// Only the icons of the options are copied. The icons of the document are always URLs, even if
+/// they happen to name a local file, so that a document cannot publish files of the machine it
+/// is rendered on.
+fn write_icons(
+    document: &Index,
+    options: &RenderOptions,
+    output: &Output,
+    report: &mut RenderReport,
+) -> Result<Vec<(String, Icons)>> {
+    let mut icon = |name: &str, url: Option<&str>, document_url| -> Result<Option<Icon>> {
+        let url = match (url, document_url) {
+            (Some(url), _) => url,
+            (None, Some(url)) => return Ok(Some(Icon::Url(String::from(url)))),
+            (None, None) => return Ok(None),
+        };
+        let source = Path::new(url);
+        if !source.is_file() {
+            return Ok(Some(Icon::Url(String::from(url))));
+        }
+
+        let contents =
+            fs::read(source).chain_err(|| format!("could not read {} `{}`", name, url))?;
+        let hash = hashes::content_hash(&contents);
+        let file = match source.extension() {
+            Some(extension) => format!("{}-{}.{}", name, &hash[..8], extension.to_string_lossy()),
+            None => format!("{}-{}", name, &hash[..8]),
+        };
+
+        let path = output.doc_root.join(&file);
+        if !report.files_written.contains(&path) {
+            info!("copying {} `{}` to `{}`", name, url, path.display());
+            output.write(&path, &contents)?;
+            report.files_written.push(path);
+        }
+        Ok(Some(Icon::File(file)))
+    };
+
+    let mut icons = vec![];
+    for krate in crates(document) {
+        let metadata = match document.item(krate).as_deref().map(|item| &item.kind) {
+            Some(model::Kind::Crate(metadata)) => metadata.clone(),
+            _ => model::Crate::default(),
+        };
+        let crate_icons = Icons {
+            logo: icon("logo", options.logo_url.as_deref(), metadata.logo_url)?,
+            favicon: icon("favicon", options.favicon_url.as_deref(), metadata.favicon_url)?,
+        };
+        icons.push((krate.id.clone(), crate_icons));
+    }
+    Ok(icons)
+}
+
+/// Writes a placeholder page for an item whose page could not be rendered, with the error.
+///
+/// Failing to write the placeholder is only recorded as a warning, so that the render can go on.
+fn write_error_page(
+    document: &Index,
+    resource: &Resource,
+    output: &Output,
+    error: &Error,
+    report: &mut RenderReport,
+) {
+    let page = match document.path(resource) {
+        Some(page) => page,
+        None => return,
+    };
+
+    let path = output.doc_root.join(page);
+    let path_to_root = html_diff_paths(output.doc_root, &path);
+    let krate = resource.id.split("::").next();
+    let context = json!({
+        "path": resource.id,
+        "pathToRoot": path_to_root,
+        "error": error.to_string(),
+        "lang": output.lang,
+        "analytics": output.analytics,
+        "assets": output.assets,
+        "icons": output.icons(krate, path_to_root.as_deref().unwrap_or(".")),
+    });
+
+    match output.render("error", &context, &path) {
+        Ok(()) => report.files_written.push(path),
+        Err(e) => {
+            report.warn(format!("could not write the error page of `{}`: {}", resource.id, e))
+        }
+    }
+}
+
+/// Writes the fragments shared between item pages, returning their paths relative to the doc
+/// root by name.
+///
+/// The name of each fragment file contains a hash of its contents, so that browsers never use a
+/// cached fragment from a previous render. Fragments are rendered without the context of a page;
+/// what differs between pages is kept on the placeholders, and filled in by the script.
+fn write_fragments(output: &Output, report: &mut RenderReport) -> Result<Value> {
+    let mut fragments = serde_json::Map::new();
+
+    for &name in FRAGMENTS {
+        let fragment = output
+            .handlebars
+            .render(name, &json!({}))
+            .chain_err(|| ErrorKind::Template(String::from(name)))?;
+
+        let hash = hashes::content_hash(fragment.as_bytes());
+        let path = format!("fragments/{}-{}.html", name, &hash[..8]);
+
+        info!("writing {} fragment to `{}`", name, path);
+        output.write(&output.doc_root.join(&path), fragment.as_bytes())?;
+        report.files_written.push(output.doc_root.join(&path));
+
+        fragments.insert(String::from(name), json!(path));
+    }
+
+    Ok(Value::Object(fragments))
+}
+
+/// Returns the schema.org description of the page of an item as JSON-LD, which search engines use
+/// for rich results. The JSON is escaped so that it can be embedded in a `<script>` element.
+fn structured_data(
+    resource: &Resource,
+    krate: &Value,
+    description: &str,
+    url: Option<&str>,
+) -> String {
+    let mut data = json!({
+        "@context": "https://schema.org",
+        "@type": "APIReference",
+        "name": resource.id,
+        "articleSection": resource._type,
+        "description": description,
+        "programmingLanguage": "Rust",
+        "isPartOf": {
+            "@type": "SoftwareSourceCode",
+            "name": krate["name"],
+            "programmingLanguage": "Rust",
+        },
+    });
+    if let Some(url) = url {
+        data["url"] = Value::String(String::from(url));
+    }
+    if let Some(version) = krate["version"].as_str() {
+        data["isPartOf"]["version"] = Value::String(String::from(version));
+    }
+
+    serde_json::to_string(&data).unwrap().replace("</", "<\\/")
+}
+
+/// Writes a documentation file at the documentation root.
+///
+/// The page is skipped if the previous run rendered it from the same context, according to the
+/// `previous` manifest. The hash of the context is recorded in `manifest` either way.
+fn write_doc(
+    document: &Index,
+    resource: &Resource,
+    output: &Output,
+    previous: &Manifest,
+    manifest: &mut Manifest,
+    report: &mut RenderReport,
+) -> Result<()> {
+    let doc_root = output.doc_root;
+
+    if let Some(page) = document.path(resource) {
+        let path = doc_root.join(&page);
+        let context = page_context(document, resource, &page, output);
+        let hash = manifest::hash(&context);
+
+        if previous.is_fresh(&page, &hash) && path.exists() {
+            debug!("skipping unchanged `{}`", path.display());
+            manifest.insert(&page, hash);
+            report.skipped.push(resource.id.clone());
+            return Ok(());
+        }
+
+        info!("rendering `{}` as `{}`", resource.id, path.display());
+        debug!("context: {}", context);
+        // Hooks are given the whole page, so it is only held in memory if there are any.
+        if output.hooks.after_item.is_empty() {
+            output.render("item", &context, &path)?;
+        } else {
+            let mut html = output.render_to_string("item", &context, &path)?;
+            output.hooks.after_item(resource, &mut html);
+            output.write(&path, html.as_bytes())?;
+        }
+        output.hooks.emit(&RenderEvent::ItemRendered { id: &resource.id, path: &path });
+        manifest.insert(&page, hash);
+        report.files_written.push(path);
+    }
+
+    Ok(())
+}
+
+/// Returns the context that the page of a resource is rendered from, at a path relative to the
+/// doc root, after the `before_item` hooks.
+fn page_context(document: &Index, resource: &Resource, page: &Path, output: &Output) -> Value {
+    let doc_root = output.doc_root;
+    let path = doc_root.join(page);
+    let mut context = generate_context(doc_root, document, resource);
+    context.as_object_mut().unwrap().insert(
+        String::from("fragments"),
+        output.fragments.clone(),
+    );
+    context.as_object_mut().unwrap().insert(String::from("assets"), output.assets.clone());
+    context.as_object_mut().unwrap().insert(String::from("lang"), json!(output.lang));
+    let icons = output.icons(
+        context["crate"]["name"].as_str(),
+        context["pathToRoot"].as_str().unwrap_or("."),
+    );
+    context.as_object_mut().unwrap().insert(String::from("icons"), icons);
+
+    // Search engines show the description of a page along with its link, and index the
+    // canonical URL of a page that is hosted at more than one.
+    let description = summary_for_resource(document, resource).unwrap_or_else(|| {
+        format!("API documentation for the {} `{}`.", resource._type, resource.id)
+    });
+    let canonical_url = output.canonical_url(&path);
+
+    // Chat apps and social networks unfurl links to a page into a card with its Open Graph
+    // title, description and image.
+    let image = output.logo_url(context["crate"]["name"].as_str());
+    context.as_object_mut().unwrap().insert(
+        String::from("openGraph"),
+        json!({
+            "title": resource.id,
+            "description": description,
+            "url": canonical_url,
+            "image": image,
+        }),
+    );
+    let structured_data =
+        structured_data(resource, &context["crate"], &description, canonical_url.as_deref());
+    context.as_object_mut().unwrap().insert(
+        String::from("structuredData"),
+        Value::String(structured_data),
+    );
+    context.as_object_mut().unwrap().insert(
+        String::from("description"),
+        Value::String(description),
+    );
+    if let Some(canonical_url) = canonical_url {
+        context.as_object_mut().unwrap().insert(
+            String::from("canonicalUrl"),
+            Value::String(canonical_url),
+        );
+    }
+    if let Some(analytics) = output.analytics {
+        context.as_object_mut().unwrap().insert(
+            String::from("analytics"),
+            Value::String(String::from(analytics)),
+        );
+    }
+    if let Some(version) = output.version {
+        let krate = context["crate"]["name"].as_str();
+        let krate = krate.and_then(|name| document.path_by_id(name));
+        context.as_object_mut().unwrap().insert(
+            String::from("version"),
+            json!({ "name": version, "page": page, "cratePage": krate }),
+        );
+    }
+    if output.changes_since.is_some() && resource._type == "crate" {
+        let path_to_root = context["pathToRoot"].as_str().unwrap_or(".");
+        let changes_link = format!("{}/{}", path_to_root, diff::CHANGES_PAGE);
+        context.as_object_mut().unwrap().insert(
+            String::from("changesLink"),
+            Value::String(changes_link),
+        );
+    }
+    let first_crate = crates(document).first().map(|krate| &krate.id);
+    if let (Some(readme), true) = (output.readme, first_crate == Some(&resource.id)) {
+        let readme = markdown::render_with_links(readme, |dest| {
+            let id = intra_doc_target(document, resource, dest)?;
+            html_diff_paths(&document.path_by_id(&id)?, page)
+        });
+        let docs = match context["docs"].as_str() {
+            Some(docs) if !output.readme_replaces_docs => format!("{}{}", docs, readme),
+            _ => readme,
+        };
+        context.as_object_mut().unwrap().insert(String::from("docs"), Value::String(docs));
+    }
+    if !output.dependencies.is_empty() && resource._type == "crate" {
+        let dependencies = output
+            .dependencies
+            .iter()
+            .map(|dependency| {
+                json!({
+                    "name": dependency.id,
+                    "link": path_for_resource(dependency).and_then(|path| {
+                        html_diff_paths(&Path::new(DEPS_DIR).join(path), page)
+                    }),
+                    "summary": summary_for_resource(document, dependency),
+                })
+            })
+            .collect();
+        let sections = context.as_object_mut().unwrap().entry("sections");
+        sections.or_insert_with(|| json!({}))["dependencies"] = Value::Array(dependencies);
+    }
+    output.hooks.before_item(resource, &mut context);
+    context
+}
+
+/// Writes a landing page at the documentation root that links to the page of every crate.
+///
+/// No page is written if the document has a single crate, since its page is the landing page.
+fn write_crate_index(
+    document: &Index,
+    output: &Output,
+    report: &mut RenderReport,
+) -> Result<()> {
+    let page = Path::new("index.html");
+
+    let crates = crates(document);
+    if crates.len() < 2 {
+        return Ok(());
+    }
+
+    let crates = crates
+        .iter()
+        .map(|krate| {
+            let mut metadata = metadata(krate, document.item(krate).as_deref());
+            metadata["link"] = json!(path_for_resource(krate)
+                .and_then(|path| html_diff_paths(&path, page)));
+            metadata["summary"] = json!(summary_for_resource(document, krate));
+            metadata
+        })
+        .collect::<Vec<_>>();
+
+    let path = output.doc_root.join(page);
+    let context = json!({
+        "pathToRoot": ".",
+        "canonicalUrl": output.canonical_url(&path),
+        "lang": output.lang,
+        "analytics": output.analytics,
+        "assets": output.assets,
+        "icons": output.icons(None, "."),
+        "crates": crates,
+    });
+
+    info!("rendering the crate index as `{}`", path.display());
+    output.render("crates", &context, &path)?;
+    report.files_written.push(path);
+    Ok(())
+}
+
+/// Writes the index that the search box of item pages searches, as `search-index.js` at the doc
+/// root.
+///
+/// Every item with a page is listed along with its crate, so that the items of every crate in the
+/// document can be found from any page. The index is a script rather than JSON so that it can be
+/// loaded when the pages are opened from the filesystem.
+fn write_search_index(
+    document: &Index,
+    output: &Output,
+    report: &mut RenderReport,
+) -> Result<()> {
+    let path = output.doc_root.join(SEARCH_INDEX);
+    info!("writing the search index to `{}`", path.display());
+
+    // The entries are written into the file one at a time, since a single JSON value for the
+    // index of a large document would take much more memory than the script, which itself grows
+    // with the number of items.
+    output.write_with(&path, |writer| {
+        writer.write_all(b"window.searchIndex = [")?;
+        let mut first = true;
+        for resource in resources(document) {
+            let link = match path_for_resource(resource) {
+                Some(link) => link,
+                None => continue,
+            };
+            let id = &resource.id[..];
+            let entry = json!({
+                "name": document.name(id),
+                "path": id,
+                "type": resource._type,
+                "crate": id.split("::").next().unwrap_or(id),
+                "link": link.iter().map(|c| c.to_string_lossy()).collect::<Vec<_>>().join("/"),
+                "summary": summary_for_resource(document, resource),
+            });
+            if !first {
+                writer.write_all(b",")?;
+            }
+            first = false;
+            writer.write_all(entry.to_string().as_bytes())?;
+        }
+        Ok(writer.write_all(b"];\n")?)
+    })?;
+    report.files_written.push(path);
+    Ok(())
+}
+
+/// Writes a page at the documentation root listing every deprecated item.
+///
+/// No page is written if the crate has no deprecated items.
+fn write_deprecated_index(
+    document: &Index,
+    output: &Output,
+    report: &mut RenderReport,
+) -> Result<()> {
+    let page = Path::new("deprecated.html");
+
+    let mut items = resources(document)
+        .filter_map(|resource| {
+            deprecation_for_resource(document, resource).map(|deprecation| {
+                json!({
+                    "type": resource._type,
+                    "path": resource.id,
+                    "link": path_for_resource(resource)
+                        .and_then(|path| html_diff_paths(&path, page)),
+                    "deprecation": deprecation,
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    items.sort_by(|a, b| a["path"].as_str().cmp(&b["path"].as_str()));
+
+    let path = output.doc_root.join(page);
+    let context = json!({
+        "pathToRoot": ".",
+        "canonicalUrl": output.canonical_url(&path),
+        "lang": output.lang,
+        "analytics": output.analytics,
+        "assets": output.assets,
+        "icons": output.icons(None, "."),
+        "crate": crate_metadata(document),
+        "items": items,
+    });
+
+    info!("rendering deprecated items as `{}`", path.display());
+    output.render("deprecated", &context, &path)?;
+    report.files_written.push(path);
+    Ok(())
+}
+
+/// Writes documentation statistics at the documentation root, both as a page and as JSON.
+fn write_stats(
+    document: &JsonApiDocument,
+    output: &Output,
+    report: &mut RenderReport,
+) -> Result<()> {
+    let stats = stats::Stats::collect(document).to_json();
+
+    let path = output.doc_root.join("stats.html");
+    let context = json!({
+        "pathToRoot": ".",
+        "canonicalUrl": output.canonical_url(&path),
+        "lang": output.lang,
+        "analytics": output.analytics,
+        "assets": output.assets,
+        "icons": output.icons(None, "."),
+        "crate": crate_metadata(document),
+        "stats": stats,
+    });
+
+    info!("rendering documentation statistics as `{}`", path.display());
+    output.render("stats", &context, &path)?;
+    report.files_written.push(path);
+
+    let json = serde_json::to_string_pretty(&stats).unwrap();
+    let path = output.doc_root.join("stats.json");
+    output.write(&path, json.as_bytes())?;
+    report.files_written.push(path);
+    Ok(())
+}
+
+/// Writes the page that lists the changes to the public API since an older document, if the
+/// changes are listed.
+fn write_changes(
+    document: &JsonApiDocument,
+    output: &Output,
+    report: &mut RenderReport,
+) -> Result<()> {
+    let old = match output.changes_since {
+        Some(old) => old,
+        None => return Ok(()),
+    };
+
+    let path = output.doc_root.join(diff::CHANGES_PAGE);
+    let mut context = diff::changes_context(&diff::diff(old, document)?);
+    context["canonicalUrl"] = json!(output.canonical_url(&path));
+    context["lang"] = json!(output.lang);
+    context["analytics"] = json!(output.analytics);
+    context["assets"] = output.assets.clone();
+    context["icons"] = output.icons(None, ".");
+    info!("rendering API changes as `{}`", path.display());
+    output.render("changes", &context, &path)?;
+    report.files_written.push(path);
+    Ok(())
+}
+
+/// Renders a template into a writer.
+fn render_to_writer<W: Write>(
+    handlebars: &Handlebars,
+    template: &str,
+    context: &Value,
+    mut writer: W,
+) -> Result<()> {
+    handlebars
+        .renderw(template, context, &mut writer)
+        .chain_err(|| ErrorKind::Template(template.to_owned()))
+}
+
+/// Generates a context to be used when rendering a resource with handlebars.
+fn generate_context(root: &Path, document: &Index, resource: &Resource) -> Value {
+    let path_to_root = document.path(resource).and_then(|path| {
+        let path = root.join(path);
+        html_diff_paths(root, &path)
+    });
+
+    let mut context = json!({
+        "type": resource._type,
+        "name": document.name(&resource.id),
+        "path": resource.id,
+        "pathToRoot": path_to_root,
+        "crate": crate_metadata_for_resource(document, resource),
+    });
+
+    let source_link = document.path(resource).and_then(|path| {
+        let location = source::location_for_resource(document, resource)?;
+        let krate = resource.id.split("::").next().unwrap_or(&resource.id);
+        source::link(&path, krate, &location)
+    });
+
+    if let Some(source_link) = source_link {
+        context.as_object_mut().unwrap().insert(
+            String::from("sourceLink"),
+            Value::String(source_link),
+        );
+    }
+
+    // The siblings are sorted, so the resource is found without comparing it to every sibling of
+    // a module with many items.
+    let siblings = document.siblings(resource);
+    let key = (&resource._type, &resource.id);
+    let position = siblings.binary_search_by(|sibling| (&sibling._type, &sibling.id).cmp(&key));
+    if let Ok(index) = position {
+        let neighbors = [
+            ("prev", index.checked_sub(1).map(|index| siblings[index])),
+            ("next", siblings.get(index + 1).cloned()),
+        ];
+
+        for &(key, sibling) in &neighbors {
+            if let Some(sibling) = sibling {
+                context.as_object_mut().unwrap().insert(
+                    String::from(key),
+                    json!({
+                        "type": sibling._type,
+                        "name": document.name(&sibling.id),
+                        "link": link(document, resource, sibling),
+                        "summary": summary_for_resource(document, sibling),
+                    }),
+                );
+            }
+        }
+    }
+
+    if let Some(cfg) = cfg_for_resource(document, resource) {
+        context.as_object_mut().unwrap().insert(
+            String::from("cfg"),
+            Value::String(cfg.banner()),
+        );
+    }
+
+    if let Some(deprecation) = deprecation_for_resource(document, resource) {
+        context.as_object_mut().unwrap().insert(String::from("deprecation"), deprecation);
+    }
+
+    // Pages link to the pages at the doc root, which resources without a page cannot link to.
+    if let Some(ref path_to_root) = path_to_root {
+        // Documents with several crates have a landing page that lists them at the doc root.
+        if crates(document).len() > 1 {
+            context.as_object_mut().unwrap().insert(
+                String::from("cratesLink"),
+                Value::String(format!("{}/index.html", path_to_root)),
+            );
+        }
+
+        if resource._type == "crate" {
+            context.as_object_mut().unwrap().insert(
+                String::from("statsLink"),
+                Value::String(format!("{}/stats.html", path_to_root)),
+            );
+        }
+
+        if resource._type == "crate" && document.has_deprecated() {
+            context.as_object_mut().unwrap().insert(
+                String::from("deprecatedLink"),
+                Value::String(format!("{}/deprecated.html", path_to_root)),
+            );
+        }
+    }
+
+    if let Some(stability) = stability_for_resource(document, resource) {
+        context.as_object_mut().unwrap().insert(String::from("stability"), stability);
+    }
+
+    let notable_traits = notable_traits(document, resource);
+    if !notable_traits.is_empty() {
+        context.as_object_mut().unwrap().insert(
+            String::from("notableTraits"),
+            Value::Array(notable_traits),
+        );
+    }
+
+    if let Some(docs) = docs_for_page(document, resource, resource) {
+        context.as_object_mut().unwrap().insert(
+            String::from("docs"),
+            Value::String(docs),
+        );
+    }
+
+    if let Some(relationships) = resource.relationships.as_ref() {
+        let mut sections = json!({});
+
+        for (key, data) in relationships {
+            let json_resources = identifiers(&data.data)
+                .iter()
+                .flat_map(|child| {
+                    // Missing resources are reported when the document is validated.
+                    let child = resource_by_id(document, &child.id)?;
+
+                    let name = document.name(&child.id);
+
+                    // Create a link to the child resource. Since /index.html paths in the
+                    // browser actually act like folders, we need to diff the paths from the
+                    // parent folder.
+                    let link = link(document, resource, child);
+
+                    let json = json!({
+                        "name": name,
+                        "link": link,
+                        "docs": docs_for_page(document, resource, child),
+                        "summary": summary_for_resource(document, child),
+                        "cfg": cfg_for_resource(document, child).map(|cfg| cfg.badge()),
+                        "stability": stability_for_resource(document, child),
+                    });
+
+                    Some(json)
+                })
+                .collect();
+
+            sections.as_object_mut().unwrap().insert(
+                key.clone(),
+                Value::Array(
+                    json_resources,
+                ),
+            );
+
+        }
+
+        context.as_object_mut().unwrap().insert(
+            String::from("sections"),
+            sections,
+        );
+    }
+
+    context
+}
+
+/// Creates a link to a child resource if a page exists for it.
+fn link(document: &Index, resource: &Resource, child: &Resource) -> Option<String> {
+    match (document.path(resource), document.path(child)) {
+        (Some(parent_path), Some(child_path)) => html_diff_paths(&child_path, &parent_path),
+        _ => None,
+    }
+}
+
+/// Returns the metadata of the crate being documented, taken from the primary resource.
+///
+/// If the document contains several crates, the first one is used.
+fn crate_metadata(document: &JsonApiDocument) -> Value {
+    match crates(document).first() {
+        Some(krate) => metadata(krate, model::Item::from_resource(krate).ok().as_ref()),
+        None => Value::Null,
+    }
+}
+
+/// Returns the metadata of the crate that a resource belongs to.
+///
+/// The metadata is displayed in the header of every page.
+fn crate_metadata_for_resource(document: &Index, resource: &Resource) -> Value {
+    let name = resource.id.split("::").next();
+
+    match crates(document).iter().find(|krate| Some(krate.id.as_str()) == name) {
+        Some(krate) => metadata(krate, document.item(krate).as_deref()),
+        None => crate_metadata(document),
+    }
+}
+
+/// Returns the metadata stored in the attributes of a crate resource, given its typed view.
+fn metadata(krate: &Resource, item: Option<&model::Item>) -> Value {
+    let metadata = match item.map(|item| &item.kind) {
+        Some(model::Kind::Crate(metadata)) => metadata.clone(),
+        _ => model::Crate::default(),
+    };
+
+    json!({
+        "name": krate.id,
+        "version": metadata.version,
+        "description": metadata.description,
+        "license": metadata.license,
+        "repository": metadata.repository,
+        "homepage": metadata.homepage,
+        "documentation": metadata.documentation,
+    })
+}
+
+/// Returns the notable traits implemented by the return type of a function.
+///
+/// The return type is given by the function's `output` attribute, which contains the ID of the
+/// returned type. Implementations are found through the type's `impls` relationship.
+fn notable_traits(document: &Index, resource: &Resource) -> Vec<Value> {
+    const NOTABLE_TRAITS: &[&str] = &["Iterator", "Future", "Read", "Write", "BufRead"];
+
+    let output = match document.item(resource).as_deref().map(|item| &item.kind) {
+        Some(model::Kind::Function(model::Function { output: Some(output) })) => *output,
+        _ => return vec![],
+    };
+
+    let output = match resource_by_id(document, output) {
+        Some(output) => output,
+        None => return vec![],
+    };
+
+    let impls = match output.relationships.as_ref().and_then(|r| r.get("impls")) {
+        Some(relationship) => identifiers(&relationship.data),
+        None => return vec![],
+    };
+
+    impls
+        .iter()
+        .filter_map(|imp| resource_by_id(document, &imp.id))
+        .filter_map(|imp| match document.item(imp)?.kind {
+            model::Kind::Impl(ref imp) => imp.trait_path,
+            _ => None,
+        })
+        .filter(|trait_path| {
+            let trait_name = document.name(trait_path);
+            NOTABLE_TRAITS.contains(&trait_name)
+        })
+        .map(|trait_path| {
+            json!({
+                "trait": trait_path,
+                "for": document.name(&output.id),
+                "link": link(document, resource, output),
+                "summary": summary_for_resource(document, output),
+            })
+        })
+        .collect()
+}
+
+/// Returns a path to the doc file for a given resource, if it exists.
+///
+/// For example, fields do not have individual links.
+fn path_for_resource(resource: &Resource) -> Option<PathBuf> {
+    if !resource.id.split("::").all(is_file_name) {
+        return None;
+    }
+
+    let mut path: PathBuf = resource.id.split("::").collect();
+
+    if resource._type == "module" || resource._type == "crate" {
+        path.push("index.html");
+        Some(path)
+    } else {
+        let ty = page_prefix(&resource._type)?;
+
+        let item_name = path.file_name().unwrap().to_owned();
+        path.pop();
+        path.push(format!("{}.{}.html", ty, item_name.to_str()?));
+        Some(path)
+    }
+}
+
+/// Returns whether a segment of a resource ID can be used as the name of a file or directory.
+///
+/// Segments that are empty, refer to the current or parent directory, or contain path separators
+/// or control characters could escape the doc root or produce unusable files.
+fn is_file_name(segment: &str) -> bool {
+    !segment.is_empty()
+        && segment != "."
+        && segment != ".."
+        && !segment.contains(|c: char| c == '/' || c == '\\' || c.is_control())
+}
+
+/// Returns the prefix of the page names of items of a given type, if the items have their own
+/// pages.
+fn page_prefix(ty: &str) -> Option<&'static str> {
+    match ty {
+        "struct" => Some("struct"),
+        "function" => Some("fn"),
+        "trait" => Some("trait"),
+        "type" => Some("type"),
+        "enum" => Some("enum"),
+        "const" => Some("constant"),
+        _ => None,
+    }
+}
+
+/// Returns whether a resource has a type that the renderer creates pages for.
+fn has_page_type(resource: &Resource) -> bool {
+    let ty = resource._type.as_str();
+    ty == "module" || ty == "crate" || page_prefix(ty).is_some()
+}
+
+/// Returns the documentation rendered as HTML for a given resource.
+///
+/// Links to the paths of items, such as `[Foo](crate::Foo)`, point to the URLs that `link`
+/// returns for the IDs of the items, which may be in another crate of the document.
+fn docs_for_resource<F>(document: &Index, resource: &Resource, link: F) -> Option<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    // TODO: We could be smart and do some caching here.
+    let rendered_docs = markdown::render_with_links(document.item(resource)?.docs?, |dest| {
+        link(&intra_doc_target(document, resource, dest)?)
+    });
+
+    if !rendered_docs.is_empty() {
+        Some(rendered_docs)
+    } else {
+        None
+    }
+}
+
+/// Returns the ID of the item with a page that a link in the documentation of a resource refers
+/// to by path.
+///
+/// Like rustdoc, paths are resolved from the module of the resource, or from the item it belongs
+/// to for fields and methods, then from the doc root, where the crates of the document are.
+/// `crate::` refers to the crate of the resource.
+fn intra_doc_target(document: &Index, resource: &Resource, dest: &str) -> Option<String> {
+    let path = dest.trim_matches('`');
+    let is_path = path.split("::").all(|segment| {
+        !segment.is_empty() && segment.chars().all(|c| c.is_alphanumeric() || c == '_')
+    });
+    if !is_path {
+        return None;
+    }
+
+    let id = &resource.id[..];
+    let krate = id.split("::").next().unwrap_or(id);
+    let candidates = if path == "crate" {
+        vec![String::from(krate)]
+    } else if let Some(path) = path.strip_prefix("crate::") {
+        vec![format!("{}::{}", krate, path)]
+    } else {
+        let module = match resource._type.as_str() {
+            "crate" | "module" => id,
+            _ => id.rsplit_once("::").map_or(krate, |(parent, _)| parent),
+        };
+        vec![format!("{}::{}", module, path), String::from(path)]
+    };
+
+    candidates.into_iter().find(|id| document.path_by_id(id).is_some())
+}
+
+/// Returns the documentation of a resource rendered as HTML for the page of another resource,
+/// with links to the paths of items pointing to their pages.
+fn docs_for_page(document: &Index, page: &Resource, resource: &Resource) -> Option<String> {
+    docs_for_resource(document, resource, |id| {
+        html_diff_paths(&document.path_by_id(id)?, &document.path(page)?)
+    })
+}
+
+/// Returns the primary resources of the document, which are the crates being documented.
+///
+/// Documents usually describe a single crate, but may contain a collection of crates as their
+/// primary data.
+fn crates(document: &JsonApiDocument) -> &[Resource] {
+    match document.data {
+        Some(PrimaryData::Single(ref resource)) => slice::from_ref(&**resource),
+        Some(PrimaryData::Multiple(ref resources)) => resources,
+        Some(PrimaryData::None) | None => &[],
+    }
+}
+
+/// Returns an iterator over every resource in the document, starting with the primary resources.
+fn resources(document: &JsonApiDocument) -> impl Iterator<Item = &Resource> {
+    crates(document).iter().chain(document.included.iter().flat_map(|included| included.iter()))
+}
+
+/// Returns the first sentence of the documentation of a resource as plain text.
+fn summary_for_resource(document: &Index, resource: &Resource) -> Option<String> {
+    document.item(resource)?.docs.and_then(markdown::summary)
+}
+
+/// Returns the conditional compilation requirements of a resource.
+fn cfg_for_resource(document: &Index, resource: &Resource) -> Option<cfg::Cfg> {
+    let predicate = document.item(resource)?.cfg?;
+    let cfg = cfg::Cfg::parse(predicate);
+
+    if cfg.is_none() {
+        warn!("could not parse cfg `{}` of `{}`", predicate, resource.id);
+    }
+
+    cfg
+}
+
+/// Returns the deprecation of a resource, as declared by `#[deprecated]`.
+///
+/// The `deprecated` attribute may contain the `since` version, a `note` written in Markdown, and a
+/// `suggestion` naming the replacement for the item.
+fn deprecation_for_resource(document: &Index, resource: &Resource) -> Option<Value> {
+    let item = document.item(resource)?;
+    let deprecation = item.deprecation.as_ref()?;
+
+    Some(json!({
+        "since": deprecation.since,
+        "note": deprecation.note.map(markdown::render),
+        "suggestion": deprecation.suggestion,
+    }))
+}
+
+/// Returns the stability of a resource, as declared by `#[stable]` or `#[unstable]`.
+///
+/// The `stability` attribute contains the `level` (either `stable` or `unstable`), and optionally
+/// the `since` version, the unstable `feature` name and its tracking `issue`.
+fn stability_for_resource(document: &Index, resource: &Resource) -> Option<Value> {
+    let item = document.item(resource)?;
+    let stability = item.stability.as_ref()?;
+
+    Some(json!({
+        "unstable": stability.unstable,
+        "since": stability.since,
+        "feature": stability.feature,
+        "issue": stability.issue,
+    }))
+}
+
+/// Returns every resource related to a resource, across all of its relationships.
+fn children<'a>(document: &Index<'a>, resource: &Resource) -> Vec<&'a Resource> {
+    resource
+        .relationships
+        .iter()
+        .flat_map(|relationships| relationships.values())
+        .flat_map(|relationship| identifiers(&relationship.data))
+        .filter_map(|child| resource_by_id(document, &child.id))
+        .collect()
+}
+
+/// Returns the identifiers of a relationship's resources.
+///
+/// The rustdoc backend describes most relationships as arrays, but to-one relationships, such as
+/// the trait of an impl, may be given as a single identifier or `null`.
+fn identifiers(data: &IdentifierData) -> &[ResourceIdentifier] {
+    match *data {
+        IdentifierData::Single(ref id) => slice::from_ref(id),
+        IdentifierData::Multiple(ref ids) => ids,
+        IdentifierData::None => &[],
+    }
+}
+
+/// Given a resource ID, finds the resource in the JSON-API document.
+fn resource_by_id<'a>(document: &Index<'a>, id: &str) -> Option<&'a Resource> {
+    document.resource(id)
+}
+
+/// Returns the path of every file under a directory, relative to the directory, in sorted order.
+fn files(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut directories = vec![PathBuf::new()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in fs::read_dir(root.join(&directory))? {
+            let entry = entry?;
+            let path = directory.join(entry.file_name());
+
+            if entry.file_type()?.is_dir() {
+                directories.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Perform a `pathdiff::diff_paths` of two `Path` objects, but return a `String` for HTML output.
+///
+/// The returned HTML path will differ from a filesystem path in two ways:
+///
+/// - It will have any backslashed replaced by forward slashes.
+/// - It will be relative from the parent folder, not the file itself.
+///
+/// Returns `None` if any of the path components are invalid UTF-8, since they cannot be written in
+/// a link.
+///
+/// # Panics
+///
+/// This function will panic if the `base` parameter does not have a parent.
+fn html_diff_paths(path: &Path, base: &Path) -> Option<String> {
+    let base = base.parent().expect("path did not have a parent");
+    let relative_path = pathdiff::diff_paths(path, base)?;
+
+    let components = relative_path
+        .iter()
+        .map(|component| component.to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(components.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Arc, Mutex};
+
+    use jsonapi::api::{
+        IdentifierData, JsonApiDocument, Relationship, Resource, ResourceIdentifier,
+    };
+    use serde_json::Value;
+
+    use errors::ErrorKind;
+    use index::Index;
+    use manifest::Manifest;
+    use output::MemoryBackend;
+
+    /// The doc root of renders into memory, which is never created.
+    const FIXTURE_ROOT: &str = "/nonexistent";
+
+    /// The files and report of a render into memory.
+    struct Fixture {
+        backend: MemoryBackend,
+        report: super::RenderReport,
+    }
+
+    impl Fixture {
+        /// Returns the contents of a file, by its path relative to the doc root.
+        fn page(&self, path: &str) -> String {
+            String::from_utf8(self.backend.get(path).unwrap()).unwrap()
+        }
+
+        /// Returns the contents of the asset whose file name starts with a prefix, if it was
+        /// written.
+        fn asset(&self, prefix: &str) -> Option<String> {
+            let files = self.report.files_written.iter().filter_map(|file| file.file_name());
+            let mut files = files.map(|name| name.to_string_lossy().into_owned());
+            files.find(|name| name.starts_with(prefix)).map(|file| self.page(&file))
+        }
+    }
+
+    /// Renders a document into memory with a renderer that has no backend.
+    fn render_fixture(document: &JsonApiDocument, renderer: super::Renderer) -> Fixture {
+        let backend = MemoryBackend::new();
+        let report = renderer.backend(backend.clone()).render(document, FIXTURE_ROOT).unwrap();
+        Fixture { backend, report }
+    }
+
+    #[test]
+    fn path_for_resource() {
+        let module = Resource {
+            _type: "module".into(),
+            id: "test_crate::test_module".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::path_for_resource(&module).unwrap(),
+            PathBuf::from("test_crate/test_module/index.html")
+        );
+
+        let strukt = Resource {
+            _type: "struct".into(),
+            id: "test_crate::TestStruct".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            super::path_for_resource(&strukt).unwrap(),
+            PathBuf::from("test_crate/struct.TestStruct.html")
+        );
+
+        let field = Resource {
+            _type: "field".into(),
+            id: "test_crate::Struct::field".into(),
+            ..Default::default()
+        };
+
+        assert_eq!(super::path_for_resource(&field), None);
+    }
+
+    #[test]
+    fn html_diff_paths() {
+        let base = PathBuf::from("/target/doc/example/index.html");
+        let path = PathBuf::from("/target/doc");
+        assert_eq!(super::html_diff_paths(&path, &base), Some("..".into()));
+    }
+
+    #[test]
+    fn notable_traits() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [
+                    {
+                        "type": "function",
+                        "id": "test_crate::iter",
+                        "attributes": { "output": "test_crate::Iter" }
+                    },
+                    {
+                        "type": "struct",
+                        "id": "test_crate::Iter",
+                        "attributes": {},
+                        "relationships": {
+                            "impls": {
+                                "data": [
+                                    { "type": "impl", "id": "test_crate::Iter::Iterator" },
+                                    { "type": "impl", "id": "test_crate::Iter::Clone" }
+                                ]
+                            }
+                        }
+                    },
+                    {
+                        "type": "impl",
+                        "id": "test_crate::Iter::Iterator",
+                        "attributes": { "trait": "std::iter::Iterator" }
+                    },
+                    {
+                        "type": "impl",
+                        "id": "test_crate::Iter::Clone",
+                        "attributes": { "trait": "std::clone::Clone" }
+                    }
+                ]
+            }"#,
+        ).unwrap();
+
+        let index = Index::parse(&document).unwrap();
+        let function = super::resource_by_id(&index, "test_crate::iter").unwrap();
+        assert_eq!(
+            super::notable_traits(&index, function),
+            vec![json!({
+                "trait": "std::iter::Iterator",
+                "for": "Iter",
+                "link": "struct.Iter.html",
+                "summary": null,
+            })]
+        );
+    }
+
+    #[test]
+    fn render_page() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": { "docs": "Crate docs." }
+                },
+                "included": [{ "type": "field", "id": "test_crate::Foo::0", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new().base_url("https://docs.example.com");
+        let renderer = super::Renderer::with_options(options).unwrap();
+        let krate = super::resources(&document).next().unwrap();
+        let mut page = vec![];
+        renderer.render_page(&document, krate, &mut page).unwrap();
+
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains(r#"<link rel="stylesheet" href="../styles-"#));
+        assert!(page.contains("<p>Crate docs.</p>"));
+        assert!(page.contains(r#"<meta name="description" content="Crate docs.">"#));
+        assert!(page.contains("https://docs.example.com/test_crate/index.html"));
+
+        let field = super::resources(&document).last().unwrap();
+        let err = renderer.render_page(&document, field, &mut vec![]).unwrap_err();
+        match *err.kind() {
+            ErrorKind::InvalidDocument(ref reason) => {
+                assert_eq!(reason, "`test_crate::Foo::0` does not have a page")
+            }
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn render_page_hooks() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+        ).unwrap();
+
+        let krate = super::resources(&document).next().unwrap();
+        let mut page = vec![];
+        super::Renderer::new()
+            .before_item(|resource, context| context["name"] = json!(resource.id.len()))
+            .render_page(&document, krate, &mut page)
+            .unwrap();
+
+        assert!(String::from_utf8(page).unwrap().contains("crate 10</p>"));
+    }
+
+    #[test]
+    fn context_transform() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new()
+            .context_transform(|resource, context| context["name"] = json!(resource.id.len()));
+        let renderer = super::Renderer::with_options(options)
+            .unwrap()
+            .before_item(|_, context| {
+                let name = context["name"].to_string();
+                context["name"] = json!(format!("{}!", name));
+            });
+
+        let page = render_fixture(&document, renderer).page("test_crate/index.html");
+        assert!(page.contains("crate 10!</"), "{}", page);
+    }
+
+    #[test]
+    fn render_page_single_relationship() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [{ "type": "module", "id": "test_crate::m", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let module = ResourceIdentifier {
+            _type: "module".into(),
+            id: "test_crate::m".into(),
+        };
+        let mut relationships = HashMap::new();
+        relationships.insert(
+            String::from("modules"),
+            Relationship { data: IdentifierData::Single(module), links: None },
+        );
+        let krate = Resource {
+            _type: "crate".into(),
+            id: "test_crate".into(),
+            relationships: Some(relationships),
+            ..Default::default()
+        };
+
+        let mut page = vec![];
+        super::Renderer::new().render_page(&document, &krate, &mut page).unwrap();
+        assert!(String::from_utf8(page).unwrap().contains(r#"href="m/index.html""#));
+    }
+
+    #[test]
+    fn multiple_crates() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": [
+                    { "type": "crate", "id": "a", "attributes": { "version": "1.0.0" } },
+                    { "type": "crate", "id": "b", "attributes": { "version": "2.0.0" } }
+                ],
+                "included": [{ "type": "function", "id": "b::f", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let ids = super::resources(&document).map(|resource| &*resource.id).collect::<Vec<_>>();
+        assert_eq!(ids, ["a", "b", "b::f"]);
+
+        let function = super::resources(&document).last().unwrap();
+        assert_eq!(
+            super::crate_metadata_for_resource(&Index::parse(&document).unwrap(), function)
+                ["version"],
+            "2.0.0"
+        );
+    }
+
+    #[test]
+    fn jobs() {
+        let item = |i| {
+            format!(r#"{{ "type": "function", "id": "test_crate::f{}", "attributes": {{}} }}"#, i)
+        };
+        let items = (0..10).map(item).collect::<Vec<_>>();
+        let document = JsonApiDocument::from_str(&format!(
+            r#"{{
+                "data": {{ "type": "crate", "id": "test_crate", "attributes": {{}} }},
+                "included": [{}]
+            }}"#,
+            items.join(", ")
+        )).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-jobs");
+        let render = |jobs| {
+            let _ = fs::remove_dir_all(&root);
+            let options = super::RenderOptions::new().jobs(jobs);
+            super::render_docs_with(&document, &root, options).unwrap().files_written
+        };
+
+        let files_written = render(1);
+        assert!(root.join("test_crate/fn.f9.html").is_file());
+        assert_eq!(render(4), files_written);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn render_without_included() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-render-without-included");
+        let _ = fs::remove_dir_all(&root);
+        super::render_docs(&document, &root).unwrap();
+
+        assert!(root.join("test_crate/index.html").is_file());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn exclude_resources() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": {},
+                    "relationships": {
+                        "modules": { "data": [{ "type": "module", "id": "test_crate::internal" }] },
+                        "functions": { "data": [{ "type": "function", "id": "test_crate::f" }] }
+                    }
+                },
+                "included": [
+                    { "type": "module", "id": "test_crate::internal", "attributes": {} },
+                    { "type": "function", "id": "test_crate::internal::g", "attributes": {} },
+                    { "type": "function", "id": "test_crate::f", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+        let options = super::RenderOptions::new().exclude("test_crate::internal");
+
+        let document = super::exclude_resources(&document, &options, &HashSet::new());
+        let ids = super::resources(&document).map(|resource| &*resource.id).collect::<Vec<_>>();
+        assert_eq!(ids, ["test_crate", "test_crate::f"]);
+
+        let krate = super::resources(&document).next().unwrap();
+        let relationships = krate.relationships.as_ref().unwrap();
+        match relationships["modules"].data {
+            IdentifierData::Multiple(ref modules) => assert!(modules.is_empty()),
+            ref data => panic!("unexpected relationship data: {:?}", data),
+        }
+    }
+
+    #[test]
+    fn merge_documents() {
+        let document = |krate: &str| {
+            JsonApiDocument::from_str(&format!(
+                r#"{{
+                    "data": {{ "type": "crate", "id": "{}", "attributes": {{}} }},
+                    "included": [
+                        {{ "type": "struct", "id": "{0}::Foo", "attributes": {{}} }},
+                        {{ "type": "struct", "id": "shared::Bar", "attributes": {{}} }}
+                    ],
+                    "meta": {{ "sources": {{ "{0}/src/lib.rs": "struct Foo;" }} }}
+                }}"#,
+                krate
+            ))
+            .unwrap()
+        };
+
+        let document = super::merge_documents(&[document("a"), document("b")]);
+        let ids = super::resources(&document).map(|resource| &*resource.id).collect::<Vec<_>>();
+        assert_eq!(ids, ["a", "b", "a::Foo", "shared::Bar", "b::Foo"]);
+
+        let sources = &document.meta.as_ref().unwrap()["sources"];
+        assert_eq!(sources["a/src/lib.rs"], "struct Foo;");
+        assert_eq!(sources["b/src/lib.rs"], "struct Foo;");
+    }
+
+    #[test]
+    fn render_docs_multi() {
+        let document = |krate: &str, docs: &str| {
+            JsonApiDocument::from_str(&format!(
+                r#"{{
+                    "data": {{ "type": "crate", "id": "{}", "attributes": {{ "docs": "{}" }} }}
+                }}"#,
+                krate, docs
+            )).unwrap()
+        };
+
+        let root = env::temp_dir().join("rustdoc-static-render-docs-multi");
+        let _ = fs::remove_dir_all(&root);
+        let documents = [document("a", "The first crate."), document("b", "The second crate.")];
+        super::render_docs_multi(&documents, &root).unwrap();
+
+        let index = fs::read_to_string(root.join("index.html")).unwrap();
+        assert!(index.contains(r#"<a href="a/index.html">a</a>"#));
+        assert!(index.contains("The second crate."));
+        let page = fs::read_to_string(root.join("b/index.html")).unwrap();
+        assert!(page.contains(r#"<a href="../index.html">All crates</a>"#));
+
+        // Both crates are in the search index, whichever page the search box is on.
+        let search_index = fs::read_to_string(root.join("search-index.js")).unwrap();
+        let search_index = search_index.trim_start_matches("window.searchIndex = ");
+        let search_index = search_index.trim_end().trim_end_matches(';');
+        let search_index = serde_json::from_str::<serde_json::Value>(search_index).unwrap();
+        assert_eq!(search_index[1]["crate"], "b");
+        assert_eq!(search_index[1]["link"], "b/index.html");
+        assert_eq!(search_index[1]["summary"], "The second crate.");
+
+        // A single crate is its own landing page.
+        fs::remove_dir_all(&root).unwrap();
+        super::render_docs_multi(&documents[..1], &root).unwrap();
+        assert!(!root.join("index.html").exists());
+        let page = fs::read_to_string(root.join("a/index.html")).unwrap();
+        assert!(!page.contains("All crates"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn versioned() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "a", "attributes": { "version": "1.0.0" } } }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-versioned");
+        let _ = fs::remove_dir_all(&root);
+        let options = super::RenderOptions::new().versioned(true);
+        let report = super::render_docs_with(&document, &root, options).unwrap();
+        assert_eq!(report.version.as_deref(), Some("1.0.0"));
+        let options = super::RenderOptions::new().version("main");
+        super::render_docs_with(&document, &root, options).unwrap();
+
+        let page = fs::read_to_string(root.join("1.0.0/a/index.html")).unwrap();
+        assert!(page.contains(r#"data-versions="../../versions.json""#));
+        assert!(page.contains(r#"data-page="a/index.html""#));
+        assert!(root.join("main/a/index.html").is_file());
+        assert_eq!(::versions::versions(&root).unwrap(), ["main", "1.0.0"]);
+
+        let options = super::RenderOptions::new().version("..");
+        assert!(super::render_docs_with(&document, &root, options).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn changes_since() {
+        let document = |included: &str| {
+            JsonApiDocument::from_str(&format!(
+                r#"{{
+                    "data": {{ "type": "crate", "id": "a", "attributes": {{}} }},
+                    "included": [{}]
+                }}"#,
+                included
+            )).unwrap()
+        };
+        let old = document("");
+        let new = document(r#"{ "type": "struct", "id": "a::Added", "attributes": {} }"#);
+
+        let root = env::temp_dir().join("rustdoc-static-changes-since");
+        let _ = fs::remove_dir_all(&root);
+        let options = super::RenderOptions::new().changes_since(old);
+        super::render_docs_with(&new, &root, options).unwrap();
+
+        let changes = fs::read_to_string(root.join("changes.html")).unwrap();
+        assert!(changes.contains(r#"<a href="a/struct.Added.html"><code>a::Added</code></a>"#));
+        let page = fs::read_to_string(root.join("a/index.html")).unwrap();
+        assert!(page.contains(r#"<a href="../changes.html">Changes</a>"#));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn readme() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": { "docs": "Crate docs." } },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-readme");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let readme = root.join("README.md");
+        fs::write(&readme, "# The crate\n\nSee [`Foo`].\n\n[`Foo`]: crate::Foo").unwrap();
+
+        let options = super::RenderOptions::new().readme(&readme);
+        super::render_docs_with(&document, root.join("doc"), options.clone()).unwrap();
+        let page = fs::read_to_string(root.join("doc/a/index.html")).unwrap();
+        assert!(page.contains("<p>Crate docs.</p>\n<h2>The crate</h2>"));
+        assert!(page.contains(r#"See <a href="struct.Foo.html"><code>Foo</code></a>."#));
+
+        let options = options.readme_replaces_docs(true);
+        super::render_docs_with(&document, root.join("doc"), options).unwrap();
+        let page = fs::read_to_string(root.join("doc/a/index.html")).unwrap();
+        assert!(!page.contains("<p>Crate docs.</p>"));
+        assert!(page.contains("<h2>The crate</h2>"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn dependencies() {
+        let dependency = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "dep", "attributes": { "docs": "A dependency." } },
+                "included": [{ "type": "struct", "id": "dep::Thing", "attributes": {} }]
+            }"#,
+        ).unwrap();
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "a",
+                    "attributes": { "docs": "Uses [`Thing`](dep::Thing)." }
+                }
+            }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-dependencies");
+        let _ = fs::remove_dir_all(&root);
+        let options = super::RenderOptions::new().dependency(dependency);
+        super::render_docs_with(&document, &root, options).unwrap();
+
+        assert!(root.join("deps/dep/struct.Thing.html").is_file());
+        let deps = fs::read_dir(root.join("deps")).unwrap().map(|e| e.unwrap().file_name());
+        assert!(deps.into_iter().any(|name| name.to_string_lossy().starts_with("styles-")));
+        let page = fs::read_to_string(root.join("a/index.html")).unwrap();
+        assert!(page.contains(r#"<a href="../deps/dep/struct.Thing.html"><code>Thing</code></a>"#));
+        assert!(page.contains(r#"<a href="../deps/dep/index.html" title="A dependency.">dep</a>"#));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn intra_doc_links() {
+        let documents = [
+            JsonApiDocument::from_str(
+                r#"{
+                    "data": { "type": "crate", "id": "a", "attributes": {} },
+                    "included": [{ "type": "struct", "id": "a::Thing", "attributes": {} }]
+                }"#,
+            ).unwrap(),
+            JsonApiDocument::from_str(
+                r#"{
+                    "data": {
+                        "type": "crate",
+                        "id": "b",
+                        "attributes": {
+                            "docs": "Wraps [`Thing`](a::Thing) in [`Wrapper`](Wrapper)."
+                        },
+                        "relationships": {
+                            "structs": { "data": [{ "type": "struct", "id": "b::Wrapper" }] }
+                        }
+                    },
+                    "included": [
+                        {
+                            "type": "struct",
+                            "id": "b::Wrapper",
+                            "attributes": { "docs": "See [the crate](crate) and [std](std::mem)." }
+                        }
+                    ]
+                }"#,
+            ).unwrap(),
+        ];
+
+        let root = env::temp_dir().join("rustdoc-static-intra-doc-links");
+        let _ = fs::remove_dir_all(&root);
+        super::render_docs_multi(&documents, &root).unwrap();
+
+        let page = fs::read_to_string(root.join("b/index.html")).unwrap();
+        assert!(page.contains(r#"<a href="../a/struct.Thing.html"><code>Thing</code></a>"#));
+        assert!(page.contains(r#"<a href="struct.Wrapper.html"><code>Wrapper</code></a>"#));
+        let page = fs::read_to_string(root.join("b/struct.Wrapper.html")).unwrap();
+        assert!(page.contains(r#"<a href="index.html">the crate</a>"#));
+        assert!(page.contains(r#"<a href="std::mem">std</a>"#));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn hidden_items() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [
+                    { "type": "function", "id": "test_crate::f", "attributes": {} },
+                    {
+                        "type": "function",
+                        "id": "test_crate::private",
+                        "attributes": { "visibility": "crate" }
+                    },
+                    {
+                        "type": "function",
+                        "id": "test_crate::hidden",
+                        "attributes": { "visibility": "public", "hidden": true }
+                    }
+                ]
+            }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new();
+        assert_eq!(
+            super::hidden_items(&document, &options).unwrap(),
+            ["test_crate::private", "test_crate::hidden"]
+        );
+
+        let options = options.document_private_items(true);
+        assert_eq!(super::hidden_items(&document, &options).unwrap(), ["test_crate::hidden"]);
+
+        let options = options.document_hidden_items(true);
+        assert!(super::hidden_items(&document, &options).unwrap().is_empty());
+    }
+
+    #[test]
+    fn not_included() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [
+                    { "type": "module", "id": "test_crate::api", "attributes": {} },
+                    { "type": "function", "id": "test_crate::api::f", "attributes": {} },
+                    { "type": "module", "id": "test_crate::internal", "attributes": {} },
+                    { "type": "function", "id": "test_crate::internal::g", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new().include("test_crate::api::*");
+        assert_eq!(
+            super::not_included(&document, &options),
+            ["test_crate::internal", "test_crate::internal::g"]
+        );
+    }
+
+    #[test]
+    fn render_report() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": {},
+                    "relationships": {
+                        "functions": { "data": [{ "type": "function", "id": "test_crate::f" }] }
+                    }
+                }
+            }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-render-report");
+        let _ = fs::remove_dir_all(&root);
+
+        let report = super::render_docs(&document, &root).unwrap();
+        assert!(report.files_written.contains(&root.join("test_crate/index.html")));
+        assert!(report.skipped.is_empty());
+        assert_eq!(
+            report.unresolved_links,
+            [super::UnresolvedLink {
+                from: String::from("test_crate"),
+                relationship: String::from("functions"),
+                to: String::from("test_crate::f"),
+            }]
+        );
+
+        let report = super::render_docs(&document, &root).unwrap();
+        assert!(!report.files_written.contains(&root.join("test_crate/index.html")));
+        assert_eq!(report.skipped, ["test_crate"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn memory_backend() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+
+        let root = Path::new(FIXTURE_ROOT);
+        assert!(!root.exists());
+        assert!(render.report.files_written.contains(&root.join("test_crate/index.html")));
+        assert!(render.page("test_crate/index.html").contains("test_crate"));
+        assert!(render.asset("styles-").is_some());
+    }
+
+    #[test]
+    fn canonical_urls() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": { "docs": "The crate." } },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new()
+            .base_url("https://docs.example.com/")
+            .logo_url("logo.png");
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+
+        let krate = render.page("a/index.html");
+        assert!(krate.contains(r#"<meta name="description" content="The crate.">"#));
+        let canonical = r#"<link rel="canonical" href="https://docs.example.com/a/index.html">"#;
+        assert!(krate.contains(canonical));
+        assert!(krate.contains(r#"<meta property="og:title" content="a">"#));
+        let image = r#"<meta property="og:image" content="https://docs.example.com/logo.png">"#;
+        assert!(krate.contains(image));
+        assert!(krate.contains(r#"<meta name="twitter:description" content="The crate.">"#));
+        assert!(krate.contains(r#""@type":"APIReference""#));
+        assert!(krate.contains(r#""url":"https://docs.example.com/a/index.html""#));
+        let foo = render.page("a/struct.Foo.html");
+        assert!(foo.contains(r#"content="API documentation for the struct `a::Foo`.""#));
+        let stats = render.page("stats.html");
+        let canonical = r#"<link rel="canonical" href="https://docs.example.com/stats.html">"#;
+        assert!(stats.contains(canonical));
+    }
+
+    #[test]
+    fn analytics() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+        let snippet = env::temp_dir().join("rustdoc-static-analytics.html");
+        fs::write(&snippet, "<script src=\"/count.js\"></script>\n").unwrap();
+
+        let options = super::RenderOptions::new()
+            .analytics("plausible:docs.example.com".parse().unwrap())
+            .analytics_snippet(&snippet);
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+
+        for path in &["a/index.html", "a/struct.Foo.html", "stats.html"] {
+            let page = render.page(path);
+            let head = &page[..page.find("</head>").unwrap()];
+            assert!(head.contains(r#"data-domain="docs.example.com""#), "{}", path);
+            assert!(head.ends_with("<script src=\"/count.js\"></script>\n"), "{}", path);
+        }
+        fs::remove_file(&snippet).unwrap();
+    }
+
+    #[test]
+    fn service_worker() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "a",
+                    "attributes": {},
+                    "relationships": {
+                        "structs": { "data": [{ "type": "struct", "id": "a::Foo" }] }
+                    }
+                },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-service-worker");
+        let _ = fs::remove_dir_all(&root);
+        let options = super::RenderOptions::new().service_worker(true);
+        let renderer = super::Renderer::with_options(options).unwrap();
+        renderer.render(&document, &root).unwrap();
+
+        // Pages that are skipped because they are unchanged are still cached.
+        let report = renderer.render(&document, &root).unwrap();
+        assert!(report.skipped.contains(&String::from("a::Foo")));
+        let files = fs::read_to_string(root.join(super::offline::CACHE_MANIFEST)).unwrap();
+        let files = serde_json::from_str::<serde_json::Value>(&files).unwrap()["files"].clone();
+        let files = files.as_array().unwrap().iter().map(|f| f.as_str().unwrap());
+        let files = files.collect::<Vec<_>>();
+        assert!(files.contains(&"a/struct.Foo.html"));
+        assert!(files.contains(&"search-index.js"));
+
+        assert!(root.join(super::offline::SERVICE_WORKER).is_file());
+        let recorded = Manifest::files(&root).unwrap().unwrap();
+        assert!(recorded.contains(&PathBuf::from(super::offline::SERVICE_WORKER)));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn content_hashes() {
+        let document = |docs: &str| {
+            JsonApiDocument::from_str(&format!(
+                r#"{{
+                    "data": {{
+                        "type": "crate",
+                        "id": "a",
+                        "attributes": {{ "docs": "{}" }},
+                        "relationships": {{
+                            "structs": {{ "data": [{{ "type": "struct", "id": "a::Foo" }}] }}
+                        }}
+                    }},
+                    "included": [{{ "type": "struct", "id": "a::Foo", "attributes": {{}} }}]
+                }}"#,
+                docs
+            )).unwrap()
+        };
+
+        let root = env::temp_dir().join("rustdoc-static-content-hashes");
+        let _ = fs::remove_dir_all(&root);
+        let options = super::RenderOptions::new().content_hashes(true);
+        let renderer = super::Renderer::with_options(options).unwrap();
+        renderer.render(&document("Old docs."), &root).unwrap();
+        let report = renderer.render(&document("New docs."), &root).unwrap();
+        assert!(report.content_hashes.contains_key(&root.join("a/index.html")));
+
+        let hashes = fs::read_to_string(root.join(super::hashes::CONTENT_HASHES)).unwrap();
+        let hashes = serde_json::from_str::<serde_json::Value>(&hashes).unwrap();
+        assert_eq!(hashes["files"]["a/index.html"]["changed"], true);
+        assert_eq!(hashes["files"]["a/struct.Foo.html"]["changed"], false);
+        assert!(hashes["changed"].as_array().unwrap().contains(&json!("a/index.html")));
+        assert_eq!(hashes["removed"], json!([]));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn lang() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+        assert!(render.page("a/struct.Foo.html").contains(r#"<html lang="en">"#));
+
+        let options = super::RenderOptions::new().lang("pt-BR");
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        for path in &["a/index.html", "a/struct.Foo.html", "stats.html"] {
+            let page = render.page(path);
+            assert!(page.contains(r#"<html lang="pt-BR">"#), "{}", path);
+        }
+
+        let options = super::RenderOptions::new().lang("en US");
+        assert!(super::Renderer::with_options(options).is_err());
+    }
+
+    #[test]
+    fn high_contrast_theme() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "a", "attributes": {} } }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new().theme("high-contrast");
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+
+        let settings = render.asset("settings-").unwrap();
+        assert!(settings.starts_with("window.rustdocDefaultTheme = \"high-contrast\";"));
+        let styles = render.asset("styles-").unwrap();
+        assert!(styles.contains(r#":root[data-theme="high-contrast"] {"#));
+        let page = render.page("a/index.html");
+        assert!(page.contains(r#"<option value="high-contrast">High contrast</option>"#));
+    }
+
+    #[test]
+    fn skip_link() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+        for path in &["a/index.html", "a/struct.Foo.html", "stats.html"] {
+            let page = render.page(path);
+            let body = &page[page.find("<body>").unwrap() + 6..].trim_start();
+            assert!(body.starts_with(r##"<a class="skip-link" href="#main">"##), "{}", path);
+            assert!(page.contains(r#"<main class="content" id="main" tabindex="-1">"#), "{}", path);
+        }
+    }
+
+    #[test]
+    fn share_fragments() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new().share_fragments(true);
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        let page = render.page("a/struct.Foo.html");
+
+        for name in &["settings", "sidebar", "search"] {
+            let prefix = format!("{}/fragments/{}-", FIXTURE_ROOT, name);
+            let mut files = render.report.files_written.iter().map(|file| file.to_string_lossy());
+            let file = files.find(|file| file.starts_with(&prefix)).unwrap();
+            let path = &file[FIXTURE_ROOT.len() + 1..];
+            assert!(page.contains(&format!(r#"data-fragment="../{}""#, path)), "{}", name);
+        }
+
+        // What differs between pages is kept on the placeholders.
+        assert!(page.contains(r#"data-title="struct Foo""#));
+        assert!(page.contains(r#"data-root=".." data-crate="a""#));
+        assert!(!page.contains(r#"class="sidebar-sections""#));
+        assert!(!page.contains(r#"class="search-input""#));
+    }
+
+    #[test]
+    fn icons() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "a",
+                    "attributes": {
+                        "html_logo_url": "https://example.com/logo.svg",
+                        "html_favicon_url": "https://example.com/favicon.ico"
+                    }
+                },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let page = render_fixture(&document, super::Renderer::new()).page("a/struct.Foo.html");
+        assert!(page.contains(r#"<link rel="icon" href="https://example.com/favicon.ico">"#));
+        assert!(page.contains(r#"<img class="crate-logo" src="https://example.com/logo.svg""#));
+
+        // A logo that is a file is copied into the doc root, and takes precedence over the crate.
+        let logo = env::temp_dir().join("rustdoc-static-logo.png");
+        fs::write(&logo, "not really a PNG").unwrap();
+        let options = super::RenderOptions::new()
+            .base_url("https://docs.example.com/")
+            .logo_url(logo.to_string_lossy());
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        fs::remove_file(&logo).unwrap();
+
+        let page = render.page("a/struct.Foo.html");
+        let src = &page[page.find("class=\"crate-logo\" src=\"../").unwrap() + 27..];
+        let file = &src[..src.find('"').unwrap()];
+        assert!(file.starts_with("logo-") && file.ends_with(".png"));
+        assert_eq!(render.page(file), "not really a PNG");
+        let image = format!(r#"property="og:image" content="https://docs.example.com/{}""#, file);
+        assert!(page.contains(&image));
+        assert!(page.contains(r#"<link rel="icon" href="https://example.com/favicon.ico">"#));
+        let stats = render.page("stats.html");
+        assert!(stats.contains(&format!(r#"src="./{}""#, file)));
+
+        // A logo of the document that names a local file is only linked to, never copied.
+        let logo = env::temp_dir().join("rustdoc-static-document-logo.png");
+        fs::write(&logo, "not for publishing").unwrap();
+        let document = json!({
+            "data": { "type": "crate", "id": "a", "attributes": { "html_logo_url": logo } },
+        });
+        let document = JsonApiDocument::from_str(&document.to_string()).unwrap();
+        let render = render_fixture(&document, super::Renderer::new());
+        fs::remove_file(&logo).unwrap();
+
+        let img = format!(r#"<img class="crate-logo" src="{}""#, logo.display());
+        assert!(render.page("a/index.html").contains(&img));
+        assert!(render.asset("logo-").is_none());
+    }
+
+    #[test]
+    fn extra_css() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+        ).unwrap();
+        let stylesheet = env::temp_dir().join("rustdoc-static-extra.css");
+        fs::write(&stylesheet, ".custom { color: red; }").unwrap();
+
+        let options = super::RenderOptions::new().extra_css(&stylesheet);
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+
+        // The page links to the stylesheet by a name that changes along with its contents.
+        let page = render.page("test_crate/index.html");
+        let link = &page[page.find("href=\"../styles-").unwrap() + 9..];
+        let styles = render.page(&link[..link.find('"').unwrap()]);
+        assert!(styles.ends_with("\n.custom { color: red; }"));
+        fs::remove_file(&stylesheet).unwrap();
+    }
+
+    #[test]
+    fn source_dir() {
+        let root = env::temp_dir().join("rustdoc-static-source-dir");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("src/lib.rs"), "// crate a\nstruct Foo;\n").unwrap();
+        let secret = root.join("secret.txt");
+        fs::write(&secret, "not for publishing").unwrap();
+
+        let document = json!({
+            "data": [
+                { "type": "crate", "id": "a", "attributes": { "source_dir": "/" } },
+                { "type": "crate", "id": "b", "attributes": {} },
+            ],
+            "included": [
+                {
+                    "type": "struct",
+                    "id": "a::Foo",
+                    "attributes": { "source": { "file": "src/lib.rs", "line": 2 } },
+                },
+                {
+                    "type": "struct",
+                    "id": "b::Foo",
+                    "attributes": { "source": { "file": secret, "line": 1 } },
+                },
+                {
+                    "type": "struct",
+                    "id": "b::Bar",
+                    "attributes": { "source": { "file": "../secret.txt", "line": 1 } },
+                },
+            ],
+        });
+        let document = JsonApiDocument::from_str(&document.to_string()).unwrap();
+
+        // The sources are only read from the directory of the options, and files outside of it
+        // are skipped, which the document cannot change.
+        let options = super::RenderOptions::new().source_dir(root.join("."));
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(render.page("a/src/src/lib.rs.html").contains("// crate a"));
+        assert!(render.page("a/struct.Foo.html").contains(r#"href="src/src/lib.rs.html#L2""#));
+        for (path, contents) in render.backend.files() {
+            let contents = String::from_utf8_lossy(&contents);
+            assert!(!contents.contains("not for publishing"), "{}", path.display());
+        }
+        assert_eq!(render.report.warnings.len(), 2);
+    }
+
+    #[test]
+    fn hooks() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+        ).unwrap();
+
+        let renderer = super::Renderer::new()
+            .before_item(|_, context| context["name"] = json!("renamed_crate"))
+            .after_item(|resource, html| html.push_str(&format!("<!-- {} -->", resource.id)))
+            .after_all(|report| assert!(!report.files_written.is_empty()));
+
+        let page = render_fixture(&document, renderer).page("test_crate/index.html");
+        assert!(page.contains("renamed_crate"));
+        assert!(page.ends_with("<!-- test_crate -->"));
+    }
+
+    #[test]
+    fn events() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": {},
+                    "relationships": {
+                        "functions": { "data": [{ "type": "function", "id": "test_crate::f" }] }
+                    }
+                }
+            }"#,
+        ).unwrap();
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let observed = Arc::clone(&events);
+        let renderer = super::Renderer::new().on_event(move |event| {
+            let event = match *event {
+                super::RenderEvent::ItemRendered { id, .. } => format!("rendered {}", id),
+                super::RenderEvent::LinkUnresolved(link) => format!("unresolved {}", link.to),
+                super::RenderEvent::TemplateError { template, .. } => format!("error {}", template),
+            };
+            observed.lock().unwrap().push(event);
+        });
+        render_fixture(&document, renderer);
+
+        assert_eq!(*events.lock().unwrap(), ["unresolved test_crate::f", "rendered test_crate"]);
+    }
+
+    #[test]
+    fn keep_going() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+        ).unwrap();
+
+        let template_dir = env::temp_dir().join("rustdoc-static-keep-going");
+        fs::create_dir_all(&template_dir).unwrap();
+        fs::write(template_dir.join("item.hbs"), "{{missing_helper name}}").unwrap();
+
+        let options = super::RenderOptions::new()
+            .template_dir(&template_dir)
+            .keep_going(true)
+            .error_pages(true);
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        fs::remove_dir_all(&template_dir).unwrap();
+
+        assert_eq!(render.report.failures.len(), 1);
+        assert_eq!(render.report.failures[0].id, "test_crate");
+        assert!(render.page("test_crate/index.html").contains("could not be rendered"));
+    }
+
+    #[test]
+    fn write_threads() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new()
+            .write_threads(2)
+            .base_url("https://docs.example.com/");
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        assert!(render.page("a/struct.Foo.html").contains("Foo"));
+        assert!(render.page("sitemap.xml").contains("https://docs.example.com/a/struct.Foo.html"));
+        assert!(render.asset("styles-").is_some());
+
+        // Every page is larger than the memory limit, so the pages are written one at a time.
+        let options = super::RenderOptions::new()
+            .write_threads(2)
+            .memory_limit(1)
+            .base_url("https://docs.example.com/");
+        let limited = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        assert_eq!(limited.page("a/struct.Foo.html"), render.page("a/struct.Foo.html"));
+    }
+
+    #[test]
+    fn low_memory() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": { "docs": "The crate." } },
+                "included": [
+                    { "type": "struct", "id": "a::Foo", "attributes": { "docs": "A struct." } }
+                ]
+            }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+        let options = super::RenderOptions::new().low_memory(true);
+        let low = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        for page in &["a/index.html", "a/struct.Foo.html"] {
+            assert_eq!(low.page(page), render.page(page), "{}", page);
+        }
+        assert!(low.page("a/struct.Foo.html").contains("A struct."));
+    }
+
+    #[test]
+    fn json_backend() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "a",
+                    "attributes": { "docs": "The crate." },
+                    "relationships": {
+                        "structs": { "data": [{ "type": "struct", "id": "a::Foo" }] }
+                    }
+                },
+                "included": [
+                    { "type": "struct", "id": "a::Foo", "attributes": { "docs": "A struct." } }
+                ]
+            }"#,
+        ).unwrap();
+
+        let backend = MemoryBackend::new();
+        super::backend::json::render_to(&document, &backend).unwrap();
+        let files = backend.files();
+        let paths = files.keys().map(PathBuf::as_path).collect::<Vec<_>>();
+        assert_eq!(paths, [Path::new("a/index.json"), Path::new("a/struct.Foo.json")]);
+
+        let json = |path: &str| serde_json::from_slice::<Value>(&files[Path::new(path)]).unwrap();
+        let krate = json("a/index.json");
+        assert_eq!(krate["type"], "crate");
+        assert_eq!(krate["crate"]["name"], "a");
+        assert_eq!(
+            krate["sections"]["structs"],
+            json!([{
+                "name": "Foo",
+                "link": "struct.Foo.html",
+                "docs": "<p>A struct.</p>\n",
+                "summary": "A struct.",
+                "cfg": null,
+                "stability": null,
+            }])
+        );
+
+        let strukt = json("a/struct.Foo.json");
+        assert_eq!(strukt["name"], "Foo");
+        assert_eq!(strukt["path"], "a::Foo");
+        assert_eq!(strukt["pathToRoot"], "..");
+        assert_eq!(strukt["docs"], "<p>A struct.</p>\n");
+    }
+
+    #[test]
+    fn single_backend() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "a",
+                    "attributes": { "docs": "Uses [`Foo`](m/struct.Foo.html)." },
+                    "relationships": { "modules": { "data": [{ "type": "module", "id": "a::m" }] } }
+                },
+                "included": [
+                    {
+                        "type": "module",
+                        "id": "a::m",
+                        "attributes": {},
+                        "relationships": {
+                            "structs": { "data": [{ "type": "struct", "id": "a::m::Foo" }] },
+                            "functions": { "data": [{ "type": "function", "id": "a::m::f" }] }
+                        }
+                    },
+                    { "type": "struct", "id": "a::m::Foo", "attributes": { "docs": "A struct." } },
+                    { "type": "function", "id": "a::m::f", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let backend = MemoryBackend::new();
+        super::backend::single::render_to(&document, &backend).unwrap();
+        let files = backend.files();
+        assert_eq!(files.keys().collect::<Vec<_>>(), [Path::new("a.html")]);
+
+        let page = String::from_utf8(backend.get("a.html").unwrap()).unwrap();
+        for anchor in &["a", "a-m", "a-m-Foo", "a-m-f"] {
+            assert!(page.contains(&format!(r#"<section class="single-item" id="{}""#, anchor)));
+            assert!(page.contains(&format!(r##"<a href="#{}">"##, anchor)), "{}", anchor);
+        }
+        assert!(page.contains(r##"<a href="#a-m-Foo" title="A struct.">Foo</a>"##), "{}", page);
+    }
+
+    #[test]
+    fn stability() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "a",
+                    "attributes": {},
+                    "relationships": {
+                        "structs": {
+                            "data": [
+                                { "type": "struct", "id": "a::Foo" },
+                                { "type": "struct", "id": "a::Bar" }
+                            ]
+                        }
+                    }
+                },
+                "included": [
+                    {
+                        "type": "struct",
+                        "id": "a::Foo",
+                        "attributes": {
+                            "stability": { "level": "unstable", "feature": "foo", "issue": "123" }
+                        }
+                    },
+                    {
+                        "type": "struct",
+                        "id": "a::Bar",
+                        "attributes": { "stability": { "level": "stable", "since": "1.2.0" } }
+                    }
+                ]
+            }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+        let krate = render.page("a/index.html");
+        let badge = r#">Foo</a> <span class="stability unstable">Experimental</span>"#;
+        assert!(krate.contains(badge), "{}", krate);
+        assert!(krate.contains(r#">Bar</a></td>"#), "{}", krate);
+
+        let foo = render.page("a/struct.Foo.html");
+        let badge = r#"<span class="stability unstable">Experimental (<code>foo</code> #123)"#;
+        assert!(foo.contains(badge), "{}", foo);
+
+        let bar = render.page("a/struct.Bar.html");
+        let badge = r#"<span class="stability since" title="Stable since 1.2.0">1.2.0</span>"#;
+        assert!(bar.contains(badge), "{}", bar);
+        assert!(!bar.contains("Experimental"));
+    }
+
+    #[test]
+    fn deprecated_index() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [
+                    {
+                        "type": "struct",
+                        "id": "a::Old",
+                        "attributes": {
+                            "deprecated": {
+                                "since": "1.1.0",
+                                "note": "Too *slow*.",
+                                "suggestion": "a::New"
+                            }
+                        }
+                    },
+                    { "type": "function", "id": "a::f", "attributes": { "deprecated": {} } },
+                    { "type": "struct", "id": "a::New", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+        assert!(render.page("a/index.html").contains(r#"<a href="../deprecated.html">"#));
+
+        let index = render.page("deprecated.html");
+        let old = index.find(r#"struct <a href="a/struct.Old.html">a::Old</a>"#).unwrap();
+        let f = index.find(r#"function <a href="a/fn.f.html">a::f</a>"#).unwrap();
+        assert!(old < f, "{}", index);
+        assert!(index.contains("Deprecated since 1.1.0."));
+        assert!(index.contains("<p>Too <em>slow</em>.</p>"));
+        assert!(index.contains("Use <code>a::New</code> instead."));
+        assert!(!index.contains("a::New</a>"));
+
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "a", "attributes": {} } }"#,
+        ).unwrap();
+        let render = render_fixture(&document, super::Renderer::new());
+        assert!(render.backend.get("deprecated.html").is_none());
+        assert!(!render.page("a/index.html").contains("deprecated.html"));
+    }
+
+    #[test]
+    fn settings_panel() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [{ "type": "struct", "id": "a::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+        let settings = render.asset("settings-").unwrap();
+        assert!(!settings.contains("rustdocDefaultTheme ="));
+        assert!(settings.contains("localStorage.getItem('rustdoc-static-settings')"));
+
+        let names = render.report.files_written.iter().filter_map(|file| file.file_name());
+        let mut names = names.filter_map(|name| name.to_str());
+        let name = names.find(|name| name.starts_with("settings-")).unwrap();
+        let script = format!(r#"<script src="../{}"></script>"#, name);
+        for path in &["a/index.html", "a/struct.Foo.html"] {
+            let page = render.page(path);
+            assert!(page[..page.find("</head>").unwrap()].contains(&script), "{}", path);
+            assert!(page.contains(r#"aria-controls="settings-panel" aria-expanded="false">"#));
+            assert!(page.contains(r#"<section class="settings-panel" id="settings-panel""#));
+            for setting in &["theme", "wrapLines", "collapseSections"] {
+                assert!(page.contains(&format!(r#"data-setting="{}""#, setting)), "{}", path);
+            }
+        }
+    }
+
+    #[test]
+    fn search_box() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [
+                    { "type": "module", "id": "a::m", "attributes": {} },
+                    { "type": "struct", "id": "a::m::Foo", "attributes": { "docs": "A struct." } }
+                ]
+            }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+        let page = render.page("a/m/struct.Foo.html");
+        let search = r#"<div class="search" role="search" data-root="../.." data-crate="a">"#;
+        assert!(page.contains(search), "{}", page);
+        assert!(page.contains(r#"<input class="search-input" type="search""#));
+        assert!(page.contains(r#"aria-controls="search-results""#));
+        assert!(page.contains(r#"<ul class="search-results" id="search-results""#));
+        assert!(page.contains(r#"<script src="../../search-index.js"></script>"#));
+
+        let index = render.page("search-index.js");
+        assert!(index.starts_with("window.searchIndex = ["));
+        let entries = index["window.searchIndex = ".len()..].trim_end().trim_end_matches(';');
+        let entries = serde_json::from_str::<Value>(entries).unwrap();
+        assert_eq!(entries.as_array().unwrap().len(), 3);
+        let foo = entries.as_array().unwrap().iter().find(|entry| entry["name"] == "Foo");
+        assert_eq!(
+            foo.unwrap(),
+            &json!({
+                "name": "Foo",
+                "path": "a::m::Foo",
+                "type": "struct",
+                "crate": "a",
+                "link": "a/m/struct.Foo.html",
+                "summary": "A struct.",
+            })
+        );
+    }
+
+    #[test]
+    fn open_graph() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "a", "attributes": {} },
+                "included": [{
+                    "type": "struct",
+                    "id": "a::Foo",
+                    "attributes": { "docs": "A \"quoted\" struct." }
+                }]
+            }"#,
+        ).unwrap();
+
+        let render = render_fixture(&document, super::Renderer::new());
+        let page = render.page("a/struct.Foo.html");
+        assert!(page.contains(r#"<meta property="og:type" content="website">"#));
+        assert!(page.contains(r#"<meta property="og:title" content="a::Foo">"#));
+        let description = r#"content="A &quot;quoted&quot; struct.">"#;
+        assert!(page.contains(&format!(r#"<meta property="og:description" {}"#, description)));
+        assert!(page.contains(r#"<meta name="twitter:card" content="summary">"#));
+        assert!(page.contains(r#"<meta name="twitter:title" content="a::Foo">"#));
+        assert!(page.contains(&format!(r#"<meta name="twitter:description" {}"#, description)));
+
+        // Without a base URL, pages have no absolute URL or image to share.
+        assert!(!page.contains("og:url"));
+        assert!(!page.contains("og:image"));
+        assert!(!page.contains("twitter:image"));
+    }
+
+    #[test]
+    fn structured_data() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "a",
+                    "attributes": { "name": "a", "version": "1.2.3" }
+                },
+                "included": [
+                    {
+                        "type": "struct",
+                        "id": "a::Foo",
+                        "attributes": { "docs": "Ends a `</script>` element." }
+                    }
+                ]
+            }"#,
+        ).unwrap();
+
+        let options = super::RenderOptions::new().base_url("https://docs.example.com");
+        let render = render_fixture(&document, super::Renderer::with_options(options).unwrap());
+        let page = render.page("a/struct.Foo.html");
+
+        let start = r#"<script type="application/ld+json">"#;
+        let json = &page[page.find(start).unwrap() + start.len()..];
+        let json = &json[..json.find("</script>").unwrap()];
+        assert!(json.contains(r#"<\/script>"#), "{}", json);
+        assert_eq!(
+            serde_json::from_str::<Value>(json).unwrap(),
+            json!({
+                "@context": "https://schema.org",
+                "@type": "APIReference",
+                "name": "a::Foo",
+                "articleSection": "struct",
+                "description": "Ends a </script> element.",
+                "programmingLanguage": "Rust",
+                "url": "https://docs.example.com/a/struct.Foo.html",
+                "isPartOf": {
+                    "@type": "SoftwareSourceCode",
+                    "name": "a",
+                    "version": "1.2.3",
+                    "programmingLanguage": "Rust",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn dry_run() {
+        let document = JsonApiDocument::from_str(
+            r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-dry-run");
+        let _ = fs::remove_dir_all(&root);
+        let options = super::RenderOptions::new().dry_run(true);
+        let report = super::render_docs_with(&document, &root, options).unwrap();
+
+        assert!(report.files_written.contains(&root.join("test_crate/index.html")));
+        assert!(!root.exists());
+    }
+
+    #[test]
+    fn strict() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [{ "type": "macro", "id": "test_crate::m", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let root = env::temp_dir().join("rustdoc-static-strict");
+        let options = super::RenderOptions::new().strict(true).dry_run(true);
+        let err = super::render_docs_with(&document, &root, options).unwrap_err();
+        match *err.kind() {
+            ErrorKind::InvalidDocument(ref reason) => {
+                assert_eq!(reason, "unknown resource type `macro` for `test_crate::m`")
+            }
+            ref kind => panic!("unexpected error: {}", kind),
+        }
     }
 }
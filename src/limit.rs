@@ -0,0 +1,160 @@
+//! Caps on the number of output files that are open at the same time, and on the memory held by
+//! rendered files that wait to be written.
+//!
+//! Pages are rendered on more than one thread, so very large crates could otherwise exhaust the
+//! process's file descriptors. Writers wait for a permit before creating a file, and release it
+//! when the file is closed. Likewise, renderers wait for a permit for the size of a rendered file
+//! before handing it to the writers, which release it once the file is written.
+
+use std::sync::{Condvar, Mutex};
+
+/// Limits the number of output files that may be open at once.
+#[derive(Debug, Default)]
+pub struct FileLimit {
+    max: Option<usize>,
+    open: Mutex<usize>,
+    closed: Condvar,
+}
+
+impl FileLimit {
+    /// Creates a limit of `max` open files, or no limit if `max` is `None`.
+    pub fn new(max: Option<usize>) -> FileLimit {
+        FileLimit {
+            max: max.map(|max| max.max(1)),
+            open: Mutex::new(0),
+            closed: Condvar::new(),
+        }
+    }
+
+    /// Waits until another file may be opened, returning a permit that must be held while the
+    /// file is open.
+    pub fn acquire(&self) -> FilePermit<'_> {
+        let mut open = self.open.lock().unwrap();
+
+        if let Some(max) = self.max {
+            while *open >= max {
+                open = self.closed.wait(open).unwrap();
+            }
+        }
+
+        *open += 1;
+        FilePermit { limit: self }
+    }
+}
+
+/// Permission to hold a single output file open. The file should be closed before the permit is
+/// dropped.
+#[derive(Debug)]
+pub struct FilePermit<'a> {
+    limit: &'a FileLimit,
+}
+
+impl<'a> Drop for FilePermit<'a> {
+    fn drop(&mut self) {
+        *self.limit.open.lock().unwrap() -= 1;
+        self.limit.closed.notify_one();
+    }
+}
+
+/// Limits the number of bytes of rendered files that may be held in memory at once.
+#[derive(Debug, Default)]
+pub struct MemoryLimit {
+    max: Option<usize>,
+    held: Mutex<usize>,
+    released: Condvar,
+}
+
+impl MemoryLimit {
+    /// Creates a limit of `max` bytes, or no limit if `max` is `None`.
+    pub fn new(max: Option<usize>) -> MemoryLimit {
+        MemoryLimit { max, held: Mutex::new(0), released: Condvar::new() }
+    }
+
+    /// Waits until `bytes` more may be held, returning a permit that must be kept while they are.
+    ///
+    /// A file that is larger than the limit is let through once nothing else is held, so that it
+    /// does not wait forever.
+    pub fn acquire(&self, bytes: usize) -> MemoryPermit<'_> {
+        let mut held = self.held.lock().unwrap();
+
+        if let Some(max) = self.max {
+            while *held > 0 && *held + bytes > max {
+                held = self.released.wait(held).unwrap();
+            }
+        }
+
+        *held += bytes;
+        MemoryPermit { limit: self, bytes }
+    }
+}
+
+/// Permission to hold a number of bytes of a rendered file in memory.
+#[derive(Debug)]
+pub struct MemoryPermit<'a> {
+    limit: &'a MemoryLimit,
+    bytes: usize,
+}
+
+impl<'a> Drop for MemoryPermit<'a> {
+    fn drop(&mut self) {
+        *self.limit.held.lock().unwrap() -= self.bytes;
+        // Waiters need different amounts to be released, so every one of them checks again.
+        self.limit.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{FileLimit, MemoryLimit};
+
+    #[test]
+    fn acquire() {
+        let limit = FileLimit::new(Some(2));
+        let open = AtomicUsize::new(0);
+        let most_open = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let _permit = limit.acquire();
+                    let now_open = open.fetch_add(1, Ordering::SeqCst) + 1;
+                    most_open.fetch_max(now_open, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(5));
+                    open.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        assert_eq!(most_open.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn memory() {
+        let limit = MemoryLimit::new(Some(100));
+        let held = AtomicUsize::new(0);
+        let most_held_with_others = AtomicUsize::new(0);
+
+        thread::scope(|scope| {
+            for &bytes in &[40, 40, 40, 30, 30, 150] {
+                let (limit, held, most_held) = (&limit, &held, &most_held_with_others);
+                scope.spawn(move || {
+                    let _permit = limit.acquire(bytes);
+                    let now_held = held.fetch_add(bytes, Ordering::SeqCst) + bytes;
+                    if now_held > bytes {
+                        most_held.fetch_max(now_held, Ordering::SeqCst);
+                    }
+                    thread::sleep(Duration::from_millis(5));
+                    held.fetch_sub(bytes, Ordering::SeqCst);
+                });
+            }
+        });
+
+        // The file that is larger than the limit is only held on its own.
+        assert!(most_held_with_others.load(Ordering::SeqCst) <= 100);
+        assert_eq!(*limit.held.lock().unwrap(), 0);
+    }
+}
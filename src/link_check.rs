@@ -0,0 +1,212 @@
+//! Checking the links of rendered documentation, such as after it was post-processed or its
+//! templates were changed.
+//!
+//! Every `href` of every HTML page under a doc root is checked, except links to other sites and
+//! links relative to the root of the site. Links to an anchor are checked against the `id` and
+//! `name` attributes of the page they lead to.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use files;
+
+/// A link that does not lead anywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLink {
+    /// The page the link is on, relative to the doc root.
+    pub page: PathBuf,
+
+    /// The line of the page the link is on, starting from 1.
+    pub line: usize,
+
+    /// The target of the link, as it is written in the page.
+    pub href: String,
+
+    /// Whether the page the link leads to exists, but not its anchor.
+    pub missing_anchor: bool,
+}
+
+impl fmt::Display for DeadLink {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let missing = if self.missing_anchor { "an anchor" } else { "a page" };
+        write!(
+            f,
+            "{}:{}: `{}` leads to {} that does not exist",
+            self.page.display(),
+            self.line,
+            self.href,
+            missing
+        )
+    }
+}
+
+/// Returns the dead links of the HTML pages under a doc root, in the order of the pages.
+pub fn check<P: AsRef<Path>>(doc_root: P) -> io::Result<Vec<DeadLink>> {
+    let doc_root = doc_root.as_ref();
+    let files = files(doc_root)?;
+
+    let pages = files
+        .iter()
+        .filter(|file| file.extension().is_some_and(|ext| ext == "html"))
+        .collect::<Vec<_>>();
+
+    // Each page is read twice, once for its anchors and once for its links, so that the pages of
+    // documentation as large as the standard library are not all held in memory at once.
+    let mut anchors = HashMap::new();
+    for &page in &pages {
+        let html = read(&doc_root.join(page))?;
+        let ids = attributes(&html, "id").into_iter().chain(attributes(&html, "name"));
+        anchors.insert(page, ids.map(|(_, id)| id).collect::<HashSet<_>>());
+    }
+    let files = files.iter().collect::<HashSet<_>>();
+
+    let mut dead_links = vec![];
+    for &page in &pages {
+        let html = read(&doc_root.join(page))?;
+        for (line, href) in attributes(&html, "href") {
+            let missing_anchor = match target(doc_root, page, &href) {
+                Target::Unchecked => continue,
+                Target::Outside => false,
+                Target::Page(ref path, _) if !files.contains(path) => false,
+                Target::Page(ref path, Some(ref anchor)) => match anchors.get(path) {
+                    Some(ids) if !ids.contains(anchor) => true,
+                    _ => continue,
+                },
+                Target::Page(..) => continue,
+            };
+
+            dead_links.push(DeadLink { page: page.clone(), line, href, missing_anchor });
+        }
+    }
+
+    Ok(dead_links)
+}
+
+/// Reads an HTML page, replacing invalid UTF-8.
+fn read(path: &Path) -> io::Result<String> {
+    let mut html = vec![];
+    File::open(path)?.read_to_end(&mut html)?;
+    Ok(String::from_utf8_lossy(&html).into_owned())
+}
+
+/// Where a link leads.
+enum Target {
+    /// To another site, or to a path relative to the root of the site, which are not checked.
+    Unchecked,
+
+    /// To a path outside of the doc root.
+    Outside,
+
+    /// To a page under the doc root, relative to the doc root, and an anchor of the page.
+    Page(PathBuf, Option<String>),
+}
+
+/// Returns where a link on a page leads.
+fn target(doc_root: &Path, page: &Path, href: &str) -> Target {
+    if href.starts_with('/') || href.contains(':') {
+        return Target::Unchecked;
+    }
+
+    let (path, anchor) = match href.find('#') {
+        Some(hash) => (&href[..hash], Some(href[hash + 1..].to_owned())),
+        None => (href, None),
+    };
+    let path = path.split('?').next().unwrap_or(path);
+    let anchor = anchor.filter(|anchor| !anchor.is_empty());
+
+    if path.is_empty() {
+        return Target::Page(page.to_owned(), anchor);
+    }
+
+    let mut target = page.parent().map(Path::to_owned).unwrap_or_default();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(component) => target.push(component),
+            Component::ParentDir if target.pop() => (),
+            Component::CurDir => (),
+            _ => return Target::Outside,
+        }
+    }
+
+    if doc_root.join(&target).is_dir() {
+        target.push("index.html");
+    }
+
+    Target::Page(target, anchor)
+}
+
+/// Returns the values of the attributes of a name in some HTML, with the line each is on.
+fn attributes(html: &str, name: &str) -> Vec<(usize, String)> {
+    let mut attributes = vec![];
+    let mut line = 1;
+    let mut counted = 0;
+
+    let pattern = format!("{}=", name);
+    for (start, _) in html.match_indices(&pattern) {
+        let preceded_by_space = html[..start].ends_with(|c: char| c.is_ascii_whitespace());
+        let rest = &html[start + pattern.len()..];
+        let quote = match rest.chars().next() {
+            Some(quote) if preceded_by_space && (quote == '"' || quote == '\'') => quote,
+            _ => continue,
+        };
+        let value = match rest[1..].find(quote) {
+            Some(end) => &rest[1..end + 1],
+            None => continue,
+        };
+
+        line += html[counted..start].matches('\n').count();
+        counted = start;
+        attributes.push((line, value.replace("&amp;", "&")));
+    }
+
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::DeadLink;
+
+    #[test]
+    fn check() {
+        let root = env::temp_dir().join("rustdoc-static-link-check");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("demo")).unwrap();
+        fs::write(root.join("styles.css"), "").unwrap();
+        fs::write(
+            root.join("demo/index.html"),
+            "<link rel=\"stylesheet\" href=\"../styles.css\">\n\
+             <h2 id=\"structs\">Structs</h2>\n\
+             <a href=\"#structs\">Structs</a> <a href=\"struct.Foo.html#method.bar\">bar</a>\n\
+             <a href=\"https://example.com\">\n\
+             <a href=\"struct.Bar.html\">Bar</a> <a href=\"#fields\">Fields</a>\n",
+        ).unwrap();
+        let foo = "<a href=\"index.html\"><div id='method.bar'>";
+        fs::write(root.join("demo/struct.Foo.html"), foo).unwrap();
+
+        let dead_link = |line, href: &str, missing_anchor| DeadLink {
+            page: PathBuf::from("demo/index.html"),
+            line,
+            href: String::from(href),
+            missing_anchor,
+        };
+        let dead_links = super::check(&root).unwrap();
+        assert_eq!(
+            dead_links,
+            [dead_link(5, "struct.Bar.html", false), dead_link(5, "#fields", true)]
+        );
+        assert_eq!(
+            dead_links[1].to_string(),
+            "demo/index.html:5: `#fields` leads to an anchor that does not exist"
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,126 @@
+//! Loading of the JSON-API documents generated by the rustdoc backend.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::path::Path;
+
+use jsonapi::api::JsonApiDocument;
+use serde::de::IgnoredAny;
+use serde_json::{self, Value};
+use serde_json_jsonapi;
+
+use errors::*;
+use rustdoc_json;
+
+/// Reads a JSON-API document from a reader, such as standard input.
+///
+/// The output of `rustdoc --output-format json` is also accepted, and converted to a JSON-API
+/// document. The input is held in memory while it is parsed, since it is read twice: once to tell
+/// the formats apart, and once to parse it. Use `from_path` to stream a document from a file.
+pub fn from_reader<R: Read>(mut reader: R) -> Result<JsonApiDocument> {
+    let mut json = String::new();
+    reader.read_to_string(&mut json).chain_err(|| "could not read the document")?;
+
+    // Only the top-level keys are needed to tell the formats apart.
+    let keys = serde_json::from_str::<HashMap<String, IgnoredAny>>(&json)
+        .chain_err(|| ErrorKind::Parse)?;
+    if keys.contains_key("format_version") {
+        let json = serde_json::from_str::<Value>(&json).chain_err(|| ErrorKind::Parse)?;
+        if rustdoc_json::is_rustdoc_json(&json) {
+            return rustdoc_json::convert(&json);
+        }
+    }
+
+    JsonApiDocument::from_str(&json).chain_err(|| ErrorKind::Parse)
+}
+
+/// Reads a JSON-API document from a file, or the output of `rustdoc --output-format json`.
+///
+/// The file is parsed as it is read, rather than read into memory first, so only the parsed
+/// document is held in memory. The file is read twice, like the input of `from_reader`.
+pub fn from_path<P: AsRef<Path>>(path: P) -> Result<JsonApiDocument> {
+    let path = path.as_ref();
+
+    from_file(path).chain_err(|| format!("could not load `{}`", path.display()))
+}
+
+fn from_file(path: &Path) -> Result<JsonApiDocument> {
+    let open = || -> Result<BufReader<File>> { Ok(BufReader::new(File::open(path)?)) };
+
+    let keys = serde_json::from_reader::<_, HashMap<String, IgnoredAny>>(open()?)
+        .chain_err(|| ErrorKind::Parse)?;
+    if keys.contains_key("format_version") {
+        let json = serde_json::from_reader::<_, Value>(open()?).chain_err(|| ErrorKind::Parse)?;
+        if rustdoc_json::is_rustdoc_json(&json) {
+            return rustdoc_json::convert(&json);
+        }
+    }
+
+    // `jsonapi` is built against an older `serde_json`, which can deserialize its documents from
+    // a reader even though `jsonapi` only offers to parse them from strings.
+    serde_json_jsonapi::from_reader(open()?).chain_err(|| ErrorKind::Parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use errors::ErrorKind;
+
+    #[test]
+    fn from_reader() {
+        let document = super::from_reader(
+            &br#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#[..],
+        ).unwrap();
+        assert!(document.data.is_some());
+
+        let err = super::from_reader(&b"{ \"data\": "[..]).unwrap_err();
+        match *err.kind() {
+            ErrorKind::Parse => (),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[test]
+    fn from_path() {
+        let path = env::temp_dir().join("rustdoc-static-load.json");
+
+        fs::write(
+            &path,
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [{ "type": "struct", "id": "test_crate::Foo", "attributes": {} }]
+            }"#,
+        ).unwrap();
+        let document = super::from_path(&path).unwrap();
+        assert_eq!(document.included.unwrap()[0].id, "test_crate::Foo");
+
+        fs::write(
+            &path,
+            r#"{
+                "format_version": 39,
+                "root": 0,
+                "index": {
+                    "0": {
+                        "name": "test_crate",
+                        "inner": { "module": { "is_crate": true, "items": [] } }
+                    }
+                }
+            }"#,
+        ).unwrap();
+        let document = super::from_path(&path).unwrap();
+        assert!(document.data.is_some());
+
+        fs::write(&path, "{ \"data\": ").unwrap();
+        let err = super::from_path(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        match *err.kind() {
+            ErrorKind::Msg(ref message) => assert!(message.starts_with("could not load")),
+            ref kind => panic!("unexpected error: {}", kind),
+        }
+        assert!(err.iter().any(|cause| cause.to_string() == ErrorKind::Parse.to_string()));
+    }
+}
@@ -8,41 +8,987 @@ extern crate handlebars;
 extern crate jsonapi;
 extern crate pretty_env_logger;
 
-use std::io::prelude::*;
+use std::env;
+use std::ffi::OsStr;
 use std::io;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
 
-use clap::{Arg, App};
+use clap::{Arg, ArgMatches, App, SubCommand};
 use jsonapi::api::JsonApiDocument;
 
+use rustdoc_static::{RenderOptions, RenderReport, Renderer};
+use rustdoc_static::archive::ArchiveFormat;
+use rustdoc_static::config::Config;
 use rustdoc_static::errors::*;
+use rustdoc_static::serve::LiveReload;
+use rustdoc_static::stats::Stats;
 
-fn run() -> Result<()> {
-    pretty_env_logger::init().unwrap();
+/// The formats that documentation can be generated in.
+const FORMATS: &[&str] = &["html", "markdown", "json", "man", "single", "epub", "llms", "text"];
+
+/// How much the CLI prints while it works.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Verbosity {
+    /// Only errors, from `-q`.
+    Quiet,
+
+    /// A summary of each render, and the problems it found.
+    Normal,
+
+    /// The summary, and the log of the renderer, from `-v`.
+    Verbose,
+
+    /// The summary, and the debug log of the renderer, from `-vv`.
+    Debug,
+}
+
+impl Verbosity {
+    fn from_matches(matches: &ArgMatches) -> Verbosity {
+        match matches.occurrences_of("verbose") {
+            _ if matches.is_present("quiet") => Verbosity::Quiet,
+            0 => Verbosity::Normal,
+            1 => Verbosity::Verbose,
+            _ => Verbosity::Debug,
+        }
+    }
+}
 
-    let matches = App::new("rustdoc-frontend")
+fn run() -> Result<()> {
+    let matches = App::new("rustdoc-static")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Generates static documentation from the JSON output of rustdoc")
+        .arg(
+            Arg::with_name("input")
+                .value_name("INPUT")
+                .index(1)
+                .multiple(true)
+                .help("the JSON documents to document together; read from stdin if omitted or `-`"),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .long("verbose")
+                .short("v")
+                .multiple(true)
+                .help("print the log of the render; pass twice to include debug messages"),
+        )
+        .arg(
+            Arg::with_name("quiet")
+                .long("quiet")
+                .short("q")
+                .conflicts_with_all(&["verbose", "progress"])
+                .help("only print errors"),
+        )
         .arg(
             Arg::with_name("output")
                 .long("output")
                 .short("o")
                 .takes_value(true)
-                .help("where the documentation should be output")
-                .required(true),
+                .help("where the documentation should be output"),
+        )
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .takes_value(true)
+                .help("the configuration file; defaults to rustdoc-static.toml or Cargo.toml"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .takes_value(true)
+                .possible_values(FORMATS)
+                .help("the format of the generated documentation; defaults to html"),
+        )
+        .arg(
+            Arg::with_name("archive")
+                .long("archive")
+                .takes_value(true)
+                .help("package the documentation into a .tar.gz or .zip archive at this path"),
+        )
+        .arg(
+            Arg::with_name("base-url")
+                .long("base-url")
+                .takes_value(true)
+                .help("the URL the docs will be hosted at, for sitemap.xml and canonical links"),
+        )
+        .arg(
+            Arg::with_name("progress")
+                .long("progress")
+                .help("report the number of pages rendered on stderr"),
+        )
+        .arg(
+            Arg::with_name("max-open-files")
+                .long("max-open-files")
+                .takes_value(true)
+                .help("the most output files to keep open at the same time"),
+        )
+        .arg(
+            Arg::with_name("write-threads")
+                .long("write-threads")
+                .takes_value(true)
+                .value_name("N")
+                .help("hand pages to N threads that write them, for slow filesystems"),
+        )
+        .arg(
+            Arg::with_name("memory-limit")
+                .long("memory-limit")
+                .takes_value(true)
+                .value_name("BYTES")
+                .help("the most bytes of pages to hold while they wait for the write threads"),
+        )
+        .arg(
+            Arg::with_name("jobs")
+                .long("jobs")
+                .short("j")
+                .takes_value(true)
+                .value_name("N")
+                .help("the number of threads that render pages; defaults to the number of cores"),
+        )
+        .arg(
+            Arg::with_name("low-memory")
+                .long("low-memory")
+                .help("render one page at a time to lower peak memory use"),
+        )
+        .arg(
+            Arg::with_name("share-fragments")
+                .long("share-fragments")
+                .help("load the settings panel, sidebar and search of pages from shared files"),
+        )
+        .arg(
+            Arg::with_name("versioned")
+                .long("versioned")
+                .help("render into a directory named after the version of the crate"),
+        )
+        .arg(
+            Arg::with_name("doc-version")
+                .long("doc-version")
+                .takes_value(true)
+                .value_name("VERSION")
+                .help("the version to render under instead; implies --versioned"),
+        )
+        .arg(
+            Arg::with_name("dependency")
+                .long("dependency")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("PATH")
+                .help("the JSON document of a dependency to render under deps/; may be repeated"),
+        )
+        .arg(
+            Arg::with_name("changes-since")
+                .long("changes-since")
+                .takes_value(true)
+                .value_name("OLD")
+                .help("list the API changes since the JSON document OLD in changes.html"),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .long("theme")
+                .takes_value(true)
+                .value_name("THEME")
+                .help("the theme until the reader picks one: light, dark or high-contrast"),
+        )
+        .arg(
+            Arg::with_name("lang")
+                .long("lang")
+                .takes_value(true)
+                .value_name("TAG")
+                .help("the language the docs are written in, such as de or pt-BR [default: en]"),
+        )
+        .arg(
+            Arg::with_name("analytics")
+                .long("analytics")
+                .takes_value(true)
+                .value_name("SERVICE:ID")
+                .help("add the snippet of an analytics service, e.g. plausible:docs.example.com"),
+        )
+        .arg(
+            Arg::with_name("analytics-snippet")
+                .long("analytics-snippet")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("a file of HTML, such as an analytics snippet, to add to the head of pages"),
+        )
+        .arg(
+            Arg::with_name("robots-txt")
+                .long("robots-txt")
+                .help("write a robots.txt that keeps crawlers out of the source pages"),
+        )
+        .arg(
+            Arg::with_name("robots-disallow")
+                .long("robots-disallow")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("PATH")
+                .requires("robots-txt")
+                .help("another path under the doc root for robots.txt to disallow"),
+        )
+        .arg(
+            Arg::with_name("csp-headers")
+                .long("csp-headers")
+                .help("write a _headers file with a Content Security Policy for the pages"),
+        )
+        .arg(
+            Arg::with_name("service-worker")
+                .long("service-worker")
+                .help("write a service worker that caches the documentation for offline browsing"),
+        )
+        .arg(
+            Arg::with_name("content-hashes")
+                .long("content-hashes")
+                .help("write content-hashes.json, listing the files changed since the last render"),
+        )
+        .arg(
+            Arg::with_name("extra-css")
+                .long("extra-css")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("a stylesheet to add to the built-in styles of HTML pages"),
+        )
+        .arg(
+            Arg::with_name("readme")
+                .long("readme")
+                .takes_value(true)
+                .value_name("PATH")
+                .help("a Markdown file, such as README.md, to render on the page of the crate"),
+        )
+        .arg(
+            Arg::with_name("readme-replaces-docs")
+                .long("readme-replaces-docs")
+                .help("render the README instead of the docs of the crate root"),
+        )
+        .arg(
+            Arg::with_name("source-dir")
+                .long("source-dir")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("the directory that the source files of the crates are read from"),
+        )
+        .arg(
+            Arg::with_name("logo-url")
+                .long("logo-url")
+                .takes_value(true)
+                .value_name("URL")
+                .help("the logo of the crate, a URL or a file to copy into the docs"),
+        )
+        .arg(
+            Arg::with_name("favicon-url")
+                .long("favicon-url")
+                .takes_value(true)
+                .value_name("URL")
+                .help("the favicon of every page, a URL or a file to copy into the docs"),
+        )
+        .arg(
+            Arg::with_name("template-dir")
+                .long("template-dir")
+                .takes_value(true)
+                .help("a directory of templates that override the built-in ones"),
+        )
+        .arg(
+            Arg::with_name("include")
+                .long("include")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("only document the items that match a path glob, such as `my_crate::api::*`"),
+        )
+        .arg(
+            Arg::with_name("exclude")
+                .long("exclude")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help("leave the items that match a path glob, and everything under them, out"),
+        )
+        .arg(
+            Arg::with_name("document-private-items")
+                .long("document-private-items")
+                .help("document items that are not public"),
+        )
+        .arg(
+            Arg::with_name("document-hidden-items")
+                .long("document-hidden-items")
+                .help("document items hidden with #[doc(hidden)]"),
+        )
+        .arg(
+            Arg::with_name("keep-going")
+                .long("keep-going")
+                .help("keep rendering the other pages when a page cannot be rendered"),
+        )
+        .arg(
+            Arg::with_name("error-pages")
+                .long("error-pages")
+                .requires("keep-going")
+                .help("write a page with the error in place of each page that cannot be rendered"),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("fail on any problem with the document, such as broken links"),
+        )
+        .arg(
+            Arg::with_name("dry-run")
+                .long("dry-run")
+                .help("render without writing files, and list the files that would be written"),
+        )
+        .arg(
+            Arg::with_name("precompress")
+                .long("precompress")
+                .help("write gzip and brotli compressed copies of every page and asset"),
+        )
+        .arg(
+            Arg::with_name("open")
+                .long("open")
+                .help("open the documentation of the crate in a browser once it is rendered"),
+        )
+        .arg(
+            Arg::with_name("watch")
+                .long("watch")
+                .help("render again when the document or templates change, reloading served pages"),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Serves the documentation on localhost once it is rendered")
+                .arg(
+                    Arg::with_name("port")
+                        .long("port")
+                        .short("p")
+                        .takes_value(true)
+                        .default_value("8000")
+                        .help("the port to serve the documentation on"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("package")
+                .about("Packages the documentation into an archive once it is rendered")
+                .arg(
+                    Arg::with_name("archive")
+                        .value_name("ARCHIVE")
+                        .help("the .tar.gz or .zip archive to write; defaults to OUTPUT.tar.gz"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("link-check")
+                .about("Checks that the links between the pages of rendered documentation resolve")
+                .arg(
+                    Arg::with_name("doc-root")
+                        .value_name("DIR")
+                        .required(true)
+                        .help("the directory the documentation was rendered to"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("clean")
+                .about("Removes the files that were rendered into a directory")
+                .arg(
+                    Arg::with_name("doc-root")
+                        .value_name("DIR")
+                        .required(true)
+                        .help("the directory the documentation was rendered to"),
+                )
+                .arg(
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .help("list the files that would be removed without removing them"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("coverage")
+                .about("Reports how many of the items of each module are documented")
+                .arg(
+                    Arg::with_name("input")
+                        .value_name("INPUT")
+                        .help("the JSON document to check; read from stdin if omitted or `-`"),
+                )
+                .arg(
+                    Arg::with_name("fail-under")
+                        .long("fail-under")
+                        .takes_value(true)
+                        .value_name("PERCENT")
+                        .help("fail if less than this percentage of the items are documented"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Reports the items added, removed and changed between two documents")
+                .arg(
+                    Arg::with_name("old")
+                        .value_name("OLD")
+                        .required(true)
+                        .help("the JSON document of the old release"),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .value_name("NEW")
+                        .required(true)
+                        .help("the JSON document of the new release"),
+                )
+                .arg(
+                    Arg::with_name("html")
+                        .long("html")
+                        .takes_value(true)
+                        .value_name("DIR")
+                        .help("also write the changes to DIR/changes.html"),
+                ),
         )
         .get_matches();
 
-    let output_path = matches.value_of("output").unwrap();
+    // The log is configured by `RUST_LOG`, which takes precedence over the verbosity flags.
+    let verbosity = Verbosity::from_matches(&matches);
+    if env::var_os("RUST_LOG").is_none() {
+        match verbosity {
+            Verbosity::Verbose => env::set_var("RUST_LOG", "rustdoc_static=info"),
+            Verbosity::Debug => env::set_var("RUST_LOG", "rustdoc_static=debug"),
+            _ => (),
+        }
+    }
+    pretty_env_logger::init().unwrap();
+
+    // Checking links works on documentation that was already rendered, so nothing is rendered.
+    if let Some(link_check) = matches.subcommand_matches("link-check") {
+        let doc_root = link_check.value_of("doc-root").unwrap();
+        let dead_links = rustdoc_static::link_check::check(doc_root).chain_err(
+            || format!("could not check the links of `{}`", doc_root),
+        )?;
+
+        for dead_link in &dead_links {
+            println!("{}", dead_link);
+        }
+        match dead_links.len() {
+            0 => return Ok(()),
+            1 => bail!("found 1 dead link"),
+            n => bail!("found {} dead links", n),
+        }
+    }
+
+    // Only the files recorded in the manifest of the doc root are removed.
+    if let Some(clean) = matches.subcommand_matches("clean") {
+        let doc_root = clean.value_of("doc-root").unwrap();
+        let files = if clean.is_present("dry-run") {
+            rustdoc_static::clean::generated_files(doc_root)?
+        } else {
+            rustdoc_static::clean::clean(doc_root)?
+        };
+
+        if clean.is_present("dry-run") || verbosity >= Verbosity::Verbose {
+            for file in &files {
+                println!("{}", Path::new(doc_root).join(file).display());
+            }
+        }
+        if verbosity != Verbosity::Quiet {
+            let action = if clean.is_present("dry-run") { "Would remove" } else { "Removed" };
+            eprintln!("{} {} files from `{}`", action, files.len(), doc_root);
+        }
+        return Ok(());
+    }
+
+    if let Some(coverage) = matches.subcommand_matches("coverage") {
+        return report_coverage(coverage);
+    }
+
+    if let Some(diff) = matches.subcommand_matches("diff") {
+        return report_diff(diff);
+    }
+
+    let inputs = matches.values_of("input").into_iter().flatten().collect::<Vec<_>>();
+    if matches.is_present("watch") && (inputs.is_empty() || inputs.contains(&"-")) {
+        bail!("--watch needs the path of the document to watch");
+    }
+    if inputs.len() > 1 && inputs.contains(&"-") {
+        bail!("stdin cannot be read along with other documents");
+    }
+    if matches.is_present("dry-run") && matches.is_present("serve") {
+        bail!("--dry-run cannot be used with serve");
+    }
+    if matches.is_present("dry-run") && matches.is_present("package") {
+        bail!("--dry-run cannot be used with package");
+    }
+    if matches.is_present("dry-run") && matches.is_present("open") {
+        bail!("--dry-run cannot be used with --open");
+    }
+
+    let config = match matches.value_of("config") {
+        Some(path) => Config::from_path(path)?,
+        None => Config::discover(".")?.unwrap_or_default(),
+    };
+    let output_path = match matches.value_of("output") {
+        Some(output) => PathBuf::from(output),
+        None => config.output.clone().ok_or(
+            "no output directory; pass --output or set `output` in rustdoc-static.toml",
+        )?,
+    };
+
+    let page = build(&matches, &config, &output_path)?;
+    let open = matches.is_present("open");
+
+    let reload = LiveReload::new();
 
-    let mut json = String::new();
-    io::stdin().read_to_string(&mut json).chain_err(
-        || "could not read stdin",
-    )?;
+    if let Some(serve) = matches.subcommand_matches("serve") {
+        let port = serve.value_of("port").unwrap();
+        let port: u16 = port.parse().chain_err(|| "--port must be a port number")?;
+        let listener = TcpListener::bind(("127.0.0.1", port)).chain_err(
+            || format!("could not listen on port {}", port),
+        )?;
 
-    let document = JsonApiDocument::from_str(&json).chain_err(
-        || "could not read input as JSON API",
-    )?;
-    rustdoc_static::render_docs(&document, output_path)?;
+        if verbosity > Verbosity::Quiet {
+            println!("Serving documentation at http://127.0.0.1:{}/", port);
+        }
+        if open {
+            let path = page.as_ref().map(|page| url_path(page)).unwrap_or_default();
+            open_in_browser(format!("http://127.0.0.1:{}/{}", port, path));
+        }
+        if !matches.is_present("watch") {
+            return rustdoc_static::serve::serve(&output_path, listener).chain_err(
+                || "could not serve documentation",
+            );
+        }
+
+        let (doc_root, reload) = (output_path.clone(), reload.clone());
+        thread::spawn(move || {
+            if let Err(e) = rustdoc_static::serve::serve_with_reload(doc_root, listener, reload) {
+                eprintln!("error: could not serve documentation: {}", e);
+            }
+        });
+    }
+
+    if open && !matches.is_present("serve") {
+        match page {
+            Some(page) => {
+                let page = output_path.join(page);
+                open_in_browser(env::current_dir().map(|dir| dir.join(&page)).unwrap_or(page));
+            }
+            None => eprintln!("warning: the document has no crate to open"),
+        }
+    }
+
+    if matches.is_present("watch") {
+        let mut paths = inputs.iter().map(PathBuf::from).collect::<Vec<_>>();
+        paths.extend(matches.value_of("template-dir").map(PathBuf::from));
+        if matches.value_of("template-dir").is_none() {
+            paths.extend(config.template_dir.clone());
+        }
+
+        let names = paths.iter().map(|path| path.display().to_string()).collect::<Vec<_>>();
+        if verbosity > Verbosity::Quiet {
+            println!("Watching {} for changes", names.join(" and "));
+        }
+        rustdoc_static::watch::watch(&paths, Duration::from_millis(500), || {
+            match build(&matches, &config, &output_path) {
+                Ok(_) => reload.reload(),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    for cause in e.iter().skip(1) {
+                        eprintln!("caused by: {}", cause);
+                    }
+                }
+            }
+        }).chain_err(|| "could not watch for changes")?;
+    }
 
     Ok(())
 }
 
+/// Renders the documentation of the inputs with the given options, and runs the steps that use
+/// the rendered documentation, such as packaging it into an archive. Returns the page of the
+/// crate, or of the landing page of several crates, relative to the output directory.
+///
+/// Options given on the command line take precedence over the configuration.
+fn build(matches: &ArgMatches, config: &Config, output_path: &Path) -> Result<Option<PathBuf>> {
+    let start = Instant::now();
+    let documents = match matches.values_of("input") {
+        Some(inputs) => inputs.map(|input| load_document(Some(input))).collect::<Result<_>>()?,
+        None => vec![load_document(None)?],
+    };
+
+    let format = matches.value_of("format").or(config.format.as_deref()).unwrap_or("html");
+    if !FORMATS.contains(&format) {
+        bail!("unknown format `{}`; expected one of {}", format, FORMATS.join(", "));
+    }
+    if matches.is_present("dry-run") && format != "html" {
+        bail!("--dry-run is only supported for HTML output");
+    }
+    if matches.is_present("open") && format != "html" {
+        bail!("--open is only supported for HTML output");
+    }
+    if documents.len() > 1 && format != "html" {
+        bail!("only HTML output supports rendering several documents together");
+    }
+    let document = &documents[0];
+
+    let base_url = matches.value_of("base-url").or(config.base_url.as_deref());
+
+    // Only HTML renders report what they wrote.
+    let mut report = None;
+    match format {
+        "markdown" => rustdoc_static::backend::markdown::render(document, output_path)?,
+        "json" => rustdoc_static::backend::json::render(document, output_path)?,
+        "man" => rustdoc_static::backend::man::render(document, output_path)?,
+        "single" => rustdoc_static::backend::single::render(document, output_path)?,
+        "epub" => rustdoc_static::backend::epub::render(document, output_path)?,
+        "llms" => rustdoc_static::backend::llms::render(document, output_path)?,
+        "text" => rustdoc_static::backend::text::render(document, output_path)?,
+        _ => {
+            let mut options = config
+                .render_options(RenderOptions::new())?
+                .keep_going(matches.is_present("keep-going"))
+                .error_pages(matches.is_present("error-pages"))
+                .strict(matches.is_present("strict"))
+                .dry_run(matches.is_present("dry-run"));
+            if matches.is_present("document-private-items") {
+                options = options.document_private_items(true);
+            }
+            if matches.is_present("document-hidden-items") {
+                options = options.document_hidden_items(true);
+            }
+            if let Some(base_url) = base_url {
+                options = options.base_url(base_url);
+            }
+            if let Some(jobs) = matches.value_of("jobs") {
+                options = options.jobs(parse_jobs(jobs)?);
+            }
+            if let Some(theme) = matches.value_of("theme") {
+                options = options.theme(theme);
+            }
+            if let Some(lang) = matches.value_of("lang") {
+                options = options.lang(lang);
+            }
+            if let Some(template_dir) = matches.value_of("template-dir") {
+                options = options.template_dir(template_dir);
+            }
+            for glob in matches.values_of("include").into_iter().flatten() {
+                options = options.include(glob);
+            }
+            for glob in matches.values_of("exclude").into_iter().flatten() {
+                options = options.exclude(glob);
+            }
+            if let Some(analytics) = matches.value_of("analytics") {
+                options = options.analytics(analytics.parse()?);
+            }
+            if let Some(snippet) = matches.value_of("analytics-snippet") {
+                options = options.analytics_snippet(snippet);
+            }
+            if matches.is_present("robots-txt") {
+                options = options.robots_txt(true);
+            }
+            for path in matches.values_of("robots-disallow").into_iter().flatten() {
+                options = options.robots_disallow(path);
+            }
+            if matches.is_present("csp-headers") {
+                options = options.csp_headers(true);
+            }
+            if matches.is_present("service-worker") {
+                options = options.service_worker(true);
+            }
+            if matches.is_present("content-hashes") {
+                options = options.content_hashes(true);
+            }
+            for stylesheet in matches.values_of("extra-css").into_iter().flatten() {
+                options = options.extra_css(stylesheet);
+            }
+            if let Some(readme) = matches.value_of("readme") {
+                options = options.readme(readme);
+            }
+            if matches.is_present("readme-replaces-docs") {
+                options = options.readme_replaces_docs(true);
+            }
+            if let Some(source_dir) = matches.value_of("source-dir") {
+                options = options.source_dir(source_dir);
+            }
+            if let Some(logo_url) = matches.value_of("logo-url") {
+                options = options.logo_url(logo_url);
+            }
+            if let Some(favicon_url) = matches.value_of("favicon-url") {
+                options = options.favicon_url(favicon_url);
+            }
+
+            if matches.is_present("low-memory") {
+                options = options.low_memory(true);
+            }
+            if matches.is_present("share-fragments") {
+                options = options.share_fragments(true);
+            }
+            if matches.is_present("versioned") {
+                options = options.versioned(true);
+            }
+            if let Some(version) = matches.value_of("doc-version") {
+                options = options.version(version);
+            }
+            for dependency in matches.values_of("dependency").into_iter().flatten() {
+                options = options.dependency(rustdoc_static::load::from_path(dependency)?);
+            }
+            if let Some(old) = matches.value_of("changes-since") {
+                options = options.changes_since(rustdoc_static::load::from_path(old)?);
+            }
+            if let Some(max) = matches.value_of("max-open-files") {
+                let max = max.parse().chain_err(|| "--max-open-files must be a number")?;
+                options = options.max_open_files(max);
+            }
+            if let Some(threads) = matches.value_of("write-threads") {
+                let threads = threads.parse().chain_err(|| "--write-threads must be a number")?;
+                options = options.write_threads(threads);
+            }
+            if let Some(bytes) = matches.value_of("memory-limit") {
+                let bytes = bytes.parse().chain_err(|| "--memory-limit must be a number")?;
+                options = options.memory_limit(bytes);
+            }
+
+            let mut renderer = Renderer::with_options(options)?;
+            if matches.is_present("progress") {
+                renderer = renderer.on_progress(|progress| {
+                    let (rendered, total) = (progress.rendered, progress.total);
+                    eprint!("\r\x1b[K[{}/{}] {}", rendered, total, progress.current);
+                    if progress.rendered == progress.total {
+                        eprintln!();
+                    }
+                });
+            }
+            let html_report = renderer.render_multi(&documents, output_path)?;
+
+            if matches.is_present("dry-run") {
+                for path in html_report.files_written {
+                    println!("{}", path.display());
+                }
+                return Ok(None);
+            }
+            report = Some(html_report);
+        }
+    }
+
+    // The HTML renderer writes its own sitemap.
+    if let Some(base_url) = base_url.filter(|_| format != "html") {
+        rustdoc_static::sitemap::write_sitemap(output_path, base_url).chain_err(
+            || "could not write sitemap",
+        )?;
+    }
+
+    if matches.is_present("precompress") || config.precompress {
+        rustdoc_static::precompress::precompress(output_path).chain_err(
+            || "could not precompress documentation",
+        )?;
+    }
+
+    let archive = archive_path(matches, config, output_path)?;
+    if let Some(ref archive) = archive {
+        let format = ArchiveFormat::from_path(archive).ok_or_else(|| {
+            format!("could not determine archive format of `{}`", archive.display())
+        })?;
+        rustdoc_static::archive::package(output_path, archive, format).chain_err(
+            || "could not package documentation",
+        )?;
+
+        // The archive is what `package` is run for, so its path is printed even when quiet.
+        if matches.is_present("package") {
+            println!("{}", archive.display());
+        }
+    }
+
+    let verbosity = Verbosity::from_matches(matches);
+    if verbosity > Verbosity::Quiet {
+        print_summary(format, output_path, report.as_ref(), start.elapsed(), verbosity);
+    }
+
+    let page = rustdoc_static::browser::crate_page(&documents);
+    match report.and_then(|report| report.version) {
+        Some(version) => Ok(page.map(|page| Path::new(&version).join(page))),
+        None => Ok(page),
+    }
+}
+
+/// Parses the number of jobs to render with, which must be positive.
+fn parse_jobs(jobs: &str) -> Result<usize> {
+    match jobs.parse() {
+        Ok(0) | Err(_) => bail!("--jobs must be a positive number"),
+        Ok(jobs) => Ok(jobs),
+    }
+}
+
+/// Opens a page in a browser. A browser that cannot be opened is only a warning, since the
+/// documentation was rendered.
+fn open_in_browser<S: AsRef<OsStr>>(target: S) {
+    if let Err(e) = rustdoc_static::browser::open(&target) {
+        let target = target.as_ref().to_string_lossy();
+        eprintln!("warning: could not open `{}` in a browser: {}", target, e);
+    }
+}
+
+/// Returns the path of a page relative to the doc root as the path of a URL.
+fn url_path(page: &Path) -> String {
+    let components = page.iter().map(|component| component.to_string_lossy());
+    components.collect::<Vec<_>>().join("/")
+}
+
+/// Returns where the rendered documentation is packaged, if anywhere.
+///
+/// `package` writes the archive it is given, the archive of `--archive` or the configuration, or
+/// a tarball named after the output directory, next to it.
+fn archive_path(
+    matches: &ArgMatches,
+    config: &Config,
+    output_path: &Path,
+) -> Result<Option<PathBuf>> {
+    let archive = matches.value_of("archive").map(PathBuf::from).or_else(|| config.archive.clone());
+    let package = match matches.subcommand_matches("package") {
+        Some(package) => package,
+        None => return Ok(archive),
+    };
+
+    if let Some(archive) = package.value_of("archive").map(PathBuf::from).or(archive) {
+        return Ok(Some(archive));
+    }
+    let name = output_path.file_name().ok_or_else(|| {
+        format!("cannot name an archive after `{}`; pass ARCHIVE", output_path.display())
+    })?;
+    let mut name = name.to_owned();
+    name.push(".tar.gz");
+    Ok(Some(output_path.with_file_name(name)))
+}
+
+/// Loads the document at a path, or from stdin if the path is `-` or not given.
+fn load_document(input: Option<&str>) -> Result<JsonApiDocument> {
+    match input {
+        Some("-") | None => rustdoc_static::load::from_reader(io::stdin()).chain_err(
+            || "could not load the document from stdin",
+        ),
+        Some(input) => rustdoc_static::load::from_path(input),
+    }
+}
+
+/// Prints the documentation coverage of each module of a document, failing if the coverage of
+/// the whole document is under `--fail-under`.
+fn report_coverage(matches: &ArgMatches) -> Result<()> {
+    let fail_under = match matches.value_of("fail-under") {
+        Some(percent) => {
+            let percent: f64 = percent.trim_end_matches('%').parse().chain_err(
+                || "--fail-under must be a percentage",
+            )?;
+            Some(percent)
+        }
+        None => None,
+    };
+
+    let stats = Stats::collect(&load_document(matches.value_of("input"))?);
+    let total = stats.total();
+    let width = stats.modules.keys().map(|module| module.len()).max().unwrap_or(0).max(6);
+
+    println!(
+        "{:width$}  {:>10}  {:>5}  {:>8}",
+        "Module",
+        "Documented",
+        "Total",
+        "Coverage",
+        width = width
+    );
+    let rows = stats.modules.iter().map(|(module, counts)| (&module[..], counts));
+    for (module, counts) in rows.chain(Some(("Total", &total))) {
+        let coverage = counts.coverage().unwrap_or(100.0);
+        println!(
+            "{:width$}  {:>10}  {:>5}  {:>7.1}%",
+            module,
+            counts.documented,
+            counts.total,
+            coverage,
+            width = width
+        );
+    }
+
+    let coverage = total.coverage().unwrap_or(100.0);
+    if let Some(fail_under) = fail_under.filter(|&fail_under| total.is_under(fail_under)) {
+        bail!("documentation coverage of {:.1}% is under {}%", coverage, fail_under);
+    }
+
+    Ok(())
+}
+
+/// Prints the changes to the public API between two documents.
+fn report_diff(matches: &ArgMatches) -> Result<()> {
+    let old = rustdoc_static::load::from_path(matches.value_of("old").unwrap())?;
+    let new = rustdoc_static::load::from_path(matches.value_of("new").unwrap())?;
+    let diff = rustdoc_static::diff::diff(&old, &new)?;
+
+    if diff.is_empty() {
+        println!("The public API did not change");
+    }
+    let sections = [
+        ("Added", '+', &diff.added),
+        ("Removed", '-', &diff.removed),
+        ("Deprecated", '!', &diff.deprecated),
+        ("Changed", '~', &diff.changed),
+    ];
+    for &(heading, sign, changes) in &sections {
+        if changes.is_empty() {
+            continue;
+        }
+
+        println!("{}:", heading);
+        for change in changes {
+            let (kind, id, bump) = (&change.kind, &change.id, change.bump);
+            match change.attributes.len() {
+                0 => println!("  {} {} {} [{}]", sign, kind, id, bump),
+                _ => {
+                    let attributes = change.attributes.join(", ");
+                    println!("  {} {} {} ({}) [{}]", sign, kind, id, attributes, bump);
+                }
+            }
+        }
+    }
+    if !diff.is_empty() {
+        match (diff.old_version.as_ref(), diff.next_version()) {
+            (Some(old), Some(next)) => {
+                println!("Requires a {} version bump: {} -> {}", diff.bump(), old, next)
+            }
+            _ => println!("Requires a {} version bump", diff.bump()),
+        }
+    }
+
+    if let Some(doc_root) = matches.value_of("html") {
+        rustdoc_static::diff::write_changes_page(&diff, doc_root).chain_err(
+            || "could not write the API changes page",
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Prints what a render wrote, how long it took and, unless the log already showed them, the
+/// problems it found.
+fn print_summary(
+    format: &str,
+    output_path: &Path,
+    report: Option<&RenderReport>,
+    elapsed: Duration,
+    verbosity: Verbosity,
+) {
+    let seconds = elapsed.as_secs_f64();
+    let report = match report {
+        Some(report) => report,
+        None => {
+            let output_path = output_path.display();
+            eprintln!("Wrote {} documentation to `{}` in {:.2}s", format, output_path, seconds);
+            return;
+        }
+    };
+
+    let mut summary = format!(
+        "Rendered documentation to `{}` in {:.2}s: {} files written",
+        output_path.display(),
+        seconds,
+        report.files_written.len()
+    );
+    if !report.skipped.is_empty() {
+        summary.push_str(&format!(", {} unchanged pages skipped", report.skipped.len()));
+    }
+    eprintln!("{}", summary);
+
+    if verbosity == Verbosity::Normal {
+        if let Some(problems) = report.summary() {
+            eprint!("{}", problems);
+        }
+    }
+}
+
 quick_main!(run);
@@ -0,0 +1,183 @@
+//! A manifest of the pages written by a previous run, used to skip re-rendering unchanged pages.
+//!
+//! The manifest records a hash of the context each page was rendered from. The templates are
+//! compiled into the binary, so a page whose context is unchanged since the last run by the same
+//! version of rustdoc-static does not need to be rendered again.
+//!
+//! The manifest also records every file written into the doc root, including the files of previous
+//! runs that are still there, so that `clean` can remove them without touching other files.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::prelude::*;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::{self, Value};
+
+/// The name of the manifest file at the doc root.
+pub const MANIFEST: &str = ".rustdoc-static-manifest.json";
+
+/// The content hashes of the pages rendered into a doc root.
+#[derive(Debug, Default)]
+pub struct Manifest {
+    hashes: BTreeMap<String, String>,
+    files: BTreeSet<String>,
+}
+
+impl Manifest {
+    /// Loads the manifest written by a previous run.
+    ///
+    /// If there is no manifest, or it was written by a different version of rustdoc-static, an
+    /// empty manifest is returned so that every page is rendered.
+    pub fn load(doc_root: &Path) -> Manifest {
+        let manifest = match read(doc_root) {
+            Ok(Some(manifest)) => manifest,
+            Ok(None) => return Manifest::default(),
+            Err(e) => {
+                warn!("ignoring unreadable manifest: {}", e);
+                return Manifest::default();
+            }
+        };
+
+        if manifest["version"] != env!("CARGO_PKG_VERSION") {
+            return Manifest::default();
+        }
+
+        let hashes = manifest["pages"]
+            .as_object()
+            .into_iter()
+            .flat_map(|pages| pages.iter())
+            .filter_map(|(page, hash)| hash.as_str().map(|hash| (page.clone(), hash.to_owned())))
+            .collect();
+
+        Manifest { hashes, files: BTreeSet::new() }
+    }
+
+    /// Returns the files the manifest of a doc root records, relative to the doc root, or `None` if
+    /// there is no manifest.
+    ///
+    /// Unlike `load`, this reads manifests written by any version of rustdoc-static. Manifests
+    /// written before files were recorded only list the item pages.
+    pub fn files(doc_root: &Path) -> io::Result<Option<Vec<PathBuf>>> {
+        let manifest = match read(doc_root)? {
+            Some(manifest) => manifest,
+            None => return Ok(None),
+        };
+
+        let pages = manifest["pages"].as_object().into_iter().flat_map(|pages| pages.keys());
+        let files = manifest["files"]
+            .as_array()
+            .into_iter()
+            .flat_map(|files| files.iter())
+            .filter_map(Value::as_str);
+        let files = pages.map(|page| &page[..]).chain(files).collect::<BTreeSet<_>>();
+
+        Ok(Some(files.into_iter().map(PathBuf::from).collect()))
+    }
+
+    /// Records files written into a doc root after its manifest was saved, relative to the doc
+    /// root. Nothing is recorded if the doc root has no manifest.
+    pub fn record_files(doc_root: &Path, files: &[&Path]) -> io::Result<()> {
+        let mut manifest = match read(doc_root)? {
+            Some(manifest) => manifest,
+            None => return Ok(()),
+        };
+
+        let recorded = manifest["files"].as_array().into_iter().flat_map(|files| files.iter());
+        let mut recorded =
+            recorded.filter_map(Value::as_str).map(String::from).collect::<BTreeSet<_>>();
+        recorded.extend(files.iter().map(|file| key(file)));
+        manifest["files"] = json!(recorded);
+
+        let json = serde_json::to_string_pretty(&manifest).unwrap();
+        File::create(doc_root.join(MANIFEST))?.write_all(json.as_bytes())
+    }
+
+    /// Returns whether a page was rendered from the same context by the previous run.
+    pub fn is_fresh(&self, page: &Path, hash: &str) -> bool {
+        self.hashes.get(&key(page)).is_some_and(|previous| previous == hash)
+    }
+
+    /// Records the hash of the context a page was rendered from.
+    pub fn insert(&mut self, page: &Path, hash: String) {
+        self.hashes.insert(key(page), hash);
+    }
+
+    /// Records the pages and files of another manifest, such as that of the pages rendered by
+    /// another thread.
+    pub fn append(&mut self, mut other: Manifest) {
+        self.hashes.append(&mut other.hashes);
+        self.files.append(&mut other.files);
+    }
+
+    /// Records a file written into the doc root, relative to the doc root.
+    pub fn insert_file(&mut self, file: &Path) {
+        self.files.insert(key(file));
+    }
+
+    /// Writes the manifest to the doc root.
+    pub fn save(&self, doc_root: &Path) -> io::Result<()> {
+        let manifest = json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "pages": self.hashes,
+            "files": self.files,
+        });
+
+        let json = serde_json::to_string_pretty(&manifest).unwrap();
+        File::create(doc_root.join(MANIFEST))?.write_all(json.as_bytes())
+    }
+}
+
+/// Reads the manifest of a doc root as JSON, returning `None` if there is no manifest.
+fn read(doc_root: &Path) -> io::Result<Option<Value>> {
+    let mut json = String::new();
+    match File::open(doc_root.join(MANIFEST)) {
+        Ok(mut file) => file.read_to_string(&mut json)?,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Hashes the context a page is rendered from.
+pub fn hash(context: &Value) -> String {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(context.to_string().as_bytes());
+    format!("{:016x}", hasher.finish())
+}
+
+/// Returns the key of a page in the manifest, which is its path with forward slashes.
+///
+/// Pages are named after resource IDs, so their paths are always valid UTF-8.
+fn key(page: &Path) -> String {
+    page.iter()
+        .map(|component| component.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{hash, Manifest};
+
+    #[test]
+    fn is_fresh() {
+        let page = Path::new("test_crate/index.html");
+        let context = json!({ "name": "test_crate" });
+
+        let mut manifest = Manifest::default();
+        assert!(!manifest.is_fresh(page, &hash(&context)));
+
+        manifest.insert(page, hash(&context));
+        assert!(manifest.is_fresh(page, &hash(&context)));
+        assert!(!manifest.is_fresh(page, &hash(&json!({ "name": "other_crate" }))));
+    }
+}
@@ -0,0 +1,126 @@
+//! Rendering of Markdown documentation to HTML.
+
+use pulldown_cmark::{html, Event, Parser, Tag};
+
+/// Markup inserted before every code block, wrapping it with a button that copies its contents.
+const CODE_BLOCK_START: &str = "<div class=\"example-wrap\">\
+                                <button class=\"copy-code\" title=\"Copy code to clipboard\">\
+                                Copy</button>";
+
+/// Renders Markdown as HTML.
+///
+/// Code blocks are wrapped in a container that includes a copy-to-clipboard button. Headings are
+/// one level lower than written, since the title of the page is its only `<h1>`.
+pub fn render(markdown: &str) -> String {
+    render_with_links(markdown, |_| None)
+}
+
+/// Renders Markdown as HTML, like `render`, replacing the destinations of links that `resolve`
+/// returns a URL for. Other links are kept as written.
+pub fn render_with_links<F>(markdown: &str, resolve: F) -> String
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let events = Parser::new(markdown).flat_map(|event| match event {
+        Event::Start(Tag::Link(dest, title)) => {
+            let dest = resolve(&dest).map(Into::into).unwrap_or(dest);
+            vec![Event::Start(Tag::Link(dest, title))]
+        }
+        Event::Start(Tag::Header(level)) => vec![Event::Start(Tag::Header((level + 1).min(6)))],
+        Event::End(Tag::Header(level)) => vec![Event::End(Tag::Header((level + 1).min(6)))],
+        Event::Start(Tag::CodeBlock(lang)) => vec![
+            Event::Html(CODE_BLOCK_START.into()),
+            Event::Start(Tag::CodeBlock(lang)),
+        ],
+        Event::End(Tag::CodeBlock(lang)) => {
+            vec![Event::End(Tag::CodeBlock(lang)), Event::Html("</div>".into())]
+        }
+        event => vec![event],
+    });
+
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, events);
+    rendered
+}
+
+/// Renders Markdown as HTML, without any interactive elements.
+pub fn render_plain(markdown: &str) -> String {
+    let mut rendered = String::new();
+    html::push_html(&mut rendered, Parser::new(markdown));
+    rendered
+}
+
+/// Returns the first sentence of the first paragraph of Markdown, as plain text.
+pub fn summary(markdown: &str) -> Option<String> {
+    let mut text = String::new();
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(t) => text.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => text.push(' '),
+            Event::End(Tag::Paragraph) => break,
+            _ => (),
+        }
+    }
+
+    let end = text
+        .match_indices(". ")
+        .next()
+        .map(|(index, _)| index + 1)
+        .unwrap_or_else(|| text.len());
+    text.truncate(end);
+
+    let summary = text.trim();
+    if !summary.is_empty() {
+        Some(summary.to_owned())
+    } else {
+        None
+    }
+}
+
+/// Returns whether Markdown contains a code block.
+pub fn has_code_block(markdown: &str) -> bool {
+    Parser::new(markdown).any(|event| matches!(event, Event::Start(Tag::CodeBlock(_))))
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn code_block_copy_button() {
+        let rendered = super::render("Example:\n\n```\nlet x = 1;\n```\n");
+        assert!(rendered.contains("<div class=\"example-wrap\"><button class=\"copy-code\""));
+        assert!(rendered.ends_with("let x = 1;\n</code></pre>\n</div>"));
+    }
+
+    #[test]
+    fn headings() {
+        let rendered = super::render("# Examples\n\n###### Deepest\n");
+        assert_eq!(rendered, "<h2>Examples</h2>\n<h6>Deepest</h6>\n");
+    }
+
+    #[test]
+    fn render_with_links() {
+        let rendered = super::render_with_links(
+            "See [`Foo`](crate::Foo) and [the book](https://example.com).",
+            |dest| if dest == "crate::Foo" { Some(String::from("struct.Foo.html")) } else { None },
+        );
+        assert_eq!(
+            rendered,
+            "<p>See <a href=\"struct.Foo.html\"><code>Foo</code></a> and \
+             <a href=\"https://example.com\">the book</a>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn summary() {
+        assert_eq!(
+            super::summary("Creates a `Foo`. Panics\nsometimes.\n\nMore docs."),
+            Some(String::from("Creates a Foo."))
+        );
+        assert_eq!(
+            super::summary("A long\nfirst line\n\nSecond paragraph."),
+            Some(String::from("A long first line"))
+        );
+        assert_eq!(super::summary(""), None);
+    }
+}
@@ -0,0 +1,383 @@
+//! A typed view of the resources in a document.
+//!
+//! The rustdoc backend stores the details of each item as untyped JSON attributes. Parsing them
+//! up front into the types of this module means that attributes with the wrong shape are reported
+//! once, with the item and attribute they belong to, instead of being silently ignored by the
+//! pages that use them.
+
+use jsonapi::api::{JsonApiDocument, JsonApiValue, Resource};
+
+use errors::*;
+use resources;
+use source::SourceLocation;
+
+/// An item of the documentation, parsed from a resource.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item<'a> {
+    /// The path of the item, such as `my_crate::Foo`.
+    pub id: &'a str,
+
+    /// What kind of item this is, with the details that only apply to that kind.
+    pub kind: Kind<'a>,
+
+    /// The documentation of the item, written in Markdown.
+    pub docs: Option<&'a str>,
+
+    /// Where the item is defined.
+    pub source: Option<SourceLocation>,
+
+    /// The conditional compilation requirements of the item, such as `unix`.
+    pub cfg: Option<&'a str>,
+
+    /// The deprecation of the item, as declared by `#[deprecated]`.
+    pub deprecation: Option<Deprecation<'a>>,
+
+    /// The stability of the item, as declared by `#[stable]` or `#[unstable]`.
+    pub stability: Option<Stability<'a>>,
+
+    /// The visibility of the item, such as `public` or `crate`. Items without a visibility are
+    /// as visible as their parent.
+    pub visibility: Option<&'a str>,
+
+    /// Whether the item is hidden with `#[doc(hidden)]`.
+    pub hidden: bool,
+}
+
+/// The kinds of items, named after the resource types of the rustdoc backend.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Kind<'a> {
+    /// A crate, which is a primary resource of the document.
+    Crate(Crate<'a>),
+
+    /// A module.
+    Module,
+
+    /// A struct.
+    Struct,
+
+    /// An enum.
+    Enum,
+
+    /// A trait.
+    Trait,
+
+    /// A function or method.
+    Function(Function<'a>),
+
+    /// A type alias.
+    Type,
+
+    /// A constant.
+    Const,
+
+    /// A field of a struct.
+    Field,
+
+    /// An implementation of a type or a trait.
+    Impl(Impl<'a>),
+
+    /// A resource type that the renderer does not know how to document, such as `macro`.
+    Other(&'a str),
+}
+
+/// The metadata of a crate, usually taken from its manifest.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Crate<'a> {
+    /// The version of the crate.
+    pub version: Option<&'a str>,
+
+    /// A short description of the crate.
+    pub description: Option<&'a str>,
+
+    /// The license of the crate, as an SPDX expression.
+    pub license: Option<&'a str>,
+
+    /// The URL of the repository of the crate.
+    pub repository: Option<&'a str>,
+
+    /// The URL of the homepage of the crate.
+    pub homepage: Option<&'a str>,
+
+    /// The URL that the documentation of the crate is hosted at.
+    pub documentation: Option<&'a str>,
+
+    /// The URL or path of the logo of the crate, from `#![doc(html_logo_url)]`.
+    pub logo_url: Option<&'a str>,
+
+    /// The URL or path of the favicon of the crate, from `#![doc(html_favicon_url)]`.
+    pub favicon_url: Option<&'a str>,
+}
+
+/// The details of a function.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Function<'a> {
+    /// The ID of the type that the function returns, if it is part of the document.
+    pub output: Option<&'a str>,
+}
+
+/// The details of an implementation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Impl<'a> {
+    /// The path of the implemented trait, for trait implementations.
+    pub trait_path: Option<&'a str>,
+}
+
+/// The deprecation of an item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Deprecation<'a> {
+    /// The version the item was deprecated in.
+    pub since: Option<&'a str>,
+
+    /// Why the item was deprecated, written in Markdown.
+    pub note: Option<&'a str>,
+
+    /// The path of the item that replaces this one.
+    pub suggestion: Option<&'a str>,
+}
+
+/// The stability of an item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stability<'a> {
+    /// Whether the item is unstable, instead of stable.
+    pub unstable: bool,
+
+    /// The version the item was stabilized in.
+    pub since: Option<&'a str>,
+
+    /// The name of the feature that enables an unstable item.
+    pub feature: Option<&'a str>,
+
+    /// The tracking issue of an unstable item.
+    pub issue: Option<&'a str>,
+}
+
+impl<'a> Item<'a> {
+    /// Parses the attributes of a resource.
+    ///
+    /// Returns an `InvalidDocument` error that names the resource and attribute if an attribute
+    /// does not have the expected type.
+    pub fn from_resource(resource: &'a Resource) -> Result<Item<'a>> {
+        let attributes = Attributes { resource, value: None };
+
+        let kind = match resource._type.as_str() {
+            "crate" => Kind::Crate(Crate {
+                version: attributes.string("version")?,
+                description: attributes.string("description")?,
+                license: attributes.string("license")?,
+                repository: attributes.string("repository")?,
+                homepage: attributes.string("homepage")?,
+                documentation: attributes.string("documentation")?,
+                logo_url: attributes.string("html_logo_url")?,
+                favicon_url: attributes.string("html_favicon_url")?,
+            }),
+            "module" => Kind::Module,
+            "struct" => Kind::Struct,
+            "enum" => Kind::Enum,
+            "trait" => Kind::Trait,
+            "function" => Kind::Function(Function { output: attributes.string("output")? }),
+            "type" => Kind::Type,
+            "const" => Kind::Const,
+            "field" => Kind::Field,
+            "impl" => Kind::Impl(Impl { trait_path: attributes.string("trait")? }),
+            ty => Kind::Other(ty),
+        };
+
+        let source = match attributes.object("source")? {
+            Some(source) => Some(SourceLocation {
+                file: source.required_string("file")?.to_owned(),
+                line: source.integer("line")?,
+            }),
+            None => None,
+        };
+
+        let deprecation = match attributes.object("deprecated")? {
+            Some(deprecated) => Some(Deprecation {
+                since: deprecated.string("since")?,
+                note: deprecated.string("note")?,
+                suggestion: deprecated.string("suggestion")?,
+            }),
+            None => None,
+        };
+
+        let stability = match attributes.object("stability")? {
+            Some(stability) => {
+                let unstable = match stability.required_string("level")? {
+                    "stable" => false,
+                    "unstable" => true,
+                    level => {
+                        return Err(stability.invalid(
+                            "level",
+                            &format!("must be `stable` or `unstable`, not `{}`", level),
+                        ))
+                    }
+                };
+
+                Some(Stability {
+                    unstable,
+                    since: stability.string("since")?,
+                    feature: stability.string("feature")?,
+                    issue: stability.string("issue")?,
+                })
+            }
+            None => None,
+        };
+
+        Ok(Item {
+            id: &resource.id,
+            kind,
+            docs: attributes.string("docs")?,
+            source,
+            cfg: attributes.string("cfg")?,
+            deprecation,
+            stability,
+            visibility: attributes.string("visibility")?,
+            hidden: attributes.boolean("hidden")?.unwrap_or(false),
+        })
+    }
+}
+
+/// Parses every resource of a document, failing on the first one with invalid attributes.
+pub fn parse<'a>(document: &'a JsonApiDocument) -> Result<Vec<Item<'a>>> {
+    resources(document).map(Item::from_resource).collect()
+}
+
+/// The attributes of a resource, or the fields of one of its object attributes.
+struct Attributes<'a> {
+    resource: &'a Resource,
+
+    /// The name and value of the object attribute, or `None` for the attributes of the resource.
+    value: Option<(&'a str, &'a JsonApiValue)>,
+}
+
+impl<'a> Attributes<'a> {
+    fn get(&self, name: &str) -> Option<&'a JsonApiValue> {
+        match self.value {
+            Some((_, value)) => value.get(name).filter(|value| !value.is_null()),
+            None => self.resource.attributes.get(name).filter(|value| !value.is_null()),
+        }
+    }
+
+    fn string(&self, name: &str) -> Result<Option<&'a str>> {
+        match self.get(name) {
+            Some(value) => value
+                .as_str()
+                .map(Some)
+                .ok_or_else(|| self.invalid(name, "must be a string")),
+            None => Ok(None),
+        }
+    }
+
+    fn required_string(&self, name: &str) -> Result<&'a str> {
+        self.string(name)?.ok_or_else(|| self.invalid(name, "is missing"))
+    }
+
+    fn boolean(&self, name: &str) -> Result<Option<bool>> {
+        match self.get(name) {
+            Some(value) => {
+                value.as_bool().map(Some).ok_or_else(|| self.invalid(name, "must be a boolean"))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn integer(&self, name: &str) -> Result<Option<u64>> {
+        match self.get(name) {
+            Some(value) => {
+                value.as_u64().map(Some).ok_or_else(|| self.invalid(name, "must be an integer"))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn object(&self, name: &'a str) -> Result<Option<Attributes<'a>>> {
+        match self.get(name) {
+            Some(value) if value.is_object() => {
+                Ok(Some(Attributes { resource: self.resource, value: Some((name, value)) }))
+            }
+            Some(_) => Err(self.invalid(name, "must be an object")),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns an error for an attribute that does not have the expected type.
+    fn invalid(&self, name: &str, problem: &str) -> Error {
+        let name = match self.value {
+            Some((object, _)) => format!("{}.{}", object, name),
+            None => String::from(name),
+        };
+
+        let reason = format!("attribute `{}` of `{}` {}", name, self.resource.id, problem);
+        ErrorKind::InvalidDocument(reason).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonapi::api::JsonApiDocument;
+
+    use super::{Function, Item, Kind, Stability};
+    use resources;
+
+    fn document(included: &str) -> JsonApiDocument {
+        JsonApiDocument::from_str(&format!(
+            r#"{{
+                "data": {{ "type": "crate", "id": "test_crate", "attributes": {{}} }},
+                "included": [{}]
+            }}"#,
+            included
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn from_resource() {
+        let document = document(
+            r#"{
+                "type": "function",
+                "id": "test_crate::f",
+                "attributes": {
+                    "docs": "A function.",
+                    "output": "test_crate::Foo",
+                    "source": { "file": "src/lib.rs", "line": 3 },
+                    "stability": { "level": "unstable", "feature": "f" }
+                }
+            }"#,
+        );
+
+        let items = super::parse(&document).unwrap();
+        assert_eq!(items[0].kind, Kind::Crate(Default::default()));
+
+        let function = &items[1];
+        assert_eq!(function.kind, Kind::Function(Function { output: Some("test_crate::Foo") }));
+        assert_eq!(function.docs, Some("A function."));
+        assert_eq!(function.source.as_ref().unwrap().line, Some(3));
+        assert_eq!(
+            function.stability,
+            Some(Stability { unstable: true, feature: Some("f"), ..Default::default() })
+        );
+    }
+
+    #[test]
+    fn invalid_attributes() {
+        let error = |included| {
+            let document = document(included);
+            let resource = resources(&document).nth(1).unwrap();
+            Item::from_resource(resource).unwrap_err().to_string()
+        };
+
+        assert_eq!(
+            error(r#"{ "type": "struct", "id": "test_crate::Foo", "attributes": { "docs": 1 } }"#),
+            "invalid document: attribute `docs` of `test_crate::Foo` must be a string"
+        );
+        assert_eq!(
+            error(
+                r#"{
+                    "type": "struct",
+                    "id": "test_crate::Foo",
+                    "attributes": { "source": { "line": 3 } }
+                }"#
+            ),
+            "invalid document: attribute `source.file` of `test_crate::Foo` is missing"
+        );
+    }
+}
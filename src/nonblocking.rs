@@ -0,0 +1,110 @@
+//! Rendering for async services, behind the `async` feature.
+//!
+//! Rendering is moved onto tokio's blocking thread pool, so that services rendering documentation
+//! on demand do not block their worker threads on filesystem I/O.
+
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use jsonapi::api::JsonApiDocument;
+use tokio::task::{self, JoinHandle};
+
+use errors::*;
+use {RenderReport, Renderer};
+
+/// Given a JSON-API document generated by the rustdoc backend, generates a tree of documentation
+/// files at the doc root without blocking the current task.
+///
+/// The returned future must be polled from within a tokio runtime.
+pub fn render_docs_async<P: Into<PathBuf>>(document: JsonApiDocument, root: P) -> RenderFuture {
+    render_async(Arc::new(Renderer::new()), document, root)
+}
+
+/// Renders documentation with a shared renderer without blocking the current task.
+///
+/// The returned future must be polled from within a tokio runtime.
+pub fn render_async<P: Into<PathBuf>>(
+    renderer: Arc<Renderer>,
+    document: JsonApiDocument,
+    root: P,
+) -> RenderFuture {
+    RenderFuture {
+        render: Some((renderer, document, root.into())),
+        handle: None,
+    }
+}
+
+/// A future that resolves to the report of the render once it has finished.
+///
+/// Like an `async fn`, nothing is rendered until the future is first polled.
+pub struct RenderFuture {
+    render: Option<(Arc<Renderer>, JsonApiDocument, PathBuf)>,
+    handle: Option<JoinHandle<Result<RenderReport>>>,
+}
+
+impl Future for RenderFuture {
+    type Output = Result<RenderReport>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<RenderReport>> {
+        if let Some((renderer, document, root)) = self.render.take() {
+            self.handle = Some(task::spawn_blocking(move || renderer.render(&document, root)));
+        }
+
+        let handle = self.handle.as_mut().expect("future polled after completion");
+        match Pin::new(handle).poll(cx) {
+            Poll::Ready(Ok(result)) => Poll::Ready(result),
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e.to_string().into())),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use jsonapi::api::JsonApiDocument;
+    use tokio::runtime::Builder;
+
+    use output::MemoryBackend;
+    use Renderer;
+
+    #[test]
+    fn render_async() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": {},
+                    "relationships": {
+                        "structs": { "data": [{ "type": "struct", "id": "test_crate::Missing" }] }
+                    }
+                },
+                "included": [{ "type": "function", "id": "test_crate::f", "attributes": {} }]
+            }"#,
+        ).unwrap();
+
+        let (sync_backend, async_backend) = (MemoryBackend::new(), MemoryBackend::new());
+        let mut sync = Renderer::new()
+            .backend(sync_backend.clone())
+            .render(&document, "/nonexistent")
+            .unwrap();
+
+        let renderer = Arc::new(Renderer::new().backend(async_backend.clone()));
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let mut nonblocking = runtime
+            .block_on(super::render_async(renderer, document, "/nonexistent"))
+            .unwrap();
+
+        // Pages are written from more than one thread, in no particular order.
+        sync.files_written.sort();
+        nonblocking.files_written.sort();
+        assert_eq!(nonblocking, sync);
+        assert!(!sync.unresolved_links.is_empty());
+        assert_eq!(async_backend.files(), sync_backend.files());
+    }
+}
@@ -0,0 +1,83 @@
+//! A service worker that caches the rendered documentation, so that it can be browsed offline,
+//! search included, once a reader has visited it.
+//!
+//! The worker is written to `service-worker.js` at the doc root along with a cache manifest,
+//! `offline-manifest.json`, that lists every file of the documentation. The settings script of
+//! every page registers the worker, which caches the files of the manifest when it is installed.
+//! The version of the manifest is written into the worker, so that browsers install the worker
+//! of a new render and drop the cache of the previous one.
+
+use std::path::{Path, PathBuf};
+
+use serde_json::{self, Value};
+
+use {csp, hashes, manifest};
+
+/// The name of the service worker, at the doc root.
+pub const SERVICE_WORKER: &str = "service-worker.js";
+
+/// The name of the cache manifest, at the doc root.
+pub const CACHE_MANIFEST: &str = "offline-manifest.json";
+
+/// Returns the cache manifest and the service worker of the documentation, for the files of the
+/// documentation relative to the doc root.
+///
+/// Files that are not pages or assets of the pages, such as `_headers`, are left out.
+pub(crate) fn offline_files(files: &[PathBuf]) -> (String, String) {
+    let mut files = files
+        .iter()
+        .filter(|file| !is_left_out(file))
+        .map(|file| file.iter().map(|c| c.to_string_lossy()).collect::<Vec<_>>().join("/"))
+        .collect::<Vec<_>>();
+    files.sort();
+    files.dedup();
+
+    let files = Value::Array(files.into_iter().map(Value::String).collect());
+    let version = &manifest::hash(&files)[..8];
+    let cache_manifest = json!({ "version": version, "files": files });
+
+    let worker = format!(
+        "self.rustdocCacheVersion = {};\n{}",
+        Value::String(String::from(version)),
+        include_str!("../static/service-worker.js")
+    );
+    (serde_json::to_string_pretty(&cache_manifest).unwrap(), worker)
+}
+
+/// Returns whether a file is left out of the cache manifest.
+fn is_left_out(file: &Path) -> bool {
+    let left_out = [
+        SERVICE_WORKER,
+        CACHE_MANIFEST,
+        csp::HEADERS,
+        hashes::CONTENT_HASHES,
+        "robots.txt",
+        "sitemap.xml",
+    ];
+    let name = file.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned());
+    name.starts_with('.') || left_out.contains(&&name[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::{self, Value};
+
+    #[test]
+    fn offline_files() {
+        let files = [
+            PathBuf::from("demo/index.html"),
+            PathBuf::from("search-index.js"),
+            PathBuf::from(".rustdoc-static-manifest.json"),
+            PathBuf::from("_headers"),
+            PathBuf::from("demo/index.html"),
+        ];
+        let (cache_manifest, worker) = super::offline_files(&files);
+
+        let cache_manifest = serde_json::from_str::<Value>(&cache_manifest).unwrap();
+        assert_eq!(cache_manifest["files"], json!(["demo/index.html", "search-index.js"]));
+        let version = cache_manifest["version"].as_str().unwrap();
+        assert!(worker.starts_with(&format!("self.rustdocCacheVersion = \"{}\";\n", version)));
+    }
+}
@@ -0,0 +1,526 @@
+//! Options that configure a render through `render_docs_with`.
+
+use std::path::PathBuf;
+
+use jsonapi::api::{JsonApiDocument, Resource};
+use serde_json::Value;
+
+use analytics::Analytics;
+
+/// The language of pages unless another one is set with `RenderOptions::lang`.
+pub(crate) const DEFAULT_LANG: &str = "en";
+
+/// A builder for the options of a render.
+///
+/// The doc root that the documentation is written to is passed to `render_docs_with` alongside
+/// the options.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub(crate) theme: Option<String>,
+    pub(crate) lang: Option<String>,
+    pub(crate) template_dir: Option<PathBuf>,
+    pub(crate) base_url: Option<String>,
+    pub(crate) robots_txt: bool,
+    pub(crate) robots_disallow: Vec<String>,
+    pub(crate) csp_headers: bool,
+    pub(crate) service_worker: bool,
+    pub(crate) content_hashes: bool,
+    pub(crate) extra_css: Vec<PathBuf>,
+    pub(crate) readme: Option<PathBuf>,
+    pub(crate) readme_replaces_docs: bool,
+    pub(crate) source_dir: Option<PathBuf>,
+    pub(crate) logo_url: Option<String>,
+    pub(crate) favicon_url: Option<String>,
+    pub(crate) analytics: Option<Analytics>,
+    pub(crate) analytics_snippet: Option<PathBuf>,
+    pub(crate) included: Vec<String>,
+    pub(crate) excluded: Vec<String>,
+    pub(crate) document_private_items: bool,
+    pub(crate) document_hidden_items: bool,
+    pub(crate) parallel: bool,
+    pub(crate) jobs: Option<usize>,
+    pub(crate) strict: bool,
+    pub(crate) dry_run: bool,
+    pub(crate) keep_going: bool,
+    pub(crate) error_pages: bool,
+    pub(crate) max_open_files: Option<usize>,
+    pub(crate) write_threads: Option<usize>,
+    pub(crate) memory_limit: Option<usize>,
+    pub(crate) low_memory: bool,
+    pub(crate) share_fragments: bool,
+    pub(crate) versioned: bool,
+    pub(crate) version: Option<String>,
+    pub(crate) changes_since: Option<JsonApiDocument>,
+    pub(crate) dependencies: Vec<JsonApiDocument>,
+    pub(crate) context_transform: Option<fn(&Resource, &mut Value)>,
+}
+
+impl RenderOptions {
+    /// Creates the default options, which match those of `render_docs`.
+    pub fn new() -> RenderOptions {
+        RenderOptions {
+            theme: None,
+            lang: None,
+            template_dir: None,
+            base_url: None,
+            robots_txt: false,
+            robots_disallow: vec![],
+            csp_headers: false,
+            service_worker: false,
+            content_hashes: false,
+            extra_css: vec![],
+            readme: None,
+            readme_replaces_docs: false,
+            source_dir: None,
+            logo_url: None,
+            favicon_url: None,
+            analytics: None,
+            analytics_snippet: None,
+            included: vec![],
+            excluded: vec![],
+            document_private_items: false,
+            document_hidden_items: false,
+            parallel: true,
+            jobs: None,
+            strict: false,
+            dry_run: false,
+            keep_going: false,
+            error_pages: false,
+            max_open_files: None,
+            write_threads: None,
+            memory_limit: None,
+            low_memory: false,
+            share_fragments: false,
+            versioned: false,
+            version: None,
+            changes_since: None,
+            dependencies: vec![],
+            context_transform: None,
+        }
+    }
+
+    /// Sets the theme that pages are displayed with until the reader picks one in the settings
+    /// panel: `light`, `dark` or `high-contrast`, whose colors meet the WCAG AA contrast
+    /// requirements. The default is `light`.
+    pub fn theme<S: Into<String>>(mut self, theme: S) -> RenderOptions {
+        self.theme = Some(theme.into());
+        self
+    }
+
+    /// Sets the language that the docs are written in, as a language tag such as `de` or
+    /// `pt-BR`, for the `lang` attribute of every page. The default is `en`.
+    ///
+    /// Screen readers pick their pronunciation by the language of a page, and search engines show
+    /// pages to readers of their language.
+    pub fn lang<S: Into<String>>(mut self, lang: S) -> RenderOptions {
+        self.lang = Some(lang.into());
+        self
+    }
+
+    /// Loads templates from a directory, overriding the built-in template of the same name.
+    ///
+    /// The directory may contain any of `item.hbs`, `source.hbs`, `deprecated.hbs`, `stats.hbs`,
+    /// `single.hbs`, `settings.hbs`, `sidebar.hbs`, `search.hbs`, `error.hbs`, `changes.hbs` and
+    /// `crates.hbs`. Missing templates fall back to the built-in ones.
+    ///
+    /// The names of the stylesheets and scripts of pages contain a hash of their contents, so
+    /// templates refer to them through `assets`, such as `{{ pathToRoot }}/{{ assets.styles }}`.
+    /// The links to the logo and favicon of the crate, if it has them, are `icons.logo` and
+    /// `icons.favicon`.
+    pub fn template_dir<P: Into<PathBuf>>(mut self, template_dir: P) -> RenderOptions {
+        self.template_dir = Some(template_dir.into());
+        self
+    }
+
+    /// Sets the URL the documentation will be hosted at, and writes a `sitemap.xml` that uses it.
+    pub fn base_url<S: Into<String>>(mut self, base_url: S) -> RenderOptions {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Sets whether a `robots.txt` is written at the doc root, which lets crawlers index the
+    /// documentation but not the source pages or the search index. Disabled by default.
+    ///
+    /// The paths in `robots.txt` are under the path of the base URL, and it links to the sitemap
+    /// if there is one. Crawlers only read it at the root of the host.
+    pub fn robots_txt(mut self, robots_txt: bool) -> RenderOptions {
+        self.robots_txt = robots_txt;
+        self
+    }
+
+    /// Disallows crawlers from a path under the doc root in `robots.txt`, along with the source
+    /// pages and the search index, such as `my_crate/internal/`. May be given more than once.
+    pub fn robots_disallow<S: Into<String>>(mut self, path: S) -> RenderOptions {
+        self.robots_disallow.push(path.into());
+        self
+    }
+
+    /// Sets whether a `_headers` file is written at the doc root, which sends the recommended
+    /// Content Security Policy with every page on hosts that read it, such as Netlify and
+    /// Cloudflare Pages. Disabled by default.
+    ///
+    /// The policy allows the origins of the `analytics` service, but not those of an
+    /// `analytics_snippet`, which have to be added to the file.
+    pub fn csp_headers(mut self, csp_headers: bool) -> RenderOptions {
+        self.csp_headers = csp_headers;
+        self
+    }
+
+    /// Sets whether a service worker is written at the doc root, which caches every page of the
+    /// documentation once a reader visits one, so that the documentation and its search work
+    /// offline. Disabled by default.
+    ///
+    /// Browsers only run service workers for documentation that is served over HTTPS or from
+    /// localhost.
+    pub fn service_worker(mut self, service_worker: bool) -> RenderOptions {
+        self.service_worker = service_worker;
+        self
+    }
+
+    /// Sets whether `content-hashes.json` is written at the doc root, which maps every file of the
+    /// documentation to a hash of its contents and notes which files changed since the previous
+    /// render, so that only their URLs have to be purged from a CDN. Disabled by default.
+    ///
+    /// The hashes are also recorded in the `content_hashes` of the report.
+    pub fn content_hashes(mut self, content_hashes: bool) -> RenderOptions {
+        self.content_hashes = content_hashes;
+        self
+    }
+
+    /// Adds the rules of a stylesheet to the stylesheet of the documentation, after the
+    /// built-in rules, so that they can override them. May be given more than once.
+    pub fn extra_css<P: Into<PathBuf>>(mut self, stylesheet: P) -> RenderOptions {
+        self.extra_css.push(stylesheet.into());
+        self
+    }
+
+    /// Renders a Markdown file, such as the `README.md` of the crate, on the page of the crate
+    /// after the docs of the crate root. In a document with several crates, the README is
+    /// rendered on the page of the first one.
+    ///
+    /// Links to the paths of items in the README lead to their pages, like links in the docs.
+    pub fn readme<P: Into<PathBuf>>(mut self, readme: P) -> RenderOptions {
+        self.readme = Some(readme.into());
+        self
+    }
+
+    /// Sets whether the README replaces the docs of the crate root on the page of the crate,
+    /// instead of following them. Disabled by default.
+    pub fn readme_replaces_docs(mut self, readme_replaces_docs: bool) -> RenderOptions {
+        self.readme_replaces_docs = readme_replaces_docs;
+        self
+    }
+
+    /// Reads the source files of every crate that the document does not embed from a directory,
+    /// to render their pages. By default, they are read from the current directory.
+    ///
+    /// Files whose paths in the document are absolute or lead out of the directory are skipped.
+    pub fn source_dir<P: Into<PathBuf>>(mut self, source_dir: P) -> RenderOptions {
+        self.source_dir = Some(source_dir.into());
+        self
+    }
+
+    /// Sets the URL of the logo of the crate, which is shown in the header of every page and is
+    /// the image of the cards that chat apps and social networks show for links to its pages. A
+    /// relative URL is resolved against the base URL, unless it is the path of a file, which is
+    /// copied into the doc root.
+    ///
+    /// The logo takes precedence over the `html_logo_url` of the crate.
+    pub fn logo_url<S: Into<String>>(mut self, logo_url: S) -> RenderOptions {
+        self.logo_url = Some(logo_url.into());
+        self
+    }
+
+    /// Sets the URL of the favicon of every page, which is resolved like the URL of the logo.
+    ///
+    /// The favicon takes precedence over the `html_favicon_url` of the crate.
+    pub fn favicon_url<S: Into<String>>(mut self, favicon_url: S) -> RenderOptions {
+        self.favicon_url = Some(favicon_url.into());
+        self
+    }
+
+    /// Adds the snippet of an analytics service to the `<head>` of every HTML page, after its
+    /// stylesheets and scripts.
+    pub fn analytics(mut self, analytics: Analytics) -> RenderOptions {
+        self.analytics = Some(analytics);
+        self
+    }
+
+    /// Adds the HTML in a file, such as the snippet of an analytics service that `analytics` does
+    /// not know, to the `<head>` of every HTML page, after the snippet of `analytics` if both are
+    /// set.
+    pub fn analytics_snippet<P: Into<PathBuf>>(mut self, snippet: P) -> RenderOptions {
+        self.analytics_snippet = Some(snippet.into());
+        self
+    }
+
+    /// Only documents the items that match a path glob, everything under them, and the modules
+    /// that lead to them. May be given more than once.
+    ///
+    /// In a glob, `*` matches any part of a single path segment. For example, including
+    /// `my_crate::api::*` documents everything in the `api` module, along with the crate root
+    /// and the `api` module itself. Crates are always documented.
+    pub fn include<S: Into<String>>(mut self, glob: S) -> RenderOptions {
+        self.included.push(glob.into());
+        self
+    }
+
+    /// Excludes the items that match a path glob, and every item under their paths, from the
+    /// documentation.
+    ///
+    /// For example, excluding `my_crate::internal` omits the module and everything in it, and
+    /// excluding `my_crate::*::tests` omits the `tests` module of every top-level module. Links
+    /// to excluded items are removed from the remaining pages. Exclusions take precedence over
+    /// inclusions.
+    pub fn exclude<S: Into<String>>(mut self, glob: S) -> RenderOptions {
+        self.excluded.push(glob.into());
+        self
+    }
+
+    /// Sets whether items that are not public are documented, like rustdoc's
+    /// `--document-private-items`. Disabled by default.
+    ///
+    /// Items are private if their `visibility` attribute is anything other than `public`.
+    /// Everything under a private item is left out along with it.
+    pub fn document_private_items(mut self, document_private_items: bool) -> RenderOptions {
+        self.document_private_items = document_private_items;
+        self
+    }
+
+    /// Sets whether items hidden with `#[doc(hidden)]`, which have a `hidden` attribute, are
+    /// documented, like rustdoc's `--document-hidden-items`. Disabled by default.
+    pub fn document_hidden_items(mut self, document_hidden_items: bool) -> RenderOptions {
+        self.document_hidden_items = document_hidden_items;
+        self
+    }
+
+    /// Sets whether pages are rendered on more than one thread. Enabled by default.
+    ///
+    /// Rendering on one thread is the same as setting the number of jobs to 1.
+    pub fn parallel(mut self, parallel: bool) -> RenderOptions {
+        self.parallel = parallel;
+        self
+    }
+
+    /// Sets the number of threads that render pages. Defaults to the number of cores.
+    ///
+    /// One thread renders the pages that cover the whole crate, such as the source pages, while
+    /// the others split the item pages between them. Rendering with a single job renders every
+    /// page on the calling thread.
+    pub fn jobs(mut self, jobs: usize) -> RenderOptions {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Sets whether every problem found when the document is validated fails the render, such as
+    /// relationships to resources that the document does not include, items of unknown types and
+    /// broken links in the docs. Otherwise, the affected parts of the documentation are left out
+    /// and the problems are recorded as warnings.
+    pub fn strict(mut self, strict: bool) -> RenderOptions {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets whether the document is checked and every page rendered without writing any files.
+    ///
+    /// The report of a dry run lists the files that would have been written.
+    pub fn dry_run(mut self, dry_run: bool) -> RenderOptions {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets whether the render continues when the page of an item cannot be rendered, such as
+    /// because of an error in a custom template. Disabled by default.
+    ///
+    /// The items whose pages could not be rendered are listed in the `failures` of the report.
+    pub fn keep_going(mut self, keep_going: bool) -> RenderOptions {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// Sets whether a placeholder page with the error is written in place of each page that
+    /// could not be rendered, so that links to it still lead somewhere. Only applies if the
+    /// render keeps going after errors.
+    pub fn error_pages(mut self, error_pages: bool) -> RenderOptions {
+        self.error_pages = error_pages;
+        self
+    }
+
+    /// Caps the number of output files that are open at the same time. Writers wait for an open
+    /// file to be closed once the cap is reached.
+    ///
+    /// By default, the number of open files is not limited.
+    pub fn max_open_files(mut self, max: usize) -> RenderOptions {
+        self.max_open_files = Some(max);
+        self
+    }
+
+    /// Hands the files of a render to a number of threads that write them, so that pages go on
+    /// rendering while the previous ones are written. This speeds up renders to network and
+    /// spinning-disk filesystems. By default, pages are streamed into their files by the threads
+    /// that render them.
+    ///
+    /// Pages are rendered into memory to be handed to the writers, but only a few of them wait at
+    /// once for each writer, and no more than the memory limit allows, so renders wait for the
+    /// writers when they fall behind. Low memory mode streams every page as if there were no
+    /// writers.
+    pub fn write_threads(mut self, threads: usize) -> RenderOptions {
+        self.write_threads = Some(threads).filter(|&threads| threads > 0);
+        self
+    }
+
+    /// Caps the memory held by rendered pages that wait for the write threads, in bytes.
+    /// Renderers wait for the writers once the cap is reached. A page that is larger than the cap
+    /// is still written, once no other page is waiting.
+    ///
+    /// Pages are only held in memory when there are write threads; otherwise each page is
+    /// streamed into its file as it is rendered. The document and its index are held in memory
+    /// for the whole render, and are not counted. By default, the memory is only limited by the
+    /// number of pages that may wait for each writer.
+    pub fn memory_limit(mut self, bytes: usize) -> RenderOptions {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Lowers peak memory use at the cost of wall-clock time, for environments with tight memory
+    /// limits.
+    ///
+    /// In low memory mode, every page is rendered on one thread whatever the number of jobs, and
+    /// the pages that cover the whole crate are rendered after the item pages instead of
+    /// alongside them. The write threads are not used, so each page is streamed into its file as
+    /// it is rendered, except when a `Renderer::after_item` hook needs the whole page. The typed
+    /// views of items are parsed whenever a page needs them instead of being kept for the whole
+    /// render, but the document and its index still are. Load the document with `load::from_path`
+    /// rather than `load::from_reader`, so that it is not read into memory before it is parsed.
+    pub fn low_memory(mut self, low_memory: bool) -> RenderOptions {
+        self.low_memory = low_memory;
+        self
+    }
+
+    /// Writes the fragments that would otherwise be repeated on every item page, which are the
+    /// settings panel, the sidebar and the search box, to shared files that pages load with
+    /// JavaScript.
+    ///
+    /// This shrinks the output of large crates, but the fragments cannot be loaded when the pages
+    /// are opened directly from the filesystem in most browsers, so it only suits hosted docs.
+    /// Pages whose fragments cannot be loaded are shown without them.
+    pub fn share_fragments(mut self, share_fragments: bool) -> RenderOptions {
+        self.share_fragments = share_fragments;
+        self
+    }
+
+    /// Renders the documentation into a directory under the doc root named after the version of
+    /// the crate, such as `1.2.0/`, and lists the version in `versions.json` at the doc root.
+    ///
+    /// The header of every page has a switcher between the versions listed, which stays on the
+    /// same page when it exists in the other version. Documents with several crates are rendered
+    /// under the version of the first one.
+    pub fn versioned(mut self, versioned: bool) -> RenderOptions {
+        self.versioned = versioned;
+        self
+    }
+
+    /// Renders the documentation under a version, such as `main`, instead of the version of the
+    /// crate. Implies `versioned`.
+    pub fn version<S: Into<String>>(mut self, version: S) -> RenderOptions {
+        self.versioned = true;
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Compares the public API of the document with that of an older document, such as the
+    /// document of the previous release, and lists the changes in `changes.html` at the doc root.
+    /// The page of the crate links to it.
+    ///
+    /// Versioned documentation also adds the changes of the version to `feed.xml`, an Atom feed
+    /// of the changes in every version, at the root of the versions.
+    pub fn changes_since(mut self, old: JsonApiDocument) -> RenderOptions {
+        self.changes_since = Some(old);
+        self
+    }
+
+    /// Renders the document of a dependency along with the documentation, under `deps/` at the
+    /// doc root, so that the documentation can be browsed offline. May be called more than once.
+    ///
+    /// Links to the paths of items of the dependency in the docs of the crate point to the pages
+    /// of the dependency.
+    pub fn dependency(mut self, document: JsonApiDocument) -> RenderOptions {
+        self.dependencies.push(document);
+        self
+    }
+
+    /// Sets a function that is called with the context of each item page before it is rendered,
+    /// and may change it.
+    ///
+    /// This is a shorthand for `Renderer::before_item`, and runs before the hooks added there.
+    pub fn context_transform(mut self, transform: fn(&Resource, &mut Value)) -> RenderOptions {
+        self.context_transform = Some(transform);
+        self
+    }
+
+    /// Returns whether an item is excluded from the documentation.
+    pub(crate) fn is_excluded(&self, id: &str) -> bool {
+        self.excluded.iter().any(|glob| is_under(glob, id))
+    }
+
+    /// Returns whether an item matches an inclusion, or is under one. Every item is included if
+    /// there are no inclusions.
+    pub(crate) fn is_included(&self, id: &str) -> bool {
+        self.included.is_empty() || self.included.iter().any(|glob| is_under(glob, id))
+    }
+}
+
+/// Returns whether a path, or the path of one of its parents, matches a glob.
+fn is_under(glob: &str, id: &str) -> bool {
+    id.match_indices("::")
+        .map(|(end, _)| &id[..end])
+        .chain(Some(id))
+        .any(|path| matches(glob.as_bytes(), path.as_bytes()))
+}
+
+/// Returns whether a path matches a glob, where `*` matches any characters but `:`.
+fn matches(glob: &[u8], path: &[u8]) -> bool {
+    match glob.split_first() {
+        Some((&b'*', rest)) => {
+            let segment = path.iter().position(|&c| c == b':').unwrap_or(path.len());
+            (0..segment + 1).any(|skip| matches(rest, &path[skip..]))
+        }
+        Some((&c, rest)) => path.first() == Some(&c) && matches(rest, &path[1..]),
+        None => path.is_empty(),
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RenderOptions;
+
+    #[test]
+    fn is_excluded() {
+        let options = RenderOptions::new().exclude("test_crate::internal");
+
+        assert!(options.is_excluded("test_crate::internal"));
+        assert!(options.is_excluded("test_crate::internal::Foo"));
+        assert!(!options.is_excluded("test_crate::internals"));
+        assert!(!options.is_excluded("test_crate"));
+    }
+
+    #[test]
+    fn globs() {
+        let options = RenderOptions::new().include("test_crate::api::*").exclude("*::tests");
+
+        assert!(options.is_included("test_crate::api::Foo"));
+        assert!(options.is_included("test_crate::api::Foo::bar"));
+        assert!(!options.is_included("test_crate::api"));
+        assert!(!options.is_included("test_crate::internal::Foo"));
+
+        assert!(options.is_excluded("test_crate::tests"));
+        assert!(options.is_excluded("other_crate::tests::it_works"));
+        assert!(!options.is_excluded("test_crate::api::tests"));
+    }
+}
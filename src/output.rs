@@ -0,0 +1,185 @@
+//! Destinations that rendered documentation can be written to.
+//!
+//! Renders write the documentation to the filesystem by default. Other destinations, such as an
+//! archive or an object store, can be written to by passing an `OutputBackend` to
+//! `Renderer::backend`.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A destination for the files of a render.
+///
+/// Files are written from more than one thread at a time, unless the render is configured not to
+/// be parallel.
+pub trait OutputBackend: Send + Sync {
+    /// Writes a file, replacing it if it was already written.
+    ///
+    /// The path is relative to the doc root, such as `my_crate/struct.Foo.html`.
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+
+    /// Creates a file that is written as a stream, replacing it if it was already written, so
+    /// that pages do not have to be held in memory in full.
+    ///
+    /// The file is complete once the writer is flushed. By default, the contents are collected in
+    /// memory and passed to `write` when the writer is flushed.
+    fn create<'a>(&'a self, path: &Path) -> io::Result<Box<dyn Write + 'a>> {
+        Ok(Box::new(Buffered { backend: self, path: path.to_owned(), contents: vec![] }))
+    }
+}
+
+/// A file of a backend that cannot stream files, which is written in full when it is flushed.
+struct Buffered<'a, B: ?Sized + 'a> {
+    backend: &'a B,
+    path: PathBuf,
+    contents: Vec<u8>,
+}
+
+impl<'a, B: OutputBackend + ?Sized> Write for Buffered<'a, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.contents.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.backend.write(&self.path, &self.contents)
+    }
+}
+
+/// Writes files to a directory, creating it and any subdirectories as needed.
+#[derive(Debug, Clone)]
+pub struct Filesystem {
+    root: PathBuf,
+}
+
+impl Filesystem {
+    /// Creates a backend that writes files under a directory.
+    pub fn new<P: Into<PathBuf>>(root: P) -> Filesystem {
+        Filesystem { root: root.into() }
+    }
+}
+
+impl OutputBackend for Filesystem {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let path = self.root.join(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        File::create(path)?.write_all(contents)
+    }
+
+    fn create<'a>(&'a self, path: &Path) -> io::Result<Box<dyn Write + 'a>> {
+        let path = self.root.join(path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Ok(Box::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+/// Collects the files of a render in memory, for tests of the rendered documentation.
+///
+/// Clones of the backend share the same files, so a clone can be passed to `Renderer::backend`
+/// and the files read from the original once the render is done.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBackend {
+    files: Arc<Mutex<BTreeMap<PathBuf, Vec<u8>>>>,
+}
+
+impl MemoryBackend {
+    /// Creates a backend without any files.
+    pub fn new() -> MemoryBackend {
+        MemoryBackend::default()
+    }
+
+    /// Returns the contents of a file, by its path relative to the doc root.
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<Vec<u8>> {
+        self.files.lock().unwrap().get(path.as_ref()).cloned()
+    }
+
+    /// Returns every file written so far, by its path relative to the doc root.
+    pub fn files(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        self.files.lock().unwrap().clone()
+    }
+}
+
+impl OutputBackend for MemoryBackend {
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        self.files.lock().unwrap().insert(path.to_owned(), contents.to_vec());
+        Ok(())
+    }
+
+    fn create<'a>(&'a self, path: &Path) -> io::Result<Box<dyn Write + 'a>> {
+        self.files.lock().unwrap().insert(path.to_owned(), vec![]);
+        Ok(Box::new(MemoryFile { files: &self.files, path: path.to_owned() }))
+    }
+}
+
+/// A file of a `MemoryBackend` that is being written, whose contents are appended to the file in
+/// the backend as they are written.
+struct MemoryFile<'a> {
+    files: &'a Mutex<BTreeMap<PathBuf, Vec<u8>>>,
+    path: PathBuf,
+}
+
+impl<'a> Write for MemoryFile<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut files = self.files.lock().unwrap();
+        files.entry(self.path.clone()).or_default().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::prelude::*;
+    use std::path::Path;
+
+    use super::{Filesystem, MemoryBackend, OutputBackend};
+
+    #[test]
+    fn filesystem() {
+        let root = env::temp_dir().join("rustdoc-static-filesystem-backend");
+        let _ = fs::remove_dir_all(&root);
+
+        Filesystem::new(&root).write(Path::new("demo/index.html"), b"<html>").unwrap();
+        assert_eq!(fs::read_to_string(root.join("demo/index.html")).unwrap(), "<html>");
+
+        let filesystem = Filesystem::new(&root);
+        let mut file = filesystem.create(Path::new("demo/struct.Foo.html")).unwrap();
+        file.write_all(b"<html>").unwrap();
+        file.flush().unwrap();
+        drop(file);
+        assert_eq!(fs::read_to_string(root.join("demo/struct.Foo.html")).unwrap(), "<html>");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn memory() {
+        let backend = MemoryBackend::new();
+        backend.clone().write(Path::new("demo/index.html"), b"<html>").unwrap();
+
+        assert_eq!(backend.get("demo/index.html").unwrap(), b"<html>");
+        assert_eq!(backend.files().len(), 1);
+
+        let mut file = backend.create(Path::new("demo/struct.Foo.html")).unwrap();
+        file.write_all(b"<html>").unwrap();
+        file.write_all(b"</html>").unwrap();
+        file.flush().unwrap();
+        assert_eq!(backend.get("demo/struct.Foo.html").unwrap(), b"<html></html>");
+    }
+}
@@ -0,0 +1,136 @@
+//! A pool of threads that write the files of a render, so that pages go on rendering while the
+//! previous pages are written to a slow filesystem, such as a network share or a spinning disk.
+//!
+//! Renderers queue each file in full. The queue holds a bounded number of files, and as many
+//! bytes as the memory limit allows, so renderers wait for the writers instead of holding every
+//! page in memory when the writers fall behind.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{Scope, ScopedJoinHandle};
+
+use limit::{FileLimit, MemoryLimit, MemoryPermit};
+use output::OutputBackend;
+
+/// The number of files that may wait to be written for each writer of a pool.
+const QUEUED_PER_WRITER: usize = 16;
+
+/// A file waiting to be written, by its path relative to the doc root, with the permit for the
+/// memory that it holds.
+type Job<'env> = (PathBuf, Vec<u8>, MemoryPermit<'env>);
+
+/// The queue of files of a pool, which renderers write to.
+pub(crate) struct WriteQueue<'env> {
+    sender: SyncSender<Job<'env>>,
+    memory: &'env MemoryLimit,
+}
+
+impl<'env> WriteQueue<'env> {
+    /// Queues a file to be written, waiting while the queue is full or holds as much memory as
+    /// the limit allows. Fails if every writer has stopped because a file could not be written.
+    pub fn write(&self, path: PathBuf, contents: Vec<u8>) -> io::Result<()> {
+        let permit = self.memory.acquire(contents.len());
+        self.sender
+            .send((path, contents, permit))
+            .map_err(|_| io::Error::other("every writer stopped after failing to write a file"))
+    }
+}
+
+/// Threads that write the files of a queue through a backend.
+pub(crate) struct WritePool<'scope, 'env> {
+    queue: WriteQueue<'env>,
+    writers: Vec<ScopedJoinHandle<'scope, io::Result<()>>>,
+}
+
+impl<'scope, 'env> WritePool<'scope, 'env> {
+    /// Starts a number of writers on a scope. Each file counts towards the limit of open files
+    /// while it is being written, and towards the memory limit until it has been written.
+    pub fn start(
+        scope: &'scope Scope<'scope, 'env>,
+        backend: &'env dyn OutputBackend,
+        open_files: &'env FileLimit,
+        memory: &'env MemoryLimit,
+        writers: usize,
+    ) -> WritePool<'scope, 'env> {
+        let writers = writers.max(1);
+        let (sender, receiver) = mpsc::sync_channel(writers * QUEUED_PER_WRITER);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let writers = (0..writers)
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                scope.spawn(move || write_queued(&receiver, backend, open_files))
+            })
+            .collect();
+
+        WritePool { queue: WriteQueue { sender, memory }, writers }
+    }
+
+    /// Returns the queue that files are written to.
+    pub fn queue(&self) -> &WriteQueue<'env> {
+        &self.queue
+    }
+
+    /// Waits for every queued file to be written, returning the first error of the writers.
+    pub fn finish(self) -> io::Result<()> {
+        drop(self.queue);
+
+        let mut result = Ok(());
+        for writer in self.writers {
+            let written = writer.join().unwrap_or_else(|e| ::std::panic::resume_unwind(e));
+            result = result.and(written);
+        }
+        result
+    }
+}
+
+/// Writes the files of a queue until it is closed, or a file cannot be written.
+fn write_queued(
+    receiver: &Mutex<Receiver<Job<'_>>>,
+    backend: &dyn OutputBackend,
+    open_files: &FileLimit,
+) -> io::Result<()> {
+    loop {
+        // The lock is released before the file is written, so that the writers take turns.
+        let job = receiver.lock().unwrap().recv();
+        let (path, contents, _memory) = match job {
+            Ok(job) => job,
+            Err(_) => return Ok(()),
+        };
+
+        let _permit = open_files.acquire();
+        backend.write(&path, &contents)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::thread;
+
+    use limit::{FileLimit, MemoryLimit};
+    use output::MemoryBackend;
+
+    use super::WritePool;
+
+    #[test]
+    fn write() {
+        let backend = MemoryBackend::new();
+        let open_files = FileLimit::new(Some(2));
+        let memory = MemoryLimit::new(Some(64));
+
+        thread::scope(|scope| {
+            let pool = WritePool::start(scope, &backend, &open_files, &memory, 3);
+            for i in 0..100 {
+                let path = PathBuf::from(format!("{}.html", i));
+                pool.queue().write(path, format!("page {}", i).into_bytes()).unwrap();
+            }
+            pool.finish().unwrap();
+        });
+
+        assert_eq!(backend.files().len(), 100);
+        assert_eq!(backend.get("42.html").unwrap(), b"page 42");
+    }
+}
@@ -0,0 +1,93 @@
+//! Precompressed variants of the rendered documentation, for static hosts that serve them directly.
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use brotli;
+use brotli::enc::BrotliEncoderParams;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use files;
+
+/// Extensions of the files that are worth compressing.
+const EXTENSIONS: &[&str] = &["html", "css", "js", "json", "xml", "txt"];
+
+/// Writes a `.gz` and a `.br` file alongside every compressible file under the doc root.
+pub fn precompress<P: AsRef<Path>>(doc_root: P) -> io::Result<()> {
+    let doc_root = doc_root.as_ref();
+
+    for file in files(doc_root)? {
+        let compressible = file
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| EXTENSIONS.contains(&extension));
+
+        if !compressible {
+            continue;
+        }
+
+        let path = doc_root.join(&file);
+        let mut contents = vec![];
+        File::open(&path)?.read_to_end(&mut contents)?;
+
+        debug!("precompressing `{}`", path.display());
+
+        let gz = BufWriter::new(File::create(with_suffix(&path, "gz"))?);
+        let mut gz = GzEncoder::new(gz, Compression::best());
+        gz.write_all(&contents)?;
+        gz.finish()?.flush()?;
+
+        let mut br = BufWriter::new(File::create(with_suffix(&path, "br"))?);
+        brotli::BrotliCompress(&mut &contents[..], &mut br, &BrotliEncoderParams::default())?;
+        br.flush()?;
+    }
+
+    Ok(())
+}
+
+/// Appends an extension to a path, such that `index.html` becomes `index.html.gz`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(".");
+    path.push(suffix);
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::prelude::*;
+
+    use brotli;
+    use flate2::read::GzDecoder;
+
+    #[test]
+    fn precompress() {
+        let root = env::temp_dir().join("rustdoc-static-precompress");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("demo")).unwrap();
+        let page = "<!DOCTYPE html>\n<p>Docs.</p>\n".repeat(100);
+        fs::write(root.join("demo/index.html"), &page).unwrap();
+        fs::write(root.join("logo.png"), b"\x89PNG").unwrap();
+
+        super::precompress(&root).unwrap();
+
+        let mut gz = String::new();
+        let file = File::open(root.join("demo/index.html.gz")).unwrap();
+        GzDecoder::new(file).read_to_string(&mut gz).unwrap();
+        assert_eq!(gz, page);
+
+        let mut br = vec![];
+        let mut file = File::open(root.join("demo/index.html.br")).unwrap();
+        brotli::BrotliDecompress(&mut file, &mut br).unwrap();
+        assert_eq!(br, page.as_bytes());
+
+        assert!(!root.join("logo.png.gz").exists());
+        assert!(!root.join("logo.png.br").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,178 @@
+//! A summary of the outcome of a render, so that callers can act on it without parsing the log.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+use validate::Diagnostic;
+
+/// What a render wrote, and the problems it worked around.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RenderReport {
+    /// The files that were written, including static assets.
+    pub files_written: Vec<PathBuf>,
+
+    /// The hashes of the contents of the files that were written, by path, if the render records
+    /// them with `RenderOptions::content_hashes`.
+    pub content_hashes: BTreeMap<PathBuf, String>,
+
+    /// The IDs of the items whose pages were not written because they were unchanged since the
+    /// previous render.
+    pub skipped: Vec<String>,
+
+    /// Relationships to resources that are not included in the document. The missing resources
+    /// are omitted from the pages.
+    pub unresolved_links: Vec<UnresolvedLink>,
+
+    /// Problems that did not stop the render, such as source files that could not be read.
+    pub warnings: Vec<String>,
+
+    /// The problems found when the document was validated that did not stop the render. Each of
+    /// them is also recorded in `unresolved_links` or `warnings`.
+    pub diagnostics: Vec<Diagnostic>,
+
+    /// The items whose pages could not be rendered, when the render continues after errors.
+    pub failures: Vec<RenderFailure>,
+
+    /// The version that the documentation was rendered under, which is also the directory under
+    /// the doc root that it was written to, when the documentation is versioned.
+    pub version: Option<String>,
+}
+
+/// A relationship to a resource that is not included in the document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnresolvedLink {
+    /// The ID of the resource that the relationship belongs to.
+    pub from: String,
+
+    /// The name of the relationship, such as `functions`.
+    pub relationship: String,
+
+    /// The ID of the missing resource.
+    pub to: String,
+}
+
+/// An item whose page could not be rendered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderFailure {
+    /// The ID of the item.
+    pub id: String,
+
+    /// Why the page could not be rendered.
+    pub error: String,
+}
+
+impl RenderReport {
+    /// Logs a warning and records it in the report.
+    pub(crate) fn warn(&mut self, warning: String) {
+        warn!("{}", warning);
+        self.warnings.push(warning);
+    }
+
+    /// Logs an item whose page could not be rendered and records it in the report.
+    pub(crate) fn fail(&mut self, id: &str, error: String) {
+        error!("could not render `{}`: {}", id, error);
+        self.failures.push(RenderFailure { id: id.to_owned(), error });
+    }
+
+    /// Records a problem found when the document was validated.
+    pub(crate) fn diagnose(&mut self, diagnostic: Diagnostic) {
+        match diagnostic {
+            Diagnostic::UnresolvedLink(ref link) => {
+                warn!("{}", diagnostic);
+                self.unresolved_links.push(link.clone());
+            }
+            ref diagnostic => self.warn(diagnostic.to_string()),
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Adds the outcome of another part of the render to the report.
+    pub(crate) fn append(&mut self, mut other: RenderReport) {
+        self.files_written.append(&mut other.files_written);
+        self.content_hashes.append(&mut other.content_hashes);
+        self.skipped.append(&mut other.skipped);
+        self.unresolved_links.append(&mut other.unresolved_links);
+        self.warnings.append(&mut other.warnings);
+        self.diagnostics.append(&mut other.diagnostics);
+        self.failures.append(&mut other.failures);
+    }
+
+    /// Returns a summary of the problems that the render worked around, grouped by kind, or
+    /// `None` if there were none.
+    ///
+    /// Renders log the summary once they are done, so that problems are not lost among the
+    /// messages of a large render.
+    pub fn summary(&self) -> Option<String> {
+        let failures = self
+            .failures
+            .iter()
+            .map(|failure| ("failed pages", format!("`{}`: {}", failure.id, failure.error)));
+        let diagnostics = self.diagnostics.iter().map(|d| (d.kind(), d.to_string()));
+        let diagnosed = diagnostics.clone().map(|(_, message)| message).collect::<HashSet<_>>();
+        let others = self
+            .warnings
+            .iter()
+            .filter(|&warning| !diagnosed.contains(warning))
+            .map(|warning| ("other problems", warning.clone()));
+
+        let mut groups: Vec<(&str, Vec<String>)> = vec![];
+        for (kind, message) in failures.chain(diagnostics).chain(others) {
+            match groups.iter_mut().find(|group| group.0 == kind) {
+                Some(group) => group.1.push(message),
+                None => groups.push((kind, vec![message])),
+            }
+        }
+
+        if groups.is_empty() {
+            return None;
+        }
+
+        let total = groups.iter().map(|group| group.1.len()).sum::<usize>();
+        let plural = if total == 1 { "" } else { "s" };
+        let mut summary = format!("{} problem{} found:\n", total, plural);
+        for (kind, messages) in groups {
+            summary.push_str(&format!("  {} ({}):\n", kind, messages.len()));
+            for message in messages {
+                summary.push_str(&format!("    {}\n", message));
+            }
+        }
+        Some(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RenderReport, UnresolvedLink};
+    use validate::Diagnostic;
+
+    #[test]
+    fn summary() {
+        let mut report = RenderReport::default();
+        assert_eq!(report.summary(), None);
+
+        for to in &["test_crate::f", "test_crate::g"] {
+            report.diagnose(Diagnostic::UnresolvedLink(UnresolvedLink {
+                from: String::from("test_crate"),
+                relationship: String::from("functions"),
+                to: String::from(*to),
+            }));
+        }
+        report.diagnose(Diagnostic::DuplicateId { id: String::from("test_crate::Foo") });
+        report.warn(String::from("could not read source file `src/lib.rs`"));
+
+        assert_eq!(
+            report.summary().unwrap(),
+            "4 problems found:\n  \
+             unresolved links (2):\n    \
+             relationship `functions` of `test_crate` refers to `test_crate::f`, which is not \
+             included\n    \
+             relationship `functions` of `test_crate` refers to `test_crate::g`, which is not \
+             included\n  \
+             duplicate IDs (1):\n    \
+             more than one resource is `test_crate::Foo`\n  \
+             other problems (1):\n    \
+             could not read source file `src/lib.rs`\n"
+        );
+    }
+}
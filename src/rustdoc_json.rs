@@ -0,0 +1,298 @@
+//! An adapter from the output of `rustdoc --output-format json` to the JSON-API documents
+//! generated by the rustdoc backend.
+//!
+//! Modules, structs and their fields, enums, traits, functions, type aliases and constants are
+//! converted, starting from the crate root. Items that are only reachable through impls, such as
+//! methods, are not converted yet.
+
+use jsonapi::api::JsonApiDocument;
+use serde_json::{Map, Value};
+
+use errors::*;
+
+/// Returns whether a JSON document was produced by `rustdoc --output-format json`.
+pub fn is_rustdoc_json(json: &Value) -> bool {
+    json.get("format_version").is_some() && json.get("index").is_some()
+}
+
+/// Converts the output of `rustdoc --output-format json` to a JSON-API document.
+pub fn convert(json: &Value) -> Result<JsonApiDocument> {
+    let index = json
+        .get("index")
+        .and_then(|index| index.as_object())
+        .ok_or_else(|| invalid("the document has no index"))?;
+    let root = json.get("root").and_then(key).ok_or_else(|| invalid("the document has no root"))?;
+    let krate = index.get(&root).ok_or_else(|| invalid("the crate root is not in the index"))?;
+    let name = krate
+        .get("name")
+        .and_then(|name| name.as_str())
+        .ok_or_else(|| invalid("the crate root has no name"))?;
+
+    let mut converter = Converter { index, included: vec![] };
+    let mut data = converter.resource("crate", name, krate);
+    if let Some(version) = json.get("crate_version") {
+        data["attributes"]["version"] = version.clone();
+    }
+    for &name in &["html_logo_url", "html_favicon_url"] {
+        if let Some(url) = doc_attribute(krate, name) {
+            data["attributes"][name] = Value::String(url);
+        }
+    }
+    data["relationships"] = converter.children(krate, name);
+
+    let document = json!({ "data": data, "included": converter.included });
+
+    // The document is parsed by `jsonapi`, which can only deserialize documents from strings.
+    JsonApiDocument::from_str(&document.to_string()).chain_err(|| ErrorKind::Parse)
+}
+
+/// Collects the resources of the items reachable from the crate root.
+struct Converter<'a> {
+    index: &'a Map<String, Value>,
+    included: Vec<Value>,
+}
+
+impl<'a> Converter<'a> {
+    /// Converts the children of an item to resources, returning the relationships of the item.
+    fn children(&mut self, item: &Value, path: &str) -> Value {
+        let mut relationships = Map::new();
+
+        for id in child_ids(item) {
+            let child = match self.index.get(&id) {
+                Some(child) => child,
+                None => continue,
+            };
+            let (ty, relationship) = match resource_type(child) {
+                Some(ty) => ty,
+                None => continue,
+            };
+            let name = match child.get("name").and_then(|name| name.as_str()) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            // Resources are included before their children, in the order of the crate.
+            let id = format!("{}::{}", path, name);
+            let position = self.included.len();
+            let resource = self.resource(ty, &id, child);
+            self.included.push(resource);
+            self.included[position]["relationships"] = self.children(child, &id);
+
+            let identifiers = relationships
+                .entry(String::from(relationship))
+                .or_insert_with(|| json!({ "data": [] }));
+            identifiers["data"].as_array_mut().unwrap().push(json!({ "type": ty, "id": id }));
+        }
+
+        Value::Object(relationships)
+    }
+
+    /// Creates the resource of an item, without any relationships.
+    fn resource(&self, ty: &str, id: &str, item: &Value) -> Value {
+        let mut attributes = Map::new();
+
+        if let Some(docs) = item.get("docs").filter(|docs| docs.is_string()) {
+            attributes.insert(String::from("docs"), docs.clone());
+        }
+
+        if let Some(span) = item.get("span") {
+            attributes.insert(
+                String::from("source"),
+                json!({ "file": span["filename"], "line": span["begin"][0] }),
+            );
+        }
+
+        if let Some(deprecation) = item.get("deprecation").filter(|d| d.is_object()) {
+            attributes.insert(
+                String::from("deprecated"),
+                json!({ "since": deprecation["since"], "note": deprecation["note"] }),
+            );
+        }
+
+        // Items with the `default` visibility, such as trait methods, are as visible as their
+        // parent, so their visibility is left out.
+        match item.get("visibility") {
+            Some(visibility) if visibility == "default" => (),
+            Some(visibility) if visibility.is_string() => {
+                attributes.insert(String::from("visibility"), visibility.clone());
+            }
+            Some(visibility) if visibility.is_object() => {
+                attributes.insert(String::from("visibility"), json!("restricted"));
+            }
+            _ => (),
+        }
+
+        if is_hidden(item) {
+            attributes.insert(String::from("hidden"), json!(true));
+        }
+
+        json!({ "type": ty, "id": id, "attributes": attributes })
+    }
+}
+
+/// Returns whether an item has a `#[doc(hidden)]` attribute.
+///
+/// Older versions of the format store attributes as strings, while newer versions store them as
+/// objects.
+fn is_hidden(item: &Value) -> bool {
+    let attrs = match item.get("attrs").and_then(|attrs| attrs.as_array()) {
+        Some(attrs) => attrs,
+        None => return false,
+    };
+
+    attrs
+        .iter()
+        .filter_map(|attr| attr.as_str().or_else(|| attr.get("other")?.as_str()))
+        .any(|attr| attr.replace(' ', "") == "#[doc(hidden)]")
+}
+
+/// Returns the value of a `#[doc(name = "value")]` attribute of an item, such as the
+/// `html_logo_url` of a crate root.
+fn doc_attribute(item: &Value, name: &str) -> Option<String> {
+    let attrs = item.get("attrs")?.as_array()?;
+
+    attrs
+        .iter()
+        .filter_map(|attr| attr.as_str().or_else(|| attr.get("other")?.as_str()))
+        .filter(|attr| attr.replace(' ', "").contains("doc("))
+        .find_map(|attr| {
+            let start = attr.find(name)? + name.len();
+            let value = attr[start..].trim_start().strip_prefix('=')?.trim_start();
+            let value = value.strip_prefix('"')?;
+            Some(String::from(&value[..value.find('"')?]))
+        })
+}
+
+/// Returns the kind of an item, such as `module` or `struct`.
+///
+/// Older versions of the format store the kind in a `kind` field, while newer versions store the
+/// details of the item in an `inner` object whose only key is the kind.
+fn kind(item: &Value) -> Option<&str> {
+    match item.get("kind").and_then(|kind| kind.as_str()) {
+        Some(kind) => Some(kind),
+        None => item.get("inner")?.as_object()?.keys().next().map(|kind| kind.as_str()),
+    }
+}
+
+/// Returns the details of an item that depend on its kind.
+fn inner(item: &Value) -> Option<&Value> {
+    let inner = item.get("inner")?;
+
+    match item.get("kind") {
+        Some(_) => Some(inner),
+        None => inner.get(kind(item)?),
+    }
+}
+
+/// Returns the type of the resource and the name of the relationship for an item, if the item
+/// is converted.
+fn resource_type(item: &Value) -> Option<(&'static str, &'static str)> {
+    match kind(item)? {
+        "module" => Some(("module", "modules")),
+        "struct" => Some(("struct", "structs")),
+        "struct_field" => Some(("field", "fields")),
+        "enum" => Some(("enum", "enums")),
+        "trait" => Some(("trait", "traits")),
+        "function" => Some(("function", "functions")),
+        "type_alias" | "typedef" => Some(("type", "types")),
+        "constant" => Some(("const", "consts")),
+        _ => None,
+    }
+}
+
+/// Returns the IDs of the items contained in an item, such as the items of a module or the
+/// fields of a struct.
+fn child_ids(item: &Value) -> Vec<String> {
+    let inner = match inner(item) {
+        Some(inner) => inner,
+        None => return vec![],
+    };
+
+    let children = match kind(item) {
+        Some("module") => inner.get("items"),
+        Some("struct") => inner
+            .get("fields")
+            .or_else(|| inner.get("kind").and_then(|kind| kind.get("plain")?.get("fields"))),
+        _ => None,
+    };
+
+    children
+        .and_then(|children| children.as_array())
+        .map(|children| children.iter().filter_map(key).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the key of an ID in the index. IDs are strings in older versions of the format and
+/// integers in newer versions.
+fn key(id: &Value) -> Option<String> {
+    match *id {
+        Value::String(ref id) => Some(id.clone()),
+        Value::Number(ref id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+fn invalid(reason: &str) -> Error {
+    ErrorKind::InvalidDocument(String::from(reason)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use index::Index;
+    use resources;
+    use source::location_for_resource;
+
+    #[test]
+    fn convert() {
+        let json = json!({
+            "format_version": 39,
+            "root": 0,
+            "crate_version": "1.0.0",
+            "index": {
+                "0": {
+                    "name": "test_crate",
+                    "docs": "Crate docs.",
+                    "attrs": [
+                        "#![doc(html_logo_url = \"logo.svg\", html_favicon_url=\"favicon.ico\")]"
+                    ],
+                    "inner": { "module": { "is_crate": true, "items": [1, 2] } }
+                },
+                "1": {
+                    "name": "Foo",
+                    "docs": "A struct.",
+                    "span": { "filename": "src/lib.rs", "begin": [3, 0], "end": [5, 1] },
+                    "inner": { "struct": { "kind": { "plain": { "fields": [3] } }, "impls": [] } }
+                },
+                "2": { "name": "reexport", "inner": { "use": { "source": "std::mem" } } },
+                "3": {
+                    "name": "bar",
+                    "visibility": "crate",
+                    "attrs": [{ "other": "#[doc(hidden)]" }],
+                    "inner": { "struct_field": { "primitive": "u32" } }
+                }
+            }
+        });
+        assert!(super::is_rustdoc_json(&json));
+
+        let document = super::convert(&json).unwrap();
+        let ids = resources(&document)
+            .map(|resource| format!("{} {}", resource._type, resource.id))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ids,
+            ["crate test_crate", "struct test_crate::Foo", "field test_crate::Foo::bar"]
+        );
+
+        let krate = resources(&document).next().unwrap();
+        assert_eq!(krate.attributes["html_logo_url"], "logo.svg");
+        assert_eq!(krate.attributes["html_favicon_url"], "favicon.ico");
+
+        let strukt = resources(&document).nth(1).unwrap();
+        let index = Index::parse(&document).unwrap();
+        assert_eq!(location_for_resource(&index, strukt).unwrap().line, Some(3));
+
+        let field = resources(&document).nth(2).unwrap();
+        assert_eq!(field.attributes["visibility"], "crate");
+        assert_eq!(field.attributes["hidden"].as_bool(), Some(true));
+    }
+}
@@ -0,0 +1,407 @@
+//! A minimal HTTP server for previewing rendered documentation on localhost.
+//!
+//! Only `GET` and `HEAD` requests for files under the doc root are supported. The server is meant
+//! for previews, not for hosting documentation.
+
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::io::{self, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use handlebars::html_escape;
+
+/// The path of the event stream that tells pages to reload.
+const RELOAD_PATH: &str = "/.rustdoc-static/reload";
+
+/// The path of the script that listens to `RELOAD_PATH`.
+const RELOAD_SCRIPT_PATH: &str = "/.rustdoc-static/reload.js";
+
+/// The script that listens to `RELOAD_PATH`, which is served from a file rather than added inline
+/// so that pages with a Content Security Policy still reload.
+const RELOAD_JS: &str =
+    "new EventSource(\"/.rustdoc-static/reload\").onmessage = function() { location.reload(); };\n";
+
+/// The element added to the pages served with live reload, which loads `RELOAD_JS`.
+const RELOAD_SCRIPT: &str = "<script src=\"/.rustdoc-static/reload.js\"></script>";
+
+/// Tells the pages open in browsers to reload, such as after the documentation is rendered again.
+///
+/// Clones share the same pages, so a clone can be passed to `serve_with_reload` and the original
+/// used to reload the pages.
+#[derive(Debug, Clone, Default)]
+pub struct LiveReload {
+    /// The number of reloads so far, which the streams of the pages wait on.
+    generation: Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl LiveReload {
+    /// Creates a handle for reloading pages.
+    pub fn new() -> LiveReload {
+        LiveReload::default()
+    }
+
+    /// Reloads every page served with this handle that is open in a browser.
+    pub fn reload(&self) {
+        let (ref generation, ref changed) = *self.generation;
+        *generation.lock().unwrap() += 1;
+        changed.notify_all();
+    }
+
+    /// Returns the number of reloads so far.
+    fn generation(&self) -> u64 {
+        *self.generation.0.lock().unwrap()
+    }
+
+    /// Blocks until there has been a reload since the given generation.
+    fn wait(&self, since: u64) {
+        let (ref generation, ref changed) = *self.generation;
+        let mut current = generation.lock().unwrap();
+        while *current == since {
+            current = changed.wait(current).unwrap();
+        }
+    }
+}
+
+/// Serves the files under a doc root to the connections accepted by a listener, until accepting a
+/// connection fails.
+///
+/// Each connection is handled on its own thread. Requests for directories are answered with the
+/// `index.html` in the directory, or a list of its subdirectories if it has none.
+pub fn serve<P: Into<PathBuf>>(doc_root: P, listener: TcpListener) -> io::Result<()> {
+    run(doc_root.into(), listener, None)
+}
+
+/// Serves the files under a doc root like `serve`, adding a script to each HTML page that reloads
+/// the page when `reload` is called on the handle.
+pub fn serve_with_reload<P>(
+    doc_root: P,
+    listener: TcpListener,
+    reload: LiveReload,
+) -> io::Result<()>
+where
+    P: Into<PathBuf>,
+{
+    run(doc_root.into(), listener, Some(reload))
+}
+
+fn run(doc_root: PathBuf, listener: TcpListener, reload: Option<LiveReload>) -> io::Result<()> {
+    let doc_root = Arc::new(doc_root);
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let doc_root = Arc::clone(&doc_root);
+        let reload = reload.clone();
+
+        thread::spawn(move || {
+            if let Err(e) = respond(&doc_root, reload.as_ref(), stream) {
+                debug!("could not respond to request: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Reads a request from a connection and writes the response.
+fn respond(doc_root: &Path, reload: Option<&LiveReload>, mut stream: TcpStream) -> io::Result<()> {
+    let mut request_line = String::new();
+    let mut reader = BufReader::new(stream.try_clone()?);
+    reader.read_line(&mut request_line)?;
+
+    // The headers are read, but none of them affect the response.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next()) {
+        (Some(method), Some(target)) => (method, target),
+        _ => return write_status(&mut stream, "400 Bad Request"),
+    };
+
+    if method != "GET" && method != "HEAD" {
+        return write_status(&mut stream, "405 Method Not Allowed");
+    }
+
+    if let Some(reload) = reload.filter(|_| target == RELOAD_PATH) {
+        // The stream is held open until the next reload, when the page is told to reload.
+        let generation = reload.generation();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n"
+        )?;
+        stream.flush()?;
+        reload.wait(generation);
+        stream.write_all(b"data: reload\n\n")?;
+        return stream.flush();
+    }
+
+    let contents = if reload.is_some() && target == RELOAD_SCRIPT_PATH {
+        let content_type = content_type(Path::new(RELOAD_SCRIPT_PATH));
+        Ok((RELOAD_JS.as_bytes().to_vec(), content_type, None))
+    } else {
+        let path = match resolve(doc_root, target) {
+            Some(path) => path,
+            None => return write_status(&mut stream, "404 Not Found"),
+        };
+        if path.is_dir() {
+            listing(&path).map(|html| (html, "text/html; charset=utf-8", None))
+        } else {
+            let mut contents = vec![];
+            File::open(&path)
+                .and_then(|mut file| file.read_to_end(&mut contents))
+                .map(|_| (contents, content_type(&path), content_encoding(&path)))
+        }
+    };
+    let (mut contents, content_type, content_encoding) = match contents {
+        Ok(contents) => contents,
+        Err(_) => return write_status(&mut stream, "404 Not Found"),
+    };
+    if reload.is_some() && content_type.starts_with("text/html") && content_encoding.is_none() {
+        add_reload_script(&mut contents);
+    }
+
+    info!("{} {}", method, target);
+    write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: {}\r\n", content_type)?;
+    if let Some(encoding) = content_encoding {
+        write!(stream, "Content-Encoding: {}\r\n", encoding)?;
+    }
+    write!(stream, "Content-Length: {}\r\nConnection: close\r\n\r\n", contents.len())?;
+    if method == "GET" {
+        stream.write_all(&contents)?;
+    }
+    stream.flush()
+}
+
+/// Returns a page that links to the subdirectories of a directory without an `index.html`, such
+/// as the doc root of a render with more than one crate.
+fn listing(dir: &Path) -> io::Result<Vec<u8>> {
+    let mut names = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    names.sort();
+
+    let mut html = String::from("<!DOCTYPE html>\n<ul>\n");
+    for name in names {
+        html.push_str(&format!("<li><a href=\"{0}/\">{0}</a></li>\n", html_escape(&name)));
+    }
+    html.push_str("</ul>\n");
+
+    Ok(html.into_bytes())
+}
+
+/// Adds the live reload script to the end of the body of a page.
+fn add_reload_script(html: &mut Vec<u8>) {
+    let end = html.windows(7).rposition(|window| window == b"</body>").unwrap_or(html.len());
+    html.splice(end..end, RELOAD_SCRIPT.bytes());
+}
+
+/// Writes a response without a body.
+fn write_status(stream: &mut TcpStream, status: &str) -> io::Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status)
+}
+
+/// Returns the file under the doc root that a request target refers to.
+///
+/// Returns `None` if the target is not a valid path, or would lead outside the doc root.
+fn resolve(doc_root: &Path, target: &str) -> Option<PathBuf> {
+    let target = target.split(&['?', '#'][..]).next().unwrap_or("");
+    let decoded = percent_decode(target)?;
+    let mut path = doc_root.to_path_buf();
+
+    for component in Path::new(decoded.trim_start_matches('/')).components() {
+        match component {
+            Component::Normal(component) => path.push(component),
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+
+    if path.join("index.html").is_file() {
+        path.push("index.html");
+    }
+
+    Some(path)
+}
+
+/// Decodes the `%XX` escapes in a URL path.
+fn percent_decode(path: &str) -> Option<String> {
+    let mut bytes = vec![];
+    let mut rest = path.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail.get(..2)?;
+            let hex = ::std::str::from_utf8(hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+
+    String::from_utf8(bytes).ok()
+}
+
+/// Returns the MIME type of a file, based on its extension. Files compressed with Brotli have the
+/// type of the file they are a compressed copy of.
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("br") => path.file_stem().map_or("application/octet-stream", |stem| {
+            content_type(Path::new(stem))
+        }),
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "application/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("xml") => "application/xml",
+        Some("txt") | Some("md") => "text/plain; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("wasm") => "application/wasm",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("gz") => "application/gzip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Returns the `Content-Encoding` that a file is served with, so that browsers decompress the
+/// precompressed copies of pages.
+///
+/// Files compressed with gzip are served as they are, because they are usually archives.
+fn content_encoding(path: &Path) -> Option<&'static str> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("br") => Some("br"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::io::prelude::*;
+    use std::net::{TcpListener, TcpStream};
+    use std::path::Path;
+    use std::thread;
+
+    use super::LiveReload;
+
+    #[test]
+    fn resolve() {
+        let root = Path::new("/docs");
+
+        assert_eq!(
+            super::resolve(root, "/demo/struct.Foo.html?search=x"),
+            Some(root.join("demo/struct.Foo.html"))
+        );
+        assert_eq!(
+            super::resolve(root, "/src/my%20file.rs.html"),
+            Some(root.join("src/my file.rs.html"))
+        );
+        assert_eq!(super::resolve(root, "/../etc/passwd"), None);
+        assert_eq!(super::resolve(root, "/%2e%2e/etc/passwd"), None);
+        assert_eq!(super::content_type(Path::new("styles.css")), "text/css; charset=utf-8");
+    }
+
+    #[test]
+    fn content_type() {
+        let content_type = |path| super::content_type(Path::new(path));
+        assert_eq!(content_type("favicon.ico"), "image/x-icon");
+        assert_eq!(content_type("logo.jpg"), "image/jpeg");
+        assert_eq!(content_type("logo.jpeg"), "image/jpeg");
+        assert_eq!(content_type("logo.gif"), "image/gif");
+        assert_eq!(content_type("logo.webp"), "image/webp");
+        assert_eq!(content_type("search.wasm"), "application/wasm");
+        assert_eq!(content_type("font.woff"), "font/woff");
+        assert_eq!(content_type("docs.tar.gz"), "application/gzip");
+        assert_eq!(content_type("index.html.br"), "text/html; charset=utf-8");
+        assert_eq!(content_type("unknown"), "application/octet-stream");
+
+        assert_eq!(super::content_encoding(Path::new("index.html.br")), Some("br"));
+        assert_eq!(super::content_encoding(Path::new("docs.tar.gz")), None);
+        assert_eq!(super::content_encoding(Path::new("index.html")), None);
+    }
+
+    #[test]
+    fn serve() {
+        let root = env::temp_dir().join("rustdoc-static-serve");
+        fs::create_dir_all(root.join("demo")).unwrap();
+        fs::write(root.join("demo/index.html"), "<html>").unwrap();
+        fs::write(root.join("demo/index.html.br"), "compressed").unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let doc_root = root.clone();
+        thread::spawn(move || super::serve(doc_root, listener));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /demo/ HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/html; charset=utf-8\r\n"));
+        assert!(response.ends_with("\r\n\r\n<html>"));
+        assert!(!response.contains("Content-Encoding"));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /demo/index.html.br HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        assert!(response.contains("Content-Type: text/html; charset=utf-8\r\n"));
+        assert!(response.contains("Content-Encoding: br\r\n"));
+        assert!(response.ends_with("\r\n\r\ncompressed"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn live_reload() {
+        let root = env::temp_dir().join("rustdoc-static-live-reload");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("index.html"), "<html><body></body></html>").unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let reload = LiveReload::new();
+        let (doc_root, handle) = (root.clone(), reload.clone());
+        thread::spawn(move || super::serve_with_reload(doc_root, listener, handle));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut page = String::new();
+        stream.read_to_string(&mut page).unwrap();
+        assert!(page.ends_with(&format!("<body>{}</body></html>", super::RELOAD_SCRIPT)));
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET /.rustdoc-static/reload.js HTTP/1.1\r\n\r\n").unwrap();
+        let mut script = String::new();
+        stream.read_to_string(&mut script).unwrap();
+        assert!(script.ends_with(super::RELOAD_JS));
+
+        let mut events = TcpStream::connect(addr).unwrap();
+        events.write_all(b"GET /.rustdoc-static/reload HTTP/1.1\r\n\r\n").unwrap();
+        let mut headers = [0; 256];
+        let read = events.read(&mut headers).unwrap();
+        assert!(String::from_utf8_lossy(&headers[..read]).contains("text/event-stream"));
+
+        reload.reload();
+        let mut event = String::new();
+        events.read_to_string(&mut event).unwrap();
+        assert!(event.ends_with("data: reload\n\n"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
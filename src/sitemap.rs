@@ -0,0 +1,139 @@
+//! Generation of a `sitemap.xml` listing every page of the rendered documentation, so that search
+//! engines can index a hosted doc site, and of a `robots.txt` that keeps them out of the pages that
+//! are not worth indexing.
+
+use std::fs::File;
+use std::io::prelude::*;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use handlebars::html_escape;
+
+use files;
+
+/// Writes `sitemap.xml` at the doc root, listing every HTML page under it relative to `base_url`.
+pub fn write_sitemap<P: AsRef<Path>>(doc_root: P, base_url: &str) -> io::Result<()> {
+    let doc_root = doc_root.as_ref();
+    let pages = pages(doc_root)?;
+
+    let path = doc_root.join("sitemap.xml");
+    info!("writing sitemap to `{}`", path.display());
+    File::create(&path)?.write_all(sitemap(base_url, &pages).as_bytes())
+}
+
+/// Returns every HTML page under the doc root, relative to it.
+pub(crate) fn pages(doc_root: &Path) -> io::Result<Vec<PathBuf>> {
+    let pages = files(doc_root)?
+        .into_iter()
+        .filter(|file| file.extension().is_some_and(|extension| extension == "html"))
+        .filter(|file| {
+            let valid = file.to_str().is_some();
+            if !valid {
+                warn!("leaving `{}` out of the sitemap: invalid UTF-8", file.display());
+            }
+            valid
+        })
+        .collect();
+    Ok(pages)
+}
+
+/// Renders the sitemap for a list of page paths relative to the doc root.
+pub(crate) fn sitemap(base_url: &str, pages: &[PathBuf]) -> String {
+    let base_url = base_url.trim_end_matches('/');
+
+    let urls = pages
+        .iter()
+        .map(|page| {
+            let page = page
+                .iter()
+                .map(|component| component.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            format!("  <url><loc>{}/{}</loc></url>\n", html_escape(base_url), html_escape(&page))
+        })
+        .collect::<String>();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n{}</urlset>\n",
+        urls
+    )
+}
+
+/// The paths under the doc root that `robots.txt` disallows by default, besides the source pages
+/// of each crate: the search index.
+pub(crate) const DISALLOWED: &[&str] = &["search-index.js"];
+
+/// Renders a `robots.txt` that allows crawlers to index the documentation of `crates` under
+/// `prefix`, the path it is hosted at, except for the source pages of the crates, the default
+/// disallowed paths and `disallowed`, which are relative to the doc root. The sitemap is linked to
+/// if its URL is given.
+pub(crate) fn robots(
+    prefix: &str,
+    crates: &[&str],
+    disallowed: &[String],
+    sitemap: Option<&str>,
+) -> String {
+    let prefix = format!("/{}", prefix.trim_matches('/'));
+    let prefix = format!("{}/", prefix.trim_end_matches('/'));
+
+    let mut robots = String::from("User-agent: *\n");
+    robots.push_str(&format!("Allow: {}\n", prefix));
+    let sources = crates.iter().map(|krate| format!("{}/src/", krate)).collect::<Vec<_>>();
+    let paths = sources.iter().map(|path| &path[..]).chain(DISALLOWED.iter().cloned());
+    for path in paths.chain(disallowed.iter().map(|path| &path[..])) {
+        robots.push_str(&format!("Disallow: {}{}\n", prefix, path.trim_start_matches('/')));
+    }
+    if let Some(sitemap) = sitemap {
+        robots.push_str(&format!("\nSitemap: {}\n", sitemap));
+    }
+    robots
+}
+
+/// Returns the path of a URL on its host, such as `/docs/` for `https://example.com/docs/`.
+pub(crate) fn url_path(url: &str) -> &str {
+    let host = url.find("://").map_or(url, |scheme| &url[scheme + 3..]);
+    host.find('/').map_or("/", |path| &host[path..])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    #[test]
+    fn robots() {
+        assert_eq!(super::url_path("https://example.com/docs/"), "/docs/");
+        assert_eq!(super::url_path("https://example.com"), "/");
+
+        let disallowed = [String::from("/private/")];
+        let sitemap = Some("https://example.com/sitemap.xml");
+        assert_eq!(
+            super::robots("/docs/1.0.0", &["a", "b"], &disallowed, sitemap),
+            "User-agent: *\n\
+             Allow: /docs/1.0.0/\n\
+             Disallow: /docs/1.0.0/a/src/\n\
+             Disallow: /docs/1.0.0/b/src/\n\
+             Disallow: /docs/1.0.0/search-index.js\n\
+             Disallow: /docs/1.0.0/private/\n\
+             \n\
+             Sitemap: https://example.com/sitemap.xml\n"
+        );
+        assert_eq!(
+            super::robots("/", &["a"], &[], None),
+            "User-agent: *\nAllow: /\nDisallow: /a/src/\nDisallow: /search-index.js\n"
+        );
+    }
+
+    #[test]
+    fn sitemap() {
+        let pages = [PathBuf::from("demo/index.html"), PathBuf::from("demo/struct.Foo.html")];
+        assert_eq!(
+            super::sitemap("https://example.com/docs/", &pages),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n  \
+             <url><loc>https://example.com/docs/demo/index.html</loc></url>\n  \
+             <url><loc>https://example.com/docs/demo/struct.Foo.html</loc></url>\n\
+             </urlset>\n"
+        );
+    }
+}
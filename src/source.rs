@@ -0,0 +1,497 @@
+//! Rendering of the crate's source files as syntax-highlighted HTML pages.
+//!
+//! Resources may carry a `source` attribute of the form `{ "file": "src/lib.rs", "line": 10 }`.
+//! The contents of each referenced file are taken from the document's `meta.sources` object if
+//! the backend embedded them, and are otherwise read from disk relative to the source directory
+//! of the render, which is never taken from the document. The pages of each crate's sources are
+//! written under `src/` in the directory of the crate, so that crates with files of the same name
+//! do not overwrite each other's pages.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use handlebars::html_escape;
+use jsonapi::api::{JsonApiDocument, Resource};
+
+use errors::*;
+use index::Index;
+use report::RenderReport;
+use {html_diff_paths, path_for_resource, resources, Output};
+
+/// Rust keywords that should be highlighted.
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// The location of a resource's definition in the crate's source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLocation {
+    /// Path to the file, relative to the source directory.
+    pub file: String,
+
+    /// The 1-based line of the definition, if known.
+    pub line: Option<u64>,
+}
+
+/// Links from identifiers in a source file to the documentation of the items they name.
+#[derive(Debug, Default)]
+pub struct SourceLinks {
+    /// The pages of item definitions in the file, keyed by line and item name.
+    definitions: HashMap<(u64, String), PathBuf>,
+
+    /// The pages of items that may be referenced by their unqualified name, shared by the source
+    /// files of a document. If the name is ambiguous within the crate, no link is created.
+    references: Rc<HashMap<String, Option<PathBuf>>>,
+
+    /// The path of the page of the source file, which the links are relative to.
+    source_path: PathBuf,
+}
+
+impl SourceLinks {
+    /// Returns a link for an identifier at a given line, preferring definitions.
+    fn get(&self, line: u64, ident: &str) -> Option<String> {
+        let page = match self.definitions.get(&(line, ident.to_owned())) {
+            Some(page) => page,
+            None => self.references.get(ident)?.as_ref()?,
+        };
+        html_diff_paths(page, &self.source_path)
+    }
+}
+
+/// The pages of every documented resource, from which the links of each source file are taken.
+///
+/// The resources are collected once for all of the source pages of a document, since a document
+/// as large as the standard library has thousands of source files.
+#[derive(Debug, Default)]
+struct SourceIndex {
+    /// The pages of item definitions, keyed by crate and file, then by line and item name.
+    definitions: HashMap<(String, String), HashMap<(u64, String), PathBuf>>,
+
+    /// The pages of the items of each crate by unqualified name, or `None` if the name is
+    /// ambiguous.
+    references: HashMap<String, Rc<HashMap<String, Option<PathBuf>>>>,
+}
+
+impl SourceIndex {
+    /// Collects the pages of every documented resource.
+    fn new(document: &Index) -> SourceIndex {
+        let mut definitions = HashMap::<_, HashMap<_, _>>::new();
+        let mut references = HashMap::<_, HashMap<_, _>>::new();
+
+        for resource in resources(document) {
+            let page = match path_for_resource(resource) {
+                Some(page) => page,
+                None => continue,
+            };
+            let krate = crate_of(resource).to_owned();
+            let name = document.name(&resource.id).to_owned();
+
+            if let Some(location) = location_for_resource(document, resource) {
+                if let Some(line) = location.line {
+                    let key = (krate.clone(), location.file);
+                    definitions.entry(key).or_default().insert((line, name.clone()), page.clone());
+                }
+            }
+
+            if resource._type != "crate" && resource._type != "module" {
+                references
+                    .entry(krate)
+                    .or_default()
+                    .entry(name)
+                    .and_modify(|page| *page = None)
+                    .or_insert_with(|| Some(page));
+            }
+        }
+
+        let references = references.into_iter().map(|(krate, names)| (krate, Rc::new(names)));
+        SourceIndex { definitions, references: references.collect() }
+    }
+
+    /// Returns the links from the page of a source file of a crate, taking the definitions in the
+    /// file.
+    fn links(&mut self, krate: &str, file: &str, source_path: &Path) -> SourceLinks {
+        let key = (krate.to_owned(), file.to_owned());
+        SourceLinks {
+            definitions: self.definitions.remove(&key).unwrap_or_default(),
+            references: self.references.get(krate).cloned().unwrap_or_default(),
+            source_path: source_path.to_owned(),
+        }
+    }
+}
+
+/// Returns the name of the crate that a resource belongs to, which is the first segment of its ID.
+fn crate_of(resource: &Resource) -> &str {
+    resource.id.split("::").next().unwrap_or(&resource.id)
+}
+
+/// Returns the source location of a resource, if the backend provided one.
+pub fn location_for_resource(document: &Index, resource: &Resource) -> Option<SourceLocation> {
+    document.item(resource)?.source.clone()
+}
+
+/// Returns the path of the rendered page for a source file of a crate, relative to the doc root.
+///
+/// Returns `None` if the file path would escape the `src` directory of the crate.
+pub fn path_for_source(krate: &str, file: &str) -> Option<PathBuf> {
+    let mut path = Path::new(krate).join("src");
+
+    for component in Path::new(file).components() {
+        match component {
+            Component::Normal(component) => path.push(component),
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+
+    let file_name = format!("{}.html", path.file_name()?.to_str()?);
+    path.set_file_name(file_name);
+    Some(path)
+}
+
+/// Creates a link from the page of a resource of a crate to the line in the source where it is
+/// defined.
+pub fn link(resource_path: &Path, krate: &str, location: &SourceLocation) -> Option<String> {
+    let source_path = path_for_source(krate, &location.file)?;
+    let mut link = html_diff_paths(&source_path, resource_path)?;
+
+    if let Some(line) = location.line {
+        link.push_str(&format!("#L{}", line));
+    }
+
+    Some(link)
+}
+
+/// Renders a page for every source file referenced by the document.
+///
+/// Files that the document does not embed are read from `source_dir` if it is given, and
+/// otherwise from the current directory.
+pub(crate) fn render_sources(
+    document: &Index,
+    output: &Output,
+    source_dir: Option<&Path>,
+    report: &mut RenderReport,
+) -> Result<()> {
+    let doc_root = output.doc_root;
+    let files = resources(document)
+        .filter_map(|resource| {
+            let location = location_for_resource(document, resource)?;
+            Some((crate_of(resource).to_owned(), location.file))
+        })
+        .collect::<BTreeSet<_>>();
+    let mut index = SourceIndex::new(document);
+
+    for (krate, file) in files {
+        // The path of the file comes from the document, so a file that is absolute or leads out of
+        // the source directory could name any file on the machine. Such files are never read.
+        let path = match path_for_source(&krate, &file) {
+            Some(path) => path,
+            None => {
+                report.warn(format!(
+                    "skipping source file outside of the source directory: `{}`",
+                    file,
+                ));
+                continue;
+            }
+        };
+
+        let contents = match embedded_source(document, &file) {
+            Some(contents) => contents.to_owned(),
+            None => {
+                let mut contents = String::new();
+                let source_dir = source_dir.unwrap_or_else(|| Path::new("."));
+                match File::open(source_dir.join(&file)) {
+                    Ok(mut source) => source.read_to_string(&mut contents)?,
+                    Err(e) => {
+                        report.warn(format!("could not read source file `{}`: {}", file, e));
+                        continue;
+                    }
+                };
+                contents
+            }
+        };
+
+        let links = index.links(&krate, &file, &path);
+        let path_to_root = html_diff_paths(doc_root, &doc_root.join(&path));
+        let context = json!({
+            "name": file,
+            "pathToRoot": path_to_root,
+            "canonicalUrl": output.canonical_url(&doc_root.join(&path)),
+            "lang": output.lang,
+            "analytics": output.analytics,
+            "assets": output.assets,
+            "icons": output.icons(None, path_to_root.as_deref().unwrap_or(".")),
+            "lineNumbers": (1..contents.lines().count() + 1).collect::<Vec<_>>(),
+            "code": highlight(&contents, &links),
+        });
+
+        let path = doc_root.join(&path);
+        info!("rendering source `{}` as `{}`", file, path.display());
+        output.render("source", &context, &path)?;
+        report.files_written.push(path);
+    }
+
+    Ok(())
+}
+
+/// Returns the contents of a source file if it was embedded in the document.
+fn embedded_source<'a>(document: &'a JsonApiDocument, file: &str) -> Option<&'a str> {
+    document
+        .meta
+        .as_ref()
+        .and_then(|meta| meta.get("sources"))
+        .and_then(|sources| sources.get(file))
+        .and_then(|contents| contents.as_str())
+}
+
+/// Highlights Rust source code as HTML.
+///
+/// Each token of interest is wrapped in a `<span>` whose class describes its kind: `kw`,
+/// `comment`, `string`, `number`, `lifetime`, `attribute` or `macro`. Identifiers that name a
+/// documented item are wrapped in a link to its documentation.
+pub fn highlight(source: &str, links: &SourceLinks) -> String {
+    let chars = source.char_indices().collect::<Vec<_>>();
+    let mut html = String::with_capacity(source.len());
+    let mut line = 1;
+    let mut i = 0;
+
+    // Returns the byte offset of the character at index `i`.
+    let offset = |i: usize| chars.get(i).map(|&(offset, _)| offset).unwrap_or(source.len());
+
+    while i < chars.len() {
+        let c = chars[i].1;
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let start = i;
+
+        let class = if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i].1 != '\n' {
+                i += 1;
+            }
+            Some("comment")
+        } else if c == '/' && next == Some('*') {
+            // Block comments nest, so the comment ends where the last opened one closes.
+            let mut depth = 0;
+            while i < chars.len() {
+                match (chars[i].1, chars.get(i + 1).map(|c| c.1)) {
+                    ('/', Some('*')) => {
+                        depth += 1;
+                        i += 2;
+                    }
+                    ('*', Some('/')) => {
+                        depth -= 1;
+                        i += 2;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => i += 1,
+                }
+            }
+            Some("comment")
+        } else if let Some(end) = raw_string_end(&chars, i) {
+            i = end;
+            Some("string")
+        } else if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i].1 != '"' {
+                if chars[i].1 == '\\' {
+                    i += 1;
+                }
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            Some("string")
+        } else if c == '\'' {
+            // Distinguish between character literals and lifetimes. Escapes such as `'\u{1F600}'`
+            // are longer than one character, but are always character literals.
+            let close = if next == Some('\\') {
+                let mut rest = chars.iter().skip(i + 3).take_while(|c| c.1 != '\n');
+                rest.position(|c| c.1 == '\'').map(|position| i + 3 + position)
+            } else {
+                Some(i + 2).filter(|&close| chars.get(close).map(|c| c.1) == Some('\''))
+            };
+            if let Some(close) = close {
+                i = close + 1;
+                Some("string")
+            } else {
+                i += 1;
+                while i < chars.len() && is_ident_char(chars[i].1) {
+                    i += 1;
+                }
+                Some("lifetime")
+            }
+        } else if c == '#' && (next == Some('[') || next == Some('!')) {
+            let mut depth = 0;
+            while i < chars.len() {
+                match chars[i].1 {
+                    '[' => depth += 1,
+                    ']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            i += 1;
+                            break;
+                        }
+                    }
+                    '\n' if depth == 0 => break,
+                    _ => (),
+                }
+                i += 1;
+            }
+            Some("attribute")
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (is_ident_char(chars[i].1) || chars[i].1 == '.') {
+                i += 1;
+            }
+            Some("number")
+        } else if is_ident_char(c) {
+            while i < chars.len() && is_ident_char(chars[i].1) {
+                i += 1;
+            }
+            let ident = &source[offset(start)..offset(i)];
+            if KEYWORDS.contains(&ident) {
+                Some("kw")
+            } else if chars.get(i).map(|c| c.1) == Some('!') {
+                i += 1;
+                Some("macro")
+            } else if let Some(link) = links.get(line, ident) {
+                html.push_str(&format!("<a href=\"{}\">{}</a>", link, ident));
+                continue;
+            } else {
+                None
+            }
+        } else {
+            i += 1;
+            None
+        };
+
+        let token = &source[offset(start)..offset(i)];
+        line += token.matches('\n').count() as u64;
+
+        let token = html_escape(token);
+        match class {
+            Some(class) => html.push_str(&format!("<span class=\"{}\">{}</span>", class, token)),
+            None => html.push_str(&token),
+        }
+    }
+
+    html
+}
+
+/// Returns whether a character can be part of an identifier or a number literal.
+fn is_ident_char(c: char) -> bool {
+    c == '_' || c.is_alphanumeric()
+}
+
+/// Returns the index of the character after a raw string, such as `r#"..."#` or `br"..."`,
+/// that starts at index `i`, or `None` if no raw string starts there.
+///
+/// Raw strings contain no escapes, and end at the first quote followed by as many `#` as they
+/// started with.
+fn raw_string_end(chars: &[(usize, char)], i: usize) -> Option<usize> {
+    let c = |i: usize| chars.get(i).map(|c| c.1);
+
+    let mut j = if c(i) == Some('b') { i + 1 } else { i };
+    if c(j) != Some('r') {
+        return None;
+    }
+    j += 1;
+
+    let hashes = chars[j..].iter().take_while(|c| c.1 == '#').count();
+    j += hashes;
+    if c(j) != Some('"') {
+        return None;
+    }
+    j += 1;
+
+    while j < chars.len() {
+        let closes = c(j) == Some('"') && (1..hashes + 1).all(|n| c(j + n) == Some('#'));
+        if closes {
+            return Some(j + 1 + hashes);
+        }
+        j += 1;
+    }
+    Some(chars.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use jsonapi::api::JsonApiDocument;
+
+    use index::Index;
+
+    use super::{SourceIndex, SourceLinks};
+
+    #[test]
+    fn path_for_source() {
+        assert_eq!(
+            super::path_for_source("test_crate", "src/lib.rs"),
+            Some(PathBuf::from("test_crate/src/src/lib.rs.html"))
+        );
+        assert_eq!(super::path_for_source("test_crate", "../outside.rs"), None);
+        assert_eq!(super::path_for_source("test_crate", "/etc/passwd"), None);
+    }
+
+    #[test]
+    fn highlight() {
+        assert_eq!(
+            super::highlight("pub fn foo<'a>(x: &'a str) {} // done", &SourceLinks::default()),
+            "<span class=\"kw\">pub</span> <span class=\"kw\">fn</span> foo&lt;\
+             <span class=\"lifetime\">'a</span>&gt;(x: &amp;<span class=\"lifetime\">'a</span> \
+             str) {} <span class=\"comment\">// done</span>"
+        );
+        assert_eq!(
+            super::highlight("println!(\"{}\", 'c');", &SourceLinks::default()),
+            "<span class=\"macro\">println!</span>(<span class=\"string\">&quot;{}&quot;</span>, \
+             <span class=\"string\">'c'</span>);"
+        );
+        assert_eq!(
+            super::highlight(r##"let s = r#"a "quoted" // str"#;"##, &SourceLinks::default()),
+            "<span class=\"kw\">let</span> s = \
+             <span class=\"string\">r#&quot;a &quot;quoted&quot; // str&quot;#</span>;"
+        );
+        assert_eq!(
+            super::highlight(r#"(br"\", r#match)"#, &SourceLinks::default()),
+            "(<span class=\"string\">br&quot;\\&quot;</span>, r#<span class=\"kw\">match</span>)"
+        );
+        assert_eq!(
+            super::highlight(r"['\u{1F600}', '\'', '\\'] 'a", &SourceLinks::default()),
+            "[<span class=\"string\">'\\u{1F600}'</span>, <span class=\"string\">'\\''</span>, \
+             <span class=\"string\">'\\\\'</span>] <span class=\"lifetime\">'a</span>"
+        );
+        assert_eq!(
+            super::highlight("/* a /* nested */ comment */ fn", &SourceLinks::default()),
+            "<span class=\"comment\">/* a /* nested */ comment */</span> \
+             <span class=\"kw\">fn</span>"
+        );
+    }
+
+    #[test]
+    fn highlight_links() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [{
+                    "type": "struct",
+                    "id": "test_crate::Foo",
+                    "attributes": { "source": { "file": "src/lib.rs", "line": 1 } }
+                }]
+            }"#,
+        ).unwrap();
+        let source_path = Path::new("test_crate/src/src/lib.rs.html");
+        let index = Index::parse(&document).unwrap();
+        let links = SourceIndex::new(&index).links("test_crate", "src/lib.rs", source_path);
+
+        assert_eq!(
+            super::highlight("struct Foo;\nlet x: Foo;", &links),
+            "<span class=\"kw\">struct</span> <a href=\"../../struct.Foo.html\">Foo</a>;\n\
+             <span class=\"kw\">let</span> x: <a href=\"../../struct.Foo.html\">Foo</a>;"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Documentation statistics for a crate.
+
+use std::collections::{BTreeMap, HashSet};
+
+use jsonapi::api::JsonApiDocument;
+use serde_json::Value;
+
+use {markdown, model, path_for_resource, resources};
+
+/// Counts of the items of a single kind.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct KindStats {
+    /// The total number of items.
+    pub total: usize,
+
+    /// The number of items that have documentation.
+    pub documented: usize,
+
+    /// The number of items whose documentation includes a code example.
+    pub with_examples: usize,
+}
+
+impl KindStats {
+    /// Returns the percentage of the items that have documentation, or `None` if there are no
+    /// items.
+    pub fn coverage(&self) -> Option<f64> {
+        if self.total == 0 {
+            return None;
+        }
+
+        Some(self.documented as f64 * 100.0 / self.total as f64)
+    }
+
+    /// Returns whether the coverage is under a percentage, as checked by `coverage --fail-under`.
+    ///
+    /// Coverage that is exactly the percentage is not under it, and there is nothing left to
+    /// document if there are no items.
+    pub fn is_under(&self, percent: f64) -> bool {
+        self.coverage().unwrap_or(100.0) < percent
+    }
+}
+
+/// Statistics summarizing the health of a crate's documentation.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    /// Item counts, keyed by kind of item.
+    pub kinds: BTreeMap<String, KindStats>,
+
+    /// Item counts, keyed by the path of the module the items are in.
+    ///
+    /// Items count towards their closest enclosing module, so the fields of a struct count towards
+    /// the module of the struct. Modules and crates count towards themselves.
+    pub modules: BTreeMap<String, KindStats>,
+
+    /// The number of pages rendered for the crate, including source pages.
+    pub pages: usize,
+}
+
+impl Stats {
+    /// Collects statistics about every resource in a document.
+    pub fn collect(document: &JsonApiDocument) -> Stats {
+        let mut stats = Stats::default();
+        let mut source_files = vec![];
+
+        let modules = resources(document)
+            .filter(|resource| resource._type == "module" || resource._type == "crate")
+            .map(|resource| &resource.id[..])
+            .collect::<HashSet<_>>();
+
+        for resource in resources(document) {
+            let kind = stats.kinds.entry(resource._type.clone()).or_insert_with(KindStats::default);
+            let module = enclosing_module(&modules, &resource.id);
+            let module =
+                stats.modules.entry(String::from(module)).or_insert_with(KindStats::default);
+            kind.total += 1;
+            module.total += 1;
+
+            let item = model::Item::from_resource(resource).ok();
+            let docs = item.as_ref().and_then(|item| item.docs);
+            if let Some(docs) = docs.filter(|docs| !docs.trim().is_empty()) {
+                kind.documented += 1;
+                module.documented += 1;
+
+                if markdown::has_code_block(docs) {
+                    kind.with_examples += 1;
+                    module.with_examples += 1;
+                }
+            }
+
+            if path_for_resource(resource).is_some() {
+                stats.pages += 1;
+            }
+
+            // The sources of each crate are rendered separately, even if the paths are the same.
+            if let Some(location) = item.as_ref().and_then(|item| item.source.as_ref()) {
+                let krate = resource.id.split("::").next().unwrap_or(&resource.id);
+                source_files.push((krate, location.file.clone()));
+            }
+        }
+
+        source_files.sort();
+        source_files.dedup();
+        stats.pages += source_files.len();
+
+        stats
+    }
+
+    /// Returns the counts of every item of the document.
+    pub fn total(&self) -> KindStats {
+        let mut total = KindStats::default();
+        for kind in self.kinds.values() {
+            total.total += kind.total;
+            total.documented += kind.documented;
+            total.with_examples += kind.with_examples;
+        }
+        total
+    }
+
+    /// Returns the statistics as JSON.
+    pub fn to_json(&self) -> Value {
+        let kinds = self
+            .kinds
+            .iter()
+            .map(|(kind, stats)| {
+                let json = json!({
+                    "total": stats.total,
+                    "documented": stats.documented,
+                    "withExamples": stats.with_examples,
+                });
+                (kind.clone(), json)
+            })
+            .collect::<::serde_json::Map<_, _>>();
+
+        let total = self.total();
+
+        json!({
+            "kinds": kinds,
+            "total": total.total,
+            "documented": total.documented,
+            "withExamples": total.with_examples,
+            "pages": self.pages,
+        })
+    }
+}
+
+/// Returns the path of the closest module that contains an item, or of the item itself if it is
+/// a module. Items outside of any module of the document count towards their crate.
+fn enclosing_module<'a>(modules: &HashSet<&str>, id: &'a str) -> &'a str {
+    let ancestors = id.rmatch_indices("::").map(|(end, _)| &id[..end]);
+    let crate_name = id.split("::").next().unwrap_or(id);
+
+    Some(id).into_iter().chain(ancestors).find(|path| modules.contains(path)).unwrap_or(crate_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonapi::api::JsonApiDocument;
+
+    use super::{KindStats, Stats};
+
+    #[test]
+    fn collect() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": { "docs": "Docs." } },
+                "included": [
+                    {
+                        "type": "function",
+                        "id": "test_crate::a",
+                        "attributes": { "docs": "Example:\n\n```\na();\n```" }
+                    },
+                    { "type": "function", "id": "test_crate::b", "attributes": {} },
+                    { "type": "field", "id": "test_crate::S::f", "attributes": { "docs": "" } },
+                    { "type": "module", "id": "test_crate::m", "attributes": { "docs": "Docs." } },
+                    { "type": "function", "id": "test_crate::m::c", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let stats = Stats::collect(&document);
+        assert_eq!(
+            stats.kinds["function"],
+            KindStats { total: 3, documented: 1, with_examples: 1 }
+        );
+        assert_eq!(stats.kinds["field"], KindStats { total: 1, documented: 0, with_examples: 0 });
+        assert_eq!(stats.pages, 5);
+
+        let crate_stats = &stats.modules["test_crate"];
+        assert_eq!(crate_stats, &KindStats { total: 4, documented: 2, with_examples: 1 });
+        assert_eq!(stats.modules["test_crate::m"].coverage(), Some(50.0));
+        assert_eq!(stats.total(), KindStats { total: 6, documented: 3, with_examples: 1 });
+    }
+
+    #[test]
+    fn is_under() {
+        let half = KindStats { total: 4, documented: 2, with_examples: 0 };
+        assert!(half.is_under(50.1));
+        assert!(!half.is_under(50.0));
+        assert!(!half.is_under(49.9));
+
+        let third = KindStats { total: 3, documented: 1, with_examples: 0 };
+        assert!(!third.is_under(third.coverage().unwrap()));
+        assert!(!third.is_under(33.3));
+
+        // The root of an empty crate is the only item to document.
+        let empty = Stats::collect(
+            &JsonApiDocument::from_str(
+                r#"{ "data": { "type": "crate", "id": "test_crate", "attributes": {} } }"#,
+            ).unwrap(),
+        ).total();
+        assert_eq!(empty.coverage(), Some(0.0));
+        assert!(!empty.is_under(0.0));
+        assert!(empty.is_under(0.1));
+
+        assert_eq!(KindStats::default().coverage(), None);
+        assert!(!KindStats::default().is_under(100.0));
+    }
+}
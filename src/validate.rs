@@ -0,0 +1,326 @@
+//! Checks for problems in a document before it is rendered.
+//!
+//! Renders validate the document up front, so that each problem is reported once instead of by
+//! every page that runs into it.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+use jsonapi::api::{JsonApiDocument, Resource};
+use pulldown_cmark::{Event, Parser, Tag};
+
+use index::Index;
+use report::UnresolvedLink;
+use {crates, has_page_type, identifiers, is_file_name, resources};
+
+/// A problem found in a document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// A relationship refers to a resource that the document does not include.
+    UnresolvedLink(UnresolvedLink),
+
+    /// More than one resource has the same ID.
+    DuplicateId {
+        /// The duplicated ID.
+        id: String,
+    },
+
+    /// A resource has an ID that cannot be used as the path of its page, because a segment of it is
+    /// empty, or contains a path separator or a control character.
+    InvalidId {
+        /// The ID of the resource.
+        id: String,
+    },
+
+    /// A resource has a type that the renderer does not know how to document.
+    UnsupportedType {
+        /// The ID of the resource.
+        id: String,
+
+        /// The type of the resource.
+        ty: String,
+    },
+
+    /// A resource has a `docs` attribute that is not a string.
+    InvalidDocs {
+        /// The ID of the resource.
+        id: String,
+    },
+
+    /// The docs of a resource link to a page of the documentation that is not rendered.
+    BrokenLink {
+        /// The ID of the resource.
+        id: String,
+
+        /// The destination of the link, as written in the docs.
+        link: String,
+    },
+}
+
+impl Diagnostic {
+    /// Returns a plural name for the kind of problem, such as `broken links`.
+    pub fn kind(&self) -> &'static str {
+        match *self {
+            Diagnostic::UnresolvedLink(_) => "unresolved links",
+            Diagnostic::DuplicateId { .. } => "duplicate IDs",
+            Diagnostic::InvalidId { .. } => "invalid IDs",
+            Diagnostic::UnsupportedType { .. } => "unknown resource types",
+            Diagnostic::InvalidDocs { .. } => "invalid docs",
+            Diagnostic::BrokenLink { .. } => "broken links",
+        }
+    }
+
+    /// Returns whether the problem prevents the document from being rendered. Other problems are
+    /// worked around by leaving out the affected parts of the documentation.
+    pub fn is_error(&self) -> bool {
+        matches!(*self, Diagnostic::InvalidId { .. })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Diagnostic::UnresolvedLink(ref link) => write!(
+                f,
+                "relationship `{}` of `{}` refers to `{}`, which is not included",
+                link.relationship, link.from, link.to,
+            ),
+            Diagnostic::DuplicateId { ref id } => write!(f, "more than one resource is `{}`", id),
+            Diagnostic::InvalidId { ref id } => write!(
+                f,
+                "ID `{}` has a path segment that cannot be used as a file name",
+                id.escape_debug(),
+            ),
+            Diagnostic::UnsupportedType { ref id, ref ty } => {
+                write!(f, "unknown resource type `{}` for `{}`", ty, id)
+            }
+            Diagnostic::InvalidDocs { ref id } => {
+                write!(f, "docs attribute of `{}` was not a string", id)
+            }
+            Diagnostic::BrokenLink { ref id, ref link } => write!(
+                f,
+                "link `{}` in the docs of `{}` does not lead to a rendered page",
+                link, id,
+            ),
+        }
+    }
+}
+
+/// Checks a document for problems that would prevent it from being rendered, or leave parts of
+/// the documentation out.
+pub fn validate(document: &JsonApiDocument) -> Vec<Diagnostic> {
+    check(&Index::new(document))
+}
+
+/// Checks an indexed document for problems.
+pub(crate) fn check(document: &Index) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let mut ids = HashSet::new();
+
+    let mut pages = resources(document)
+        .filter_map(|resource| document.path(resource))
+        .collect::<HashSet<_>>();
+    pages.insert(PathBuf::from("deprecated.html"));
+    pages.insert(PathBuf::from("stats.html"));
+
+    // Source pages are rendered under the directory of their crate.
+    let sources = crates(document)
+        .iter()
+        .map(|krate| Path::new(&krate.id).join("src"))
+        .collect::<Vec<_>>();
+
+    for resource in resources(document) {
+        if !ids.insert(resource.id.as_str()) {
+            diagnostics.push(Diagnostic::DuplicateId { id: resource.id.clone() });
+        }
+
+        if !resource.id.split("::").all(is_file_name) {
+            diagnostics.push(Diagnostic::InvalidId { id: resource.id.clone() });
+        }
+
+        if !has_page_type(resource) && resource._type != "field" && resource._type != "impl" {
+            diagnostics.push(Diagnostic::UnsupportedType {
+                id: resource.id.clone(),
+                ty: resource._type.clone(),
+            });
+        }
+
+        let docs = resource.attributes.get("docs");
+        if docs.is_some_and(|docs| !docs.is_string()) {
+            diagnostics.push(Diagnostic::InvalidDocs { id: resource.id.clone() });
+        }
+
+        let page = document.path(resource);
+        if let (Some(docs), Some(page)) = (docs.and_then(|docs| docs.as_str()), page) {
+            for link in broken_links(docs, &page, &pages, &sources) {
+                diagnostics.push(Diagnostic::BrokenLink { id: resource.id.clone(), link });
+            }
+        }
+
+        check_relationships(document, resource, &mut diagnostics);
+    }
+
+    diagnostics
+}
+
+/// Checks that the relationships of a resource refer to included resources.
+fn check_relationships(document: &Index, resource: &Resource, diagnostics: &mut Vec<Diagnostic>) {
+    for (key, relationship) in resource.relationships.iter().flatten() {
+        let ids = identifiers(&relationship.data);
+
+        for id in ids.iter().filter(|id| document.resource(&id.id).is_none()) {
+            diagnostics.push(Diagnostic::UnresolvedLink(UnresolvedLink {
+                from: resource.id.clone(),
+                relationship: key.clone(),
+                to: id.id.clone(),
+            }));
+        }
+    }
+}
+
+/// Returns the destinations of links in Markdown docs that lead to pages of the documentation
+/// that are not rendered.
+///
+/// Only relative links to HTML pages are checked. Links to source pages are assumed to be valid.
+fn broken_links(
+    docs: &str,
+    page: &Path,
+    pages: &HashSet<PathBuf>,
+    sources: &[PathBuf],
+) -> Vec<String> {
+    let mut broken = vec![];
+
+    for event in Parser::new(docs) {
+        let destination = match event {
+            Event::Start(Tag::Link(destination, _)) => destination,
+            _ => continue,
+        };
+
+        let target = destination.split(&['#', '?'][..]).next().unwrap_or("");
+        if target.contains(':') || target.starts_with('/') || !target.ends_with(".html") {
+            continue;
+        }
+
+        match resolve(page, target) {
+            Some(ref path) if sources.iter().any(|src| path.starts_with(src)) => (),
+            Some(ref path) if pages.contains(path) => (),
+            _ => broken.push(destination.into_owned()),
+        }
+    }
+
+    broken
+}
+
+/// Resolves a relative link from a page to a path relative to the doc root.
+///
+/// Returns `None` if the link leads outside of the doc root.
+fn resolve(page: &Path, link: &str) -> Option<PathBuf> {
+    let mut path = page.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    for component in Path::new(link).components() {
+        match component {
+            Component::Normal(component) => path.push(component),
+            Component::ParentDir => {
+                if !path.pop() {
+                    return None;
+                }
+            }
+            Component::CurDir => (),
+            _ => return None,
+        }
+    }
+
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonapi::api::JsonApiDocument;
+
+    use report::UnresolvedLink;
+    use super::Diagnostic;
+
+    #[test]
+    fn validate() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": {
+                    "type": "crate",
+                    "id": "test_crate",
+                    "attributes": {},
+                    "relationships": {
+                        "functions": { "data": [{ "type": "function", "id": "test_crate::f" }] }
+                    }
+                },
+                "included": [
+                    { "type": "macro", "id": "test_crate::m", "attributes": { "docs": 1 } },
+                    { "type": "macro", "id": "test_crate::m", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        assert_eq!(
+            super::validate(&document),
+            [
+                Diagnostic::UnresolvedLink(UnresolvedLink {
+                    from: String::from("test_crate"),
+                    relationship: String::from("functions"),
+                    to: String::from("test_crate::f"),
+                }),
+                Diagnostic::UnsupportedType {
+                    id: String::from("test_crate::m"),
+                    ty: String::from("macro"),
+                },
+                Diagnostic::InvalidDocs { id: String::from("test_crate::m") },
+                Diagnostic::DuplicateId { id: String::from("test_crate::m") },
+                Diagnostic::UnsupportedType {
+                    id: String::from("test_crate::m"),
+                    ty: String::from("macro"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_id() {
+        let document = JsonApiDocument::from_str(
+            r#"{
+                "data": { "type": "crate", "id": "test_crate", "attributes": {} },
+                "included": [
+                    { "type": "struct", "id": "test_crate::../Foo\u0007", "attributes": {} }
+                ]
+            }"#,
+        ).unwrap();
+
+        let diagnostics = super::validate(&document);
+        assert_eq!(
+            diagnostics,
+            [Diagnostic::InvalidId { id: String::from("test_crate::../Foo\u{7}") }]
+        );
+        assert!(diagnostics[0].is_error());
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "ID `test_crate::../Foo\\u{7}` has a path segment that cannot be used as a file name"
+        );
+    }
+
+    #[test]
+    fn broken_links() {
+        let docs = "[Foo](struct.Foo.html) [Bar](struct.Bar.html#method.new) \
+                    [src](src/src/lib.rs.html#L1) [Rust](https://rust-lang.org/)";
+        let document = json!({
+            "data": { "type": "crate", "id": "test_crate", "attributes": { "docs": docs } },
+            "included": [{ "type": "struct", "id": "test_crate::Foo", "attributes": {} }]
+        });
+        let document = JsonApiDocument::from_str(&document.to_string()).unwrap();
+
+        assert_eq!(
+            super::validate(&document),
+            [Diagnostic::BrokenLink {
+                id: String::from("test_crate"),
+                link: String::from("struct.Bar.html#method.new"),
+            }]
+        );
+    }
+}
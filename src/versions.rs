@@ -0,0 +1,100 @@
+//! Documentation rendered for several versions of a crate, each in its own directory under a
+//! shared root.
+//!
+//! The versions are listed in `versions.json` at the shared root, which the version switcher in
+//! the header of every page reads.
+
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Component, Path};
+
+use serde_json;
+
+use errors::*;
+
+/// The name of the file that lists the versions, at the shared root.
+pub const VERSIONS: &str = "versions.json";
+
+/// Returns the versions listed under a shared root, newest first. A root without a list has no
+/// versions.
+pub fn versions<P: AsRef<Path>>(root: P) -> Result<Vec<String>> {
+    let path = root.as_ref().join(VERSIONS);
+    if !path.is_file() {
+        return Ok(vec![]);
+    }
+
+    let contents = fs::read(&path).chain_err(|| format!("could not read `{}`", path.display()))?;
+    serde_json::from_slice(&contents).chain_err(|| format!("`{}` is not a list", path.display()))
+}
+
+/// Adds a version to the list under a shared root, returning the versions, newest first.
+pub fn add_version<P: AsRef<Path>>(root: P, version: &str) -> Result<Vec<String>> {
+    let root = root.as_ref();
+    let mut versions = versions(root)?;
+    if !versions.iter().any(|v| v == version) {
+        versions.push(String::from(version));
+    }
+    versions.sort_by(|a, b| compare(b, a));
+
+    let path = root.join(VERSIONS);
+    let json = serde_json::to_string_pretty(&versions).expect("versions are serializable");
+    fs::write(&path, json).chain_err(|| format!("could not write `{}`", path.display()))?;
+    Ok(versions)
+}
+
+/// Returns whether a version can be used as the name of its directory under the shared root.
+pub fn is_valid(version: &str) -> bool {
+    let mut components = Path::new(version).components();
+    matches!(components.next(), Some(Component::Normal(_))) && components.next().is_none()
+}
+
+/// Compares two versions, such as `1.10.0` and `1.9.2`, by their numeric parts.
+///
+/// Names that are not numbered, such as `main`, are newer than every numbered version so that
+/// they are listed first.
+pub(crate) fn compare(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| -> Vec<::std::result::Result<u64, String>> {
+        version
+            .trim_start_matches('v')
+            .split(['.', '-', '+'])
+            .map(|part| part.parse().map_err(|_| String::from(part)))
+            .collect()
+    };
+    let (a_parts, b_parts) = (parts(a), parts(b));
+
+    match (a_parts[0].is_ok(), b_parts[0].is_ok()) {
+        (false, true) => Ordering::Greater,
+        (true, false) => Ordering::Less,
+        (false, false) => b.cmp(a),
+        (true, true) => {
+            let ordering = a_parts.iter().zip(&b_parts).map(|(a, b)| a.cmp(b)).find(|o| o.is_ne());
+
+            // Pre-releases such as `1.0.0-beta` have more parts, but are older than the release.
+            ordering.unwrap_or_else(|| b_parts.len().cmp(&a_parts.len()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn add_version() {
+        let root = env::temp_dir().join("rustdoc-static-versions");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+
+        for version in &["1.9.2", "1.10.0", "main", "1.10.0-beta.1", "1.9.2"] {
+            super::add_version(&root, version).unwrap();
+        }
+        assert_eq!(super::versions(&root).unwrap(), ["main", "1.10.0", "1.10.0-beta.1", "1.9.2"]);
+
+        assert!(super::is_valid("1.0.0"));
+        assert!(!super::is_valid("../1.0.0"));
+        assert!(!super::is_valid("1/0"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
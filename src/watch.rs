@@ -0,0 +1,81 @@
+//! Polling the inputs of a render for changes, so that the documentation can be rendered again
+//! as the document or templates are edited.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Returns when a file, or a directory or anything under it, was last modified.
+///
+/// The modification time of a directory changes when files are added to it or removed from it.
+pub fn last_modified<P: AsRef<Path>>(path: P) -> io::Result<SystemTime> {
+    let path = path.as_ref();
+    let metadata = fs::metadata(path)?;
+    let mut last = metadata.modified()?;
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            last = last.max(last_modified(entry?.path())?);
+        }
+    }
+
+    Ok(last)
+}
+
+/// Calls `on_change` whenever one of the paths is modified, checking every `interval`.
+///
+/// Only returns if the paths cannot be read when the watch starts. Paths that cannot be read
+/// later, such as while an editor replaces a file, are checked again on the next interval.
+pub fn watch<P, F>(paths: &[P], interval: Duration, mut on_change: F) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    F: FnMut(),
+{
+    let mut last = latest(paths)?;
+
+    loop {
+        thread::sleep(interval);
+
+        match latest(paths) {
+            Ok(modified) if modified != last => {
+                last = modified;
+                on_change();
+            }
+            Ok(_) => (),
+            Err(e) => debug!("could not check for changes: {}", e),
+        }
+    }
+}
+
+/// Returns when any of the paths was last modified.
+fn latest<P: AsRef<Path>>(paths: &[P]) -> io::Result<Option<SystemTime>> {
+    let mut last = None;
+    for path in paths {
+        last = last.max(Some(last_modified(path)?));
+    }
+    Ok(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs::{self, File};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn last_modified() {
+        let root = env::temp_dir().join("rustdoc-static-last-modified");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("partials")).unwrap();
+        assert!(super::last_modified(&root).unwrap() <= SystemTime::now());
+
+        let file = File::create(root.join("partials/item.hbs")).unwrap();
+        let modified = SystemTime::now() + Duration::from_secs(60);
+        file.set_modified(modified).unwrap();
+        assert_eq!(super::last_modified(&root).unwrap(), modified);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
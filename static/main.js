@@ -1,15 +1,310 @@
 (function() {
     "use strict";
 
-    document.querySelector('.sidebar-toggle').addEventListener('click', function toggleSidebar(e) {
+    var sidebarToggle = document.querySelector('.sidebar-toggle');
+    sidebarToggle.addEventListener('click', function toggleSidebar(e) {
         var body = document.querySelector('body');
         body.classList.add('sidebar-toggling');
-        body.classList.toggle('sidebar-hidden');
+        var hidden = body.classList.toggle('sidebar-hidden');
+        sidebarToggle.setAttribute('aria-expanded', String(!hidden));
         setTimeout(function() {
             body.classList.remove('sidebar-toggling');
         }, 500);
     });
 
+    var copyPathButton = document.querySelector('.copy-path');
+    if (copyPathButton) {
+        copyPathButton.addEventListener('click', function copyPath(e) {
+            copyToClipboard(copyPathButton.getAttribute('data-path'), copyPathButton);
+        });
+    }
+
+    Array.prototype.forEach.call(document.querySelectorAll('.copy-code'), function(button) {
+        button.addEventListener('click', function copyCode(e) {
+            var code = button.parentNode.querySelector('pre');
+            copyToClipboard(code.textContent, button);
+        });
+    });
+
+    Array.prototype.forEach.call(document.querySelectorAll('.notable-traits'), function(notable) {
+        var popover = notable.querySelector('.notable-traits-popover');
+        var toggle = notable.querySelector('.notable-traits-toggle');
+        toggle.addEventListener('click', function(e) {
+            popover.hidden = !popover.hidden;
+            toggle.setAttribute('aria-expanded', String(!popover.hidden));
+        });
+        closeOnEscape(popover, toggle);
+    });
+
+    // Closes a panel that a toggle opened when Escape is pressed in either of them, returning focus
+    // to the toggle so that keyboard users do not lose their place.
+    function closeOnEscape(panel, toggle) {
+        [panel, toggle].forEach(function(element) {
+            element.addEventListener('keydown', function closePanel(e) {
+                if (e.key === 'Escape' && !panel.hidden) {
+                    panel.hidden = true;
+                    toggle.setAttribute('aria-expanded', 'false');
+                    toggle.focus();
+                }
+            });
+        });
+    }
+
+    // Fragments shared between pages are loaded into their placeholders before the parts of the
+    // page that may be in them are set up. A fragment that cannot be loaded, such as when the page
+    // is opened from the filesystem, is left out, and the rest of the page is still set up.
+    var fragments = document.querySelectorAll('.fragment[data-fragment]');
+    var loaded = Array.prototype.map.call(fragments, function(placeholder) {
+        return loadFragment(placeholder).catch(function(error) {
+            console.warn('could not load ' + placeholder.getAttribute('data-fragment'), error);
+        });
+    });
+    Promise.all(loaded).then(function() {
+        initSidebar();
+        initSettings();
+
+        var searchInput = document.querySelector('.search-input');
+        if (searchInput && window.searchIndex) {
+            initSearch(searchInput, window.searchIndex);
+        }
+    });
+
+    // Lists the sections of the page in a sidebar that was loaded from a fragment, which is the
+    // same on every page.
+    function initSidebar() {
+        var sidebar = document.querySelector('.sidebar[data-title]');
+        if (!sidebar) {
+            return;
+        }
+
+        sidebar.querySelector('.sidebar-title').textContent = sidebar.getAttribute('data-title');
+        var list = sidebar.querySelector('.sidebar-sections');
+        var sections = document.querySelectorAll('section[aria-labelledby]');
+        Array.prototype.forEach.call(sections, function(section) {
+            var name = section.getAttribute('aria-labelledby');
+            var item = document.createElement('li');
+            var link = document.createElement('a');
+            link.href = '#' + name;
+            link.textContent = name;
+            item.appendChild(link);
+            list.appendChild(item);
+        });
+    }
+
+    function initSettings() {
+        var settingsToggle = document.querySelector('.settings-toggle');
+        var settingsPanel = document.querySelector('.settings-panel');
+        if (!settingsToggle || !settingsPanel) {
+            return;
+        }
+
+        settingsToggle.addEventListener('click', function toggleSettings(e) {
+            settingsPanel.hidden = !settingsPanel.hidden;
+            settingsToggle.setAttribute('aria-expanded', String(!settingsPanel.hidden));
+            if (!settingsPanel.hidden) {
+                settingsPanel.querySelector('[data-setting]').focus();
+            }
+        });
+        closeOnEscape(settingsPanel, settingsToggle);
+
+        var settings = window.rustdocSettings || {};
+        var inputs = settingsPanel.querySelectorAll('[data-setting]');
+        Array.prototype.forEach.call(inputs, function(input) {
+            var name = input.getAttribute('data-setting');
+            if (input.type === 'checkbox') {
+                input.checked = !!settings[name];
+            } else if (settings[name]) {
+                input.value = settings[name];
+            }
+
+            input.addEventListener('change', function saveSetting(e) {
+                settings[name] = input.type === 'checkbox' ? input.checked : input.value;
+                try {
+                    localStorage.setItem('rustdoc-static-settings', JSON.stringify(settings));
+                } catch (e) {
+                    // The setting will only apply to the current page.
+                }
+                window.rustdocApplySettings(settings);
+            });
+        });
+
+        if (settings.collapseSections) {
+            var sections = document.querySelectorAll('details.section');
+            Array.prototype.forEach.call(sections, function(section) {
+                section.open = false;
+            });
+        }
+    }
+
+    // Lists the items whose names or paths contain the query as it is typed. Items of the crate
+    // of the current page are ranked above those of other crates with an equally good match.
+    //
+    // The results can be browsed with the arrow keys, and are closed by Escape or by moving the
+    // focus out of the search. Pressing `/` or `s` anywhere else on the page focuses the search.
+    function initSearch(input, index) {
+        var search = input.parentNode;
+        var results = document.querySelector('.search-results');
+        var root = search.getAttribute('data-root');
+        var currentCrate = search.getAttribute('data-crate');
+        var resultLinks = function() {
+            return Array.prototype.slice.call(results.querySelectorAll('a'));
+        };
+
+        document.addEventListener('keydown', function focusSearch(e) {
+            var typing = /^(INPUT|SELECT|TEXTAREA)$/.test(e.target.tagName);
+            if ((e.key === '/' || e.key === 's') && !typing && !e.ctrlKey && !e.metaKey &&
+                    !e.altKey) {
+                e.preventDefault();
+                input.focus();
+            }
+        });
+
+        input.addEventListener('keydown', function browseResults(e) {
+            var links = resultLinks();
+            if (e.key === 'ArrowDown' && !results.hidden && links.length) {
+                e.preventDefault();
+                links[0].focus();
+            } else if (e.key === 'Enter' && !results.hidden && links.length) {
+                window.location.href = links[0].href;
+            } else if (e.key === 'Escape') {
+                results.hidden = true;
+            }
+        });
+
+        results.addEventListener('keydown', function browseResults(e) {
+            var links = resultLinks();
+            var current = links.indexOf(document.activeElement);
+            if (e.key === 'ArrowDown' || e.key === 'ArrowUp') {
+                e.preventDefault();
+                var next = current + (e.key === 'ArrowDown' ? 1 : -1);
+                if (next < 0) {
+                    input.focus();
+                } else if (next < links.length) {
+                    links[next].focus();
+                }
+            } else if (e.key === 'Escape') {
+                results.hidden = true;
+                input.focus();
+            }
+        });
+
+        // Clicking a result keeps the focus in the search, since some browsers do not focus links
+        // that are clicked, and the results would close before the click lands.
+        results.addEventListener('mousedown', function keepFocus(e) {
+            e.preventDefault();
+        });
+        search.addEventListener('focusout', function closeResults(e) {
+            if (!search.contains(e.relatedTarget)) {
+                results.hidden = true;
+            }
+        });
+        input.addEventListener('focus', function reopenResults(e) {
+            results.hidden = !input.value.trim();
+        });
+
+        input.addEventListener('input', function search(e) {
+            var query = input.value.trim().toLowerCase();
+            results.innerHTML = '';
+            results.hidden = !query;
+            if (!query) {
+                return;
+            }
+
+            var rank = function(item) {
+                var name = item.name.toLowerCase();
+                var match = name === query ? 0 : name.indexOf(query) === 0 ? 1 :
+                    name.indexOf(query) !== -1 ? 2 : 3;
+                return match * 2 + (item.crate === currentCrate ? 0 : 1);
+            };
+            var matches = index.filter(function(item) {
+                return item.path.toLowerCase().indexOf(query) !== -1;
+            }).map(function(item) {
+                return { item: item, rank: rank(item) };
+            }).sort(function(a, b) {
+                return a.rank - b.rank || a.item.path.length - b.item.path.length;
+            }).slice(0, 50);
+
+            matches.forEach(function(match) {
+                var item = match.item;
+                var result = document.createElement('li');
+                var link = document.createElement('a');
+                link.href = root + '/' + item.link;
+                link.title = item.summary || '';
+                link.textContent = item.path;
+                var kind = document.createElement('span');
+                kind.className = 'search-result-type';
+                kind.textContent = item.type;
+                var krate = document.createElement('span');
+                krate.className = 'search-result-crate';
+                krate.textContent = item.crate;
+                result.appendChild(kind);
+                result.appendChild(link);
+                result.appendChild(krate);
+                results.appendChild(result);
+            });
+            if (!matches.length) {
+                var empty = document.createElement('li');
+                empty.textContent = 'No results';
+                results.appendChild(empty);
+            }
+        });
+    }
+
+    var versionSwitcher = document.querySelector('.version-switcher');
+    if (versionSwitcher) {
+        initVersionSwitcher(versionSwitcher);
+    }
+
+    // Lists the versions of the documentation in the switcher, and opens the same page in the
+    // version that is picked, or the page of the crate if that version has no such page.
+    function initVersionSwitcher(switcher) {
+        var current = switcher.value;
+        fetch(switcher.getAttribute('data-versions')).then(function(response) {
+            return response.json();
+        }).then(function(versions) {
+            switcher.innerHTML = '';
+            versions.forEach(function(version) {
+                var option = document.createElement('option');
+                option.value = option.textContent = version;
+                option.selected = version === current;
+                switcher.appendChild(option);
+            });
+        });
+
+        switcher.addEventListener('change', function switchVersion(e) {
+            var root = switcher.getAttribute('data-root') + '/' + switcher.value + '/';
+            var page = root + switcher.getAttribute('data-page');
+            var fallback = root + switcher.getAttribute('data-fallback');
+
+            // Pages opened from the filesystem cannot be checked, so the same page is opened.
+            fetch(page, { method: 'HEAD' }).then(function(response) {
+                return response.ok ? page : fallback;
+            }, function() {
+                return page;
+            }).then(function(target) {
+                window.location.href = target;
+            });
+        });
+    }
+
+    // Expand every collapsed section when printing, restoring them afterwards.
+    var collapsedSections = [];
+    window.addEventListener('beforeprint', function expandSections(e) {
+        collapsedSections = Array.prototype.filter.call(
+            document.querySelectorAll('details.section'),
+            function(section) {
+                return !section.open;
+            });
+        collapsedSections.forEach(function(section) {
+            section.open = true;
+        });
+    });
+    window.addEventListener('afterprint', function restoreSections(e) {
+        collapsedSections.forEach(function(section) {
+            section.open = false;
+        });
+    });
+
     var lastWindowWidth = window.innerWidth;
     var throttled = false;
     window.addEventListener('resize', function resetSidebar(e) {
@@ -23,6 +318,8 @@
                 if (lastWindowWidth < 768) {
                     body.classList.add('sidebar-hidden');
                 }
+                var expanded = !body.classList.contains('sidebar-hidden');
+                sidebarToggle.setAttribute('aria-expanded', String(expanded));
             }
 
             setTimeout(function() {
@@ -30,4 +327,49 @@
             }, 250);
         }
     });
+
+    // Replaces a fragment placeholder with the contents of the fragment file. The other data
+    // attributes of the placeholder, which hold what differs between pages, are moved to the
+    // first element of the fragment.
+    function loadFragment(placeholder) {
+        return fetch(placeholder.getAttribute('data-fragment')).then(function(response) {
+            if (!response.ok) {
+                throw new Error(response.status + ' ' + response.statusText);
+            }
+            return response.text();
+        }).then(function(html) {
+            var template = document.createElement('template');
+            template.innerHTML = html.trim();
+            var first = template.content.firstElementChild;
+            Array.prototype.forEach.call(placeholder.attributes, function(attribute) {
+                var name = attribute.name;
+                if (first && name.indexOf('data-') === 0 && name !== 'data-fragment') {
+                    first.setAttribute(name, attribute.value);
+                }
+            });
+            placeholder.parentNode.replaceChild(template.content, placeholder);
+        });
+    }
+
+    // Copies text to the clipboard, briefly marking the button that triggered the copy.
+    function copyToClipboard(text, button) {
+        var done = function() {
+            button.classList.add('copied');
+            setTimeout(function() {
+                button.classList.remove('copied');
+            }, 1000);
+        };
+
+        if (navigator.clipboard) {
+            navigator.clipboard.writeText(text).then(done);
+        } else {
+            var textarea = document.createElement('textarea');
+            textarea.value = text;
+            document.body.appendChild(textarea);
+            textarea.select();
+            document.execCommand('copy');
+            document.body.removeChild(textarea);
+            done();
+        }
+    }
 })();
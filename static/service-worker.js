@@ -0,0 +1,61 @@
+// Caches the documentation so that it can be browsed offline once it has been visited. Pages are
+// fetched from the network while it is available, so that readers always see the latest render,
+// and from the cache otherwise.
+(function() {
+    "use strict";
+
+    // Versioned documentation has one worker per version, so caches are named after the scope of
+    // the worker as well as the version of the files, which the renderer sets.
+    var prefix = 'rustdoc-static:' + self.registration.scope + ':';
+    var cacheName = prefix + self.rustdocCacheVersion;
+
+    self.addEventListener('install', function(event) {
+        event.waitUntil(fetch('offline-manifest.json').then(function(response) {
+            return response.json();
+        }).then(function(manifest) {
+            return caches.open(cacheName).then(function(cache) {
+                return cache.addAll(manifest.files);
+            });
+        }).then(function() {
+            return self.skipWaiting();
+        }));
+    });
+
+    // Removes the caches of previous renders.
+    self.addEventListener('activate', function(event) {
+        event.waitUntil(caches.keys().then(function(names) {
+            return Promise.all(names.filter(function(name) {
+                return name.indexOf(prefix) === 0 && name !== cacheName;
+            }).map(function(name) {
+                return caches.delete(name);
+            }));
+        }).then(function() {
+            return self.clients.claim();
+        }));
+    });
+
+    self.addEventListener('fetch', function(event) {
+        var request = event.request;
+        if (request.method !== 'GET' || request.url.indexOf(self.registration.scope) !== 0) {
+            return;
+        }
+
+        event.respondWith(fetch(request).then(function(response) {
+            if (response.ok) {
+                var copy = response.clone();
+                caches.open(cacheName).then(function(cache) {
+                    cache.put(request, copy);
+                });
+            }
+            return response;
+        }).catch(function() {
+            // Directories are cached as their `index.html`.
+            var url = request.url.split('#')[0];
+            return caches.match(request).then(function(response) {
+                return response || (/\/$/.test(url) && caches.match(url + 'index.html'));
+            }).then(function(response) {
+                return response || Response.error();
+            });
+        }));
+    });
+})();
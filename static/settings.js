@@ -0,0 +1,56 @@
+// Applies the user's saved settings to the page. This script is loaded in the `<head>` so that the
+// settings take effect before the page is displayed.
+(function() {
+    "use strict";
+
+    var settings = {};
+    try {
+        settings = JSON.parse(localStorage.getItem('rustdoc-static-settings')) || {};
+    } catch (e) {
+        // Storage may be unavailable (e.g., for `file://` URLs in some browsers).
+    }
+
+    // The renderer may set a default theme for readers who have not picked one.
+    if (!settings.theme && window.rustdocDefaultTheme) {
+        settings.theme = window.rustdocDefaultTheme;
+    }
+
+    // Reflects the settings on the root element, where they are used by the stylesheet.
+    function applySettings(settings) {
+        var root = document.documentElement;
+        root.setAttribute('data-theme', settings.theme || 'light');
+        root.classList.toggle('wrap-lines', !!settings.wrapLines);
+        root.classList.toggle('collapse-sections', !!settings.collapseSections);
+    }
+
+    applySettings(settings);
+
+    // Configures the Google tag of the analytics snippet, if the page has one. The tag is
+    // configured here rather than by an inline script, which a Content Security Policy would block.
+    document.addEventListener('DOMContentLoaded', function() {
+        var tag = document.querySelector('script[data-gtag]');
+        if (!tag) {
+            return;
+        }
+        window.dataLayer = window.dataLayer || [];
+        window.gtag = function() {
+            window.dataLayer.push(arguments);
+        };
+        window.gtag('js', new Date());
+        window.gtag('config', tag.getAttribute('data-gtag'));
+    });
+
+    // Registers the service worker that caches the documentation for offline browsing, if the
+    // renderer wrote one. It is next to this script, at the doc root.
+    if (window.rustdocServiceWorker && 'serviceWorker' in navigator && document.currentScript) {
+        var worker = new URL(window.rustdocServiceWorker, document.currentScript.src);
+        window.addEventListener('load', function() {
+            navigator.serviceWorker.register(worker.href).catch(function() {
+                // Workers cannot be registered for `file://` URLs.
+            });
+        });
+    }
+
+    window.rustdocSettings = settings;
+    window.rustdocApplySettings = applySettings;
+})();